@@ -0,0 +1,135 @@
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+//how long throughput is measured for before the limit moves, and by how much it has to change to
+//count as better or worse rather than noise.
+const WINDOW: Duration = Duration::from_millis(100);
+const TOLERANCE: f64 = 0.05;
+const INITIAL_LIMIT: usize = 2;
+//without -j the pool gets room to go past the number of cpus, most of reading a file is waiting on
+//the storage.
+const POOL_PER_CPU: usize = 4;
+const MAX_POOL: usize = 64;
+
+//--adaptive: how many files are counted at once, tuned while the run goes. every WINDOW the bytes
+//counted per second are compared with the window before: the limit keeps moving the same way
+//while that helps, turns around when it hurts and stays put when it makes no difference. an nvme
+//drive ends up with most of the pool reading, a spinning disk or nfs mount with a few.
+pub struct Throttle {
+    max: usize,
+    state: Mutex<State>,
+    freed: Condvar,
+}
+
+struct State {
+    limit: usize,
+    active: usize,
+    growing: bool,
+    last_rate: Option<f64>,
+    window_start: Instant,
+    window_bytes: u128,
+    window_files: usize,
+}
+
+impl State {
+    fn adjust(&mut self, rate: f64, max: usize) {
+        if let Some(last_rate) = self.last_rate {
+            if rate < last_rate * (1.0 - TOLERANCE) {
+                self.growing = !self.growing;
+            } else if rate <= last_rate * (1.0 + TOLERANCE) {
+                self.last_rate = Some(rate);
+                return;
+            }
+        }
+        self.last_rate = Some(rate);
+        self.limit = if self.growing {
+            (self.limit + self.limit.div_ceil(2)).min(max)
+        } else {
+            (self.limit - self.limit.div_ceil(4)).max(1)
+        };
+    }
+}
+
+impl Throttle {
+    pub fn new(max: usize) -> Throttle {
+        Throttle {
+            max,
+            state: Mutex::new(State {
+                limit: INITIAL_LIMIT.min(max),
+                active: 0,
+                growing: true,
+                last_rate: None,
+                window_start: Instant::now(),
+                window_bytes: 0,
+                window_files: 0,
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    //waits for a free slot, the slot is given back with finish().
+    pub fn start(&self) {
+        let mut state = self.state.lock().unwrap();
+        while state.active >= state.limit {
+            state = self.freed.wait(state).unwrap();
+        }
+        state.active += 1;
+    }
+
+    //a window only closes once at least `limit` files went through it, so a handful of big files
+    //doesn't get read as the storage slowing down.
+    pub fn finish(&self, bytes: u128) {
+        let mut state = self.state.lock().unwrap();
+        state.active -= 1;
+        state.window_bytes += bytes;
+        state.window_files += 1;
+
+        let elapsed = state.window_start.elapsed();
+        if elapsed >= WINDOW && state.window_files >= state.limit {
+            let rate = state.window_bytes as f64 / elapsed.as_secs_f64();
+            state.adjust(rate, self.max);
+            state.window_start = Instant::now();
+            state.window_bytes = 0;
+            state.window_files = 0;
+        }
+        self.freed.notify_all();
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+}
+
+pub fn default_pool_size() -> usize {
+    let cpus = thread::available_parallelism().map_or(1, |cpus| cpus.get());
+    (cpus * POOL_PER_CPU).min(MAX_POOL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Throttle;
+
+    #[test]
+    fn climbs_while_it_helps_and_turns_around_when_it_hurts() {
+        let throttle = Throttle::new(16);
+        let mut state = throttle.state.lock().unwrap();
+
+        for rate in [100.0, 200.0, 300.0] {
+            state.adjust(rate, 16);
+        }
+        assert_eq!(state.limit, 8);
+
+        state.adjust(302.0, 16);
+        assert_eq!(state.limit, 8);
+
+        state.adjust(150.0, 16);
+        assert!(!state.growing);
+        assert_eq!(state.limit, 6);
+
+        for rate in [1e3, 2e3, 3e3, 4e3, 5e3, 6e3, 7e3] {
+            state.adjust(rate, 16);
+        }
+        assert_eq!(state.limit, 1);
+    }
+}
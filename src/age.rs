@@ -0,0 +1,125 @@
+use crate::diff::directory;
+use crate::{Scan, collect_files, dates, git};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::io::{Error, Result};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+//the buckets a line falls into by how long ago it was last changed, the last one is everything
+//older.
+const BUCKETS: [(&str, &str); 4] = [
+    ("< 1 month", "1m"),
+    ("< 3 months", "3m"),
+    ("< 1 year", "1y"),
+    ("< 3 years", "3y"),
+];
+const OLDER: &str = "older";
+
+//lines per bucket, the last slot for the older ones.
+type Ages = [u128; BUCKETS.len() + 1];
+
+//`lc age`: how fresh the lines there are now are, going by when git blame says each one last
+//changed. a directory that's almost all old lines is either done or forgotten.
+pub fn run(roots: &[PathBuf], scan: &Scan) -> Result<()> {
+    let mut files = Vec::new();
+    for root in roots {
+        let mut found = Vec::new();
+        collect_files(root, scan, &mut found)?;
+        files.extend(found.into_iter().map(|(path, _)| (root, path)));
+    }
+
+    //untracked files have no blame and are left out.
+    let now = SystemTime::now();
+    let blamed = files
+        .par_iter()
+        .filter_map(|(root, path)| {
+            let ages = ages(&git::blame(path).ok()?, now);
+            Some((directory(path.strip_prefix(root).unwrap_or(path)), ages))
+        })
+        .collect::<Vec<_>>();
+
+    let mut total = Ages::default();
+    let mut directories: BTreeMap<String, Ages> = BTreeMap::new();
+    for (dir, ages) in blamed {
+        let dir = directories.entry(dir).or_default();
+        for (bucket, lines) in ages.into_iter().enumerate() {
+            total[bucket] += lines;
+            dir[bucket] += lines;
+        }
+    }
+    if total.iter().sum::<u128>() == 0 {
+        return Err(Error::other(
+            "no blame to go by, are the files tracked in a git repository?",
+        ));
+    }
+
+    let mut header = format!("{:<40}{:>10}", "Directory", "Lines");
+    for (label, _) in BUCKETS {
+        header.push_str(&format!("{label:>12}"));
+    }
+    header.push_str(&format!("{OLDER:>12}"));
+    println!("{}", header.bold());
+    for (dir, ages) in &directories {
+        print_row(dir, ages);
+    }
+    print_row("Total", &total);
+    Ok(())
+}
+
+//the lines in each bucket as a share of the row, so directories of any size compare.
+fn print_row(name: &str, ages: &Ages) {
+    let lines = ages.iter().sum::<u128>();
+    let mut row = format!("{name:<40}{lines:>10}");
+    for bucket in ages {
+        row.push_str(&format!(
+            "{:>12}",
+            format!("{:.1}%", *bucket as f64 / lines.max(1) as f64 * 100.)
+        ));
+    }
+    println!("{row}");
+}
+
+//the lines of one file's `--line-porcelain` output per bucket, by their committer time. lines
+//that aren't committed yet are as new as it gets.
+fn ages(porcelain: &[u8], now: SystemTime) -> Ages {
+    let limits = BUCKETS.map(|(_, age)| dates::parse_age(age).unwrap());
+    let mut ages = Ages::default();
+    for line in porcelain.split(|&byte| byte == b'\n') {
+        let Some(time) = line.strip_prefix(b"committer-time ") else {
+            continue;
+        };
+        let Some(time) = String::from_utf8_lossy(time).parse::<u64>().ok() else {
+            continue;
+        };
+        let age = now
+            .duration_since(UNIX_EPOCH + Duration::from_secs(time))
+            .unwrap_or_default();
+        let bucket = limits
+            .iter()
+            .position(|&limit| age < limit)
+            .unwrap_or(BUCKETS.len());
+        ages[bucket] += 1;
+    }
+    ages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ages;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn buckets_lines_by_age() {
+        let day = 24 * 60 * 60;
+        let now = UNIX_EPOCH + Duration::from_secs(10_000 * day);
+        let porcelain = format!(
+            "3b18e51 1 1 3\ncommitter-time {}\n\tfn main() {{\n3b18e51 2 2\ncommitter-time {}\n\t}}\n9f2c4d0 3 3 1\ncommitter-time {}\n\t// committer-time 0\n",
+            (10_000 - 3) * day,
+            (10_000 - 200) * day,
+            (10_000 - 5_000) * day
+        );
+        assert_eq!(ages(porcelain.as_bytes(), now), [1, 0, 1, 0, 1]);
+    }
+}
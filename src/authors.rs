@@ -0,0 +1,128 @@
+use crate::diff::directory;
+use crate::{Scan, collect_files, git};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Error, Result};
+use std::path::PathBuf;
+
+//how many authors the overall leaderboard lists.
+const AUTHORS: usize = 20;
+
+//lines per author.
+type Owners = HashMap<String, u128>;
+
+//`lc authors`: who wrote the lines that are there now, going by git blame. a directory where one
+//person wrote nearly everything is one that only they know.
+pub fn run(roots: &[PathBuf], scan: &Scan) -> Result<()> {
+    let mut files = Vec::new();
+    for root in roots {
+        let mut found = Vec::new();
+        collect_files(root, scan, &mut found)?;
+        files.extend(found.into_iter().map(|(path, _)| (root, path)));
+    }
+
+    //untracked files have no blame and are left out.
+    let blamed = files
+        .par_iter()
+        .filter_map(|(root, path)| {
+            let owners = owners(&git::blame(path).ok()?);
+            Some((directory(path.strip_prefix(root).unwrap_or(path)), owners))
+        })
+        .collect::<Vec<_>>();
+
+    let mut total = Owners::new();
+    let mut directories: BTreeMap<String, Owners> = BTreeMap::new();
+    for (dir, owners) in blamed {
+        let owned = directories.entry(dir).or_default();
+        for (author, lines) in owners {
+            *total.entry(author.clone()).or_default() += lines;
+            *owned.entry(author).or_default() += lines;
+        }
+    }
+    if total.is_empty() {
+        return Err(Error::other(
+            "no blame to go by, are the files tracked in a git repository?",
+        ));
+    }
+    print_leaderboard(&total);
+    println!();
+    print_directories(&directories);
+    Ok(())
+}
+
+//the lines each author has in one file's `--line-porcelain` output, which repeats the full header
+//for every line.
+fn owners(porcelain: &[u8]) -> Owners {
+    let mut owners = Owners::new();
+    for line in porcelain.split(|&byte| byte == b'\n') {
+        if let Some(author) = line.strip_prefix(b"author ") {
+            *owners
+                .entry(String::from_utf8_lossy(author).into_owned())
+                .or_default() += 1;
+        }
+    }
+    owners
+}
+
+//the most lines first, ties by name.
+fn ranked(owners: &Owners) -> Vec<(&str, u128)> {
+    let mut ranked = owners
+        .iter()
+        .map(|(author, &lines)| (author.as_str(), lines))
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    ranked
+}
+
+fn share(lines: u128, of: u128) -> String {
+    format!("{:.1}%", lines as f64 / of as f64 * 100.)
+}
+
+fn print_leaderboard(total: &Owners) {
+    let lines = total.values().sum::<u128>();
+    println!(
+        "{}",
+        format!("{:<32}{:>12}{:>8}", "Author", "Lines", "Share").bold()
+    );
+    let ranked = ranked(total);
+    for (author, owned) in ranked.iter().take(AUTHORS) {
+        println!("{author:<32}{owned:>12}{:>8}", share(*owned, lines));
+    }
+    if ranked.len() > AUTHORS {
+        println!("...and {} more authors", ranked.len() - AUTHORS);
+    }
+    println!("{:<32}{lines:>12}", "Total");
+}
+
+fn print_directories(directories: &BTreeMap<String, Owners>) {
+    println!(
+        "{}",
+        format!(
+            "{:<40}{:>10}{:>9}  {:<24}{:>8}",
+            "Directory", "Lines", "Authors", "Top author", "Share"
+        )
+        .bold()
+    );
+    for (dir, owners) in directories {
+        let lines = owners.values().sum::<u128>();
+        let (top, owned) = ranked(owners)[0];
+        println!(
+            "{dir:<40}{lines:>10}{:>9}  {top:<24}{:>8}",
+            owners.len(),
+            share(owned, lines)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{owners, ranked};
+
+    #[test]
+    fn counts_lines_per_author() {
+        let porcelain = b"3b18e51 1 1 2\nauthor Ada\nauthor-mail <ada@example.com>\nsummary init\n\tfn main() {\n3b18e51 2 2\nauthor Ada\n\t}\n9f2c4d0 3 3 1\nauthor Linus\nauthor-time 0\n\t// author Ada\n";
+        let owners = owners(porcelain);
+        assert_eq!(ranked(&owners), [("Ada", 2), ("Linus", 1)]);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    Counts, Scan, format_byte_count, linecount_async, linecount_display, linecount_serial,
+};
+use colored::Colorize;
+use std::fs;
+use std::io::{self, Result};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const MODES: [&str; 3] = ["serial", "threaded", "display"];
+
+struct ModeResult {
+    mode: &'static str,
+    times: Vec<Duration>,
+    peak_rss: Option<u128>,
+    counts: Counts,
+}
+
+//`lc bench <path>`: every counting mode over the same tree, `iterations` times each, with the
+//display mode rendering into a sink so only building the tree gets timed. peak rss is reset
+//between modes (linux only), so each row shows what that mode needed on its own.
+pub fn run(root: &Path, scan: &Arc<Scan>, iterations: usize) -> Result<()> {
+    let mut results = Vec::new();
+    for mode in MODES {
+        reset_peak_rss();
+        let mut times = Vec::with_capacity(iterations);
+        let mut counts = Counts::default();
+
+        for _ in 0..iterations {
+            scan.reset();
+            let start_time = Instant::now();
+            counts = match mode {
+                "serial" => linecount_serial(root, scan)?,
+                "threaded" => linecount_async(Some(root.to_path_buf()), Arc::clone(scan))?,
+                _ => linecount_display(root, scan, &mut io::sink())?,
+            };
+            times.push(start_time.elapsed());
+        }
+
+        results.push(ModeResult {
+            mode,
+            times,
+            peak_rss: peak_rss(),
+            counts,
+        });
+    }
+
+    print_table(&results, iterations);
+    Ok(())
+}
+
+fn print_table(results: &[ModeResult], iterations: usize) {
+    println!(
+        "{:<10}{:>14}{:>14}{:>14}{:>10}",
+        "mode".bold(),
+        "mean".bold(),
+        "median".bold(),
+        "peak rss".bold(),
+        "speedup".bold()
+    );
+
+    let baseline = mean(&results[0].times);
+    for result in results {
+        let mean_time = mean(&result.times);
+        let peak_rss = result
+            .peak_rss
+            .map(format_byte_count)
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<10}{:>14}{:>14}{:>14}{:>9.2}x",
+            result.mode,
+            format!("{mean_time:.2?}"),
+            format!("{:.2?}", median(&result.times)),
+            peak_rss,
+            baseline.as_secs_f64() / mean_time.as_secs_f64().max(f64::EPSILON),
+        );
+    }
+    println!("{}", format!("{iterations} runs per mode").dimmed());
+
+    //every mode walks the same tree, so any difference here is a bug rather than noise.
+    if results
+        .iter()
+        .any(|result| result.counts != results[0].counts)
+    {
+        for result in results {
+            eprintln!(
+                "{} {} counted {}L, {}B",
+                "mismatch".red().bold(),
+                result.mode,
+                result.counts.lines,
+                result.counts.bytes
+            );
+        }
+    }
+}
+
+fn mean(times: &[Duration]) -> Duration {
+    times.iter().sum::<Duration>() / times.len().max(1) as u32
+}
+
+fn median(times: &[Duration]) -> Duration {
+    let mut sorted = times.to_vec();
+    sorted.sort();
+    match sorted.len() {
+        0 => Duration::ZERO,
+        len if len % 2 == 0 => (sorted[len / 2 - 1] + sorted[len / 2]) / 2,
+        len => sorted[len / 2],
+    }
+}
+
+//writing 5 to clear_refs resets the high water mark VmHWM reports.
+fn reset_peak_rss() {
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
+
+fn peak_rss() -> Option<u128> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<u128>().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mean, median};
+    use std::time::Duration;
+
+    #[test]
+    fn mean_and_median() {
+        let ms = |n| Duration::from_millis(n);
+        assert_eq!(mean(&[ms(1), ms(2), ms(6)]), ms(3));
+        assert_eq!(median(&[ms(6), ms(1), ms(2)]), ms(2));
+        assert_eq!(median(&[ms(4), ms(1), ms(2), ms(6)]), ms(3));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::diff::{self, Snapshot};
+use crate::{Counts, Scan, format_byte_count, git};
+use colored::Colorize;
+use std::collections::{HashMap, HashSet};
+use std::io::Result;
+
+//how many languages the comparison lists, the ones with the most lines on any ref.
+const LANGUAGES: usize = 15;
+
+//`lc branches main feature-x ...`: each ref counted from the object database, side by side. the
+//refs mostly share their files, so a blob that's on several of them is only read and counted once.
+pub fn run(refs: &[&str], scan: &Scan) -> Result<()> {
+    let trees = refs
+        .iter()
+        .map(|rev| git::tree(rev))
+        .collect::<Result<Vec<_>>>()?;
+    let unique = trees
+        .iter()
+        .flatten()
+        .filter(|blob| scan.filter.includes_path(&blob.path))
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let stats = diff::count_each(&unique, scan)?;
+    let counted = unique
+        .into_iter()
+        .zip(stats)
+        .map(|(blob, file_stat)| (blob, file_stat.counts))
+        .collect::<HashMap<_, _>>();
+
+    let snapshots = trees
+        .iter()
+        .map(|tree| {
+            tree.iter()
+                .filter_map(|blob| Some((blob.path.clone(), *counted.get(blob)?)))
+                .collect::<Snapshot>()
+        })
+        .collect::<Vec<_>>();
+    print_table(refs, &snapshots);
+    Ok(())
+}
+
+//the lines per language on each side, the languages with the most lines on any side first.
+fn languages(snapshots: &[Snapshot]) -> Vec<(String, Vec<u128>)> {
+    let mut languages: HashMap<String, Vec<u128>> = HashMap::new();
+    for (side, snapshot) in snapshots.iter().enumerate() {
+        for (path, counts) in snapshot {
+            languages
+                .entry(diff::language(path))
+                .or_insert_with(|| vec![0; snapshots.len()])[side] += counts.lines;
+        }
+    }
+    let mut languages = languages.into_iter().collect::<Vec<_>>();
+    let most = |lines: &[u128]| lines.iter().max().copied().unwrap_or_default();
+    languages.sort_by(|a, b| most(&b.1).cmp(&most(&a.1)).then(a.0.cmp(&b.0)));
+    languages
+}
+
+fn print_table(refs: &[&str], snapshots: &[Snapshot]) {
+    let width = refs
+        .iter()
+        .map(|rev| rev.len())
+        .max()
+        .unwrap_or_default()
+        .max(12)
+        + 2;
+    let row = |name: &str, cells: Vec<String>| {
+        let mut row = format!("{name:<20}");
+        for cell in cells {
+            row.push_str(&format!("{cell:>width$}"));
+        }
+        row
+    };
+    println!(
+        "{}",
+        row("", refs.iter().map(|rev| rev.to_string()).collect()).bold()
+    );
+    let totals = snapshots
+        .iter()
+        .map(|snapshot| {
+            let total = snapshot
+                .values()
+                .fold(Counts::default(), |mut total, counts| {
+                    total += *counts;
+                    total
+                });
+            (snapshot.len(), total)
+        })
+        .collect::<Vec<_>>();
+    let cells = |cell: &dyn Fn(&(usize, Counts)) -> String| totals.iter().map(cell).collect();
+    println!("{}", row("Files", cells(&|(files, _)| files.to_string())));
+    println!(
+        "{}",
+        row("Lines", cells(&|(_, total)| total.lines.to_string()))
+    );
+    println!(
+        "{}",
+        row("Bytes", cells(&|(_, total)| format_byte_count(total.bytes)))
+    );
+
+    println!();
+    println!("{}", "Lines by language".bold());
+    let languages = languages(snapshots);
+    for (language, lines) in languages.iter().take(LANGUAGES) {
+        println!(
+            "{}",
+            row(language, lines.iter().map(u128::to_string).collect())
+        );
+    }
+    if languages.len() > LANGUAGES {
+        println!("...and {} more languages", languages.len() - LANGUAGES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::languages;
+    use crate::Counts;
+    use crate::diff::Snapshot;
+    use std::path::PathBuf;
+
+    #[test]
+    fn lines_per_language_side_by_side() {
+        let lines = |lines| Counts {
+            lines,
+            ..Counts::default()
+        };
+        let main = Snapshot::from([
+            (PathBuf::from("src/main.rs"), lines(100)),
+            (PathBuf::from("README.md"), lines(10)),
+        ]);
+        let feature = Snapshot::from([
+            (PathBuf::from("src/main.rs"), lines(120)),
+            (PathBuf::from("src/lib.rs"), lines(30)),
+        ]);
+        let languages = languages(&[main, feature]);
+        assert_eq!(languages[0], ("Rust".to_string(), vec![100, 150]));
+        assert_eq!(languages[1], ("Markdown".to_string(), vec![10, 0]));
+    }
+}
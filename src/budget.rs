@@ -0,0 +1,101 @@
+use std::sync::{Condvar, Mutex, OnceLock};
+
+//--max-mem, unset means file buffers aren't limited at all.
+static MEMORY: OnceLock<Budget> = OnceLock::new();
+
+//a fixed amount of something (open files, bytes of buffers) shared by every worker. workers that
+//would go over it wait for someone to give theirs back instead of failing.
+pub struct Budget {
+    limit: usize,
+    used: Mutex<usize>,
+    freed: Condvar,
+}
+
+impl Budget {
+    pub fn new(limit: usize) -> Budget {
+        Budget {
+            limit,
+            used: Mutex::new(0),
+            freed: Condvar::new(),
+        }
+    }
+
+    //all n at once or nothing, so two workers each holding half of what they need can't wait on
+    //each other forever. asking for more than the whole budget waits until it's all free.
+    pub fn acquire(&'static self, n: usize) -> Permit {
+        let n = n.min(self.limit);
+        let mut used = self.used.lock().unwrap();
+        while *used + n > self.limit {
+            used = self.freed.wait(used).unwrap();
+        }
+        *used += n;
+        Permit { budget: self, n }
+    }
+}
+
+//gives its share of the budget back when dropped.
+pub struct Permit {
+    budget: &'static Budget,
+    n: usize,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        *self.budget.used.lock().unwrap() -= self.n;
+        self.budget.freed.notify_all();
+    }
+}
+
+//what --max-mem allows, if it was given.
+pub fn memory_limit() -> Option<usize> {
+    Some(MEMORY.get()?.limit)
+}
+
+pub fn limit_memory(bytes: usize) {
+    let _ = MEMORY.set(Budget::new(bytes));
+}
+
+//room for a buffer of `bytes`, held until the permit is dropped. buffers are always taken after
+//the file they're for has been opened, never the other way around, so nobody holding memory is
+//ever waiting on a file descriptor.
+pub fn reserve_memory(bytes: usize) -> Option<Permit> {
+    Some(MEMORY.get()?.acquire(bytes))
+}
+
+//"512M", "2G", "64k" or a plain number of bytes.
+pub fn parse_size(spec: &str) -> Result<usize, String> {
+    let (number, unit) = match spec.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => spec.split_at(idx),
+        None => (spec, ""),
+    };
+    let multiplier = match unit.to_ascii_lowercase().trim_end_matches('b') {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        _ => return Err(format!("'{spec}' is not a size like 512M or 2G")),
+    };
+
+    match number.parse::<usize>() {
+        Ok(number) if number > 0 => number
+            .checked_mul(multiplier)
+            .ok_or_else(|| format!("'{spec}' is too big")),
+        _ => Err(format!("'{spec}' is not a size like 512M or 2G")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_size;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("512M"), Ok(512 << 20));
+        assert_eq!(parse_size("2g"), Ok(2 << 30));
+        assert_eq!(parse_size("64KB"), Ok(64 << 10));
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("5T").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::diff;
+use crate::user_languages::strip_comment;
+use crate::{Counts, Scan, collect_files, count_file, git};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+
+//next to the code it keeps in check, at the top of the repository.
+pub const BUDGETS_FILE: &str = "lc-budgets.toml";
+
+//how big a directory is allowed to get, in lines, and how much it may grow past what it was in
+//the base revision, in percent:
+//
+//  [src/legacy]
+//  max_lines = 20_000
+//  max_growth = "5%"
+#[derive(Debug, Default, PartialEq)]
+struct LineBudget {
+    dir: String,
+    max_lines: Option<u128>,
+    max_growth: Option<f64>,
+}
+
+fn parse(text: &str) -> std::result::Result<Vec<LineBudget>, String> {
+    let mut budgets: Vec<LineBudget> = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(dir) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            let dir = dir.trim().trim_matches(['"', '\'']).trim_end_matches('/');
+            if dir.is_empty() {
+                return Err(format!("line {line_no}: a budget needs a directory"));
+            }
+            budgets.push(LineBudget {
+                dir: dir.to_string(),
+                ..LineBudget::default()
+            });
+            continue;
+        }
+
+        let Some(budget) = budgets.last_mut() else {
+            return Err(format!("line {line_no}: expected a [directory] first"));
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {line_no}: expected key = value"));
+        };
+        let value = value.trim().trim_matches(['"', '\'']).replace('_', "");
+        match key.trim() {
+            "max_lines" => {
+                budget.max_lines = Some(value.parse().map_err(|_| {
+                    format!("line {line_no}: max_lines should be a number of lines")
+                })?)
+            }
+            "max_growth" => {
+                budget.max_growth = Some(value.trim_end_matches('%').parse().map_err(|_| {
+                    format!("line {line_no}: max_growth should be a percentage like \"5%\"")
+                })?)
+            }
+            key => return Err(format!("line {line_no}: unknown key '{key}'")),
+        }
+    }
+    Ok(budgets)
+}
+
+//`lc check`: every budget in lc-budgets.toml against the working tree, growth against `base`.
+//false when one was exceeded. the file and the directories in it are at the top of the repository,
+//the same as the paths in a git tree, wherever lc check is run from.
+pub fn check(base: &str, scan: &Scan) -> Result<bool> {
+    let top = git::toplevel()?;
+    let text = fs::read_to_string(top.join(BUDGETS_FILE))
+        .map_err(|err| Error::new(err.kind(), format!("can't read {BUDGETS_FILE}: {err}")))?;
+    let budgets = parse(&text)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{BUDGETS_FILE}: {err}")))?;
+    //the base is only read when a budget limits growth.
+    let before = match budgets.iter().any(|budget| budget.max_growth.is_some()) {
+        true => Some(diff::count_blobs(git::tree(base)?, scan)?),
+        false => None,
+    };
+
+    let mut exceeded = Vec::new();
+    for budget in &budgets {
+        //"." is the whole repository.
+        let dir = Path::new(&budget.dir)
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect::<PathBuf>();
+        let lines = count_lines(&top.join(&dir), &budget.dir, scan)?;
+        if let Some(max) = budget.max_lines {
+            let over = lines > max;
+            report(&budget.dir, format!("{lines} lines, budget {max}"), over);
+            if over {
+                exceeded.push(format!("{} is {} lines over", budget.dir, lines - max));
+            }
+        }
+        if let (Some(max), Some(before)) = (budget.max_growth, &before) {
+            let was = before
+                .iter()
+                .filter(|(path, _)| path.starts_with(&dir))
+                .map(|(_, counts)| counts.lines)
+                .sum::<u128>();
+            let growth = match was {
+                0 => 0.,
+                was => (lines as f64 - was as f64) / was as f64 * 100.,
+            };
+            let over = growth > max;
+            report(
+                &budget.dir,
+                format!("{growth:+.1}% since {base} ({was} -> {lines}), budget {max}%"),
+                over,
+            );
+            if over {
+                exceeded.push(format!(
+                    "{} grew {growth:.1}%, {:.1} points over",
+                    budget.dir,
+                    growth - max
+                ));
+            }
+        }
+    }
+
+    if exceeded.is_empty() {
+        println!("{}", format!("all {} budgets met", budgets.len()).green());
+        return Ok(true);
+    }
+    eprintln!(
+        "{} {} of {} budgets exceeded:",
+        "error:".red().bold(),
+        exceeded.len(),
+        budgets.len()
+    );
+    for line in &exceeded {
+        eprintln!("  {line}");
+    }
+    Ok(false)
+}
+
+fn report(dir: &str, detail: String, over: bool) {
+    let status = match over {
+        true => "over".red().bold(),
+        false => "ok".green(),
+    };
+    println!("{status:<4}  {dir:<32}  {detail}");
+}
+
+fn count_lines(dir: &Path, name: &str, scan: &Scan) -> Result<u128> {
+    let mut files = Vec::new();
+    collect_files(dir, scan, &mut files)
+        .map_err(|err| Error::new(err.kind(), format!("{name}: {err}")))?;
+    Ok(files
+        .par_iter()
+        .filter_map(|(path, _)| count_file(path, scan).ok())
+        .map(|file_stat| file_stat.counts)
+        .reduce(Counts::default, |mut total, counts| {
+            total += counts;
+            total
+        })
+        .lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineBudget, parse};
+
+    #[test]
+    fn parses_budgets() {
+        let budgets = parse(
+            "# legacy code only shrinks\n[src/legacy/]\nmax_lines = 20_000\nmax_growth = \"5%\"\n\n[\"tools\"]\nmax_growth = 2.5 # percent\n",
+        )
+        .unwrap();
+        assert_eq!(
+            budgets,
+            [
+                LineBudget {
+                    dir: "src/legacy".into(),
+                    max_lines: Some(20_000),
+                    max_growth: Some(5.),
+                },
+                LineBudget {
+                    dir: "tools".into(),
+                    max_lines: None,
+                    max_growth: Some(2.5),
+                },
+            ]
+        );
+        assert!(parse("max_lines = 1\n").is_err());
+        assert!(parse("[src]\nmax_lines = lots\n").is_err());
+    }
+}
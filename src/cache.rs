@@ -0,0 +1,285 @@
+use crate::Counts;
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+const HEADER: &str = "lc-cache v19";
+//numbers per entry: size, modification time and the counts, the path comes after them.
+const FIELDS: usize = COUNTS + 2;
+//how many counts a file has.
+pub const COUNTS: usize = 37;
+
+//per-file counts from the last run, keyed by path. an entry is only trusted while the file still
+//has the size and modification time it had when it was counted. entries are written back only
+//for files seen during this run, so deleted files fall out of the cache on their own.
+pub struct Cache {
+    path: PathBuf,
+    settings: String,
+    previous: HashMap<PathBuf, Entry>,
+    current: Mutex<HashMap<PathBuf, Entry>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    size: u64,
+    modified: u128,
+    counts: Counts,
+}
+
+impl Cache {
+    //settings are whatever changes what a file's counts hold, like "statements=1 breakdown=0". a
+    //missing or unreadable cache, or one written with different settings, just means everything
+    //gets counted again.
+    pub fn load(path: &Path, settings: String) -> Cache {
+        let previous = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| {
+                let mut lines = content.lines();
+                if lines.next()? != format!("{HEADER} {settings}") {
+                    return None;
+                }
+                Some(lines.filter_map(parse_entry).collect())
+            })
+            .unwrap_or_default();
+
+        Cache {
+            path: path.to_path_buf(),
+            settings,
+            previous,
+            current: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, path: &Path, metadata: &Metadata) -> Option<Counts> {
+        let entry = self.previous.get(path)?;
+        (entry.size == metadata.len() && Some(entry.modified) == modified(metadata))
+            .then_some(entry.counts)
+    }
+
+    pub fn insert(&self, path: &Path, metadata: &Metadata, counts: Counts) {
+        if let Some(modified) = modified(metadata) {
+            self.current.lock().unwrap().insert(
+                path.to_path_buf(),
+                Entry {
+                    size: metadata.len(),
+                    modified,
+                    counts,
+                },
+            );
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut out = format!("{HEADER} {}", self.settings);
+        for (path, entry) in self.current.lock().unwrap().iter() {
+            //the format is one entry per line with the path last, a path with a newline in it
+            //can't be stored and just gets counted every time.
+            let Some(path) = path.to_str().filter(|path| !path.contains('\n')) else {
+                continue;
+            };
+            out.push('\n');
+            for field in [u128::from(entry.size), entry.modified]
+                .into_iter()
+                .chain(encode(&entry.counts))
+            {
+                out.push_str(&format!("{field}\t"));
+            }
+            out.push_str(path);
+        }
+        fs::write(&self.path, out)
+    }
+}
+fn modified(metadata: &Metadata) -> Option<u128> {
+    let modified = metadata.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+fn parse_entry(line: &str) -> Option<(PathBuf, Entry)> {
+    let mut fields = line.splitn(FIELDS + 1, '\t');
+    let mut number = || fields.next()?.parse::<u128>().ok();
+    let (size, modified) = (number()? as u64, number()?);
+    let counts = decode(&mut number)?;
+    let path = PathBuf::from(fields.next()?);
+
+    Some((
+        path,
+        Entry {
+            size,
+            modified,
+            counts,
+        },
+    ))
+}
+
+//what each count is called in a json report, in the same order.
+pub const NAMES: [&str; COUNTS] = [
+    "lines",
+    "bytes",
+    "statements",
+    "logical",
+    "code",
+    "comments",
+    "docs",
+    "blanks",
+    "text",
+    "longest_line",
+    "line_chars",
+    "long_lines",
+    "tab_indented",
+    "space_indented",
+    "mixed_indent_files",
+    "lf_endings",
+    "crlf_endings",
+    "cr_endings",
+    "mixed_ending_files",
+    "trailing_whitespace",
+    "words",
+    "chars",
+    "graphemes",
+    "tokens",
+    "todos",
+    "whitespace_files",
+    "duplicate_files",
+    "utf16_files",
+    "latin1_files",
+    "shift_jis_files",
+    "bom_files",
+    "lockfile_lines",
+    "lockfile_bytes",
+    "binary_files",
+    "binary_bytes",
+    "lfs_pointers",
+    "lfs_bytes",
+];
+
+//every count of a file, in the order they're stored. snapshots store them the same way.
+pub fn encode(counts: &Counts) -> [u128; COUNTS] {
+    [
+        counts.lines,
+        counts.bytes,
+        counts.statements,
+        counts.logical,
+        counts.code,
+        counts.comments,
+        counts.docs,
+        counts.blanks,
+        counts.text,
+        counts.longest_line,
+        counts.line_chars,
+        counts.long_lines,
+        counts.tab_indented,
+        counts.space_indented,
+        counts.mixed_indent_files,
+        counts.lf_endings,
+        counts.crlf_endings,
+        counts.cr_endings,
+        counts.mixed_ending_files,
+        counts.trailing_whitespace,
+        counts.words,
+        counts.chars,
+        counts.graphemes,
+        counts.tokens,
+        counts.todos,
+        counts.whitespace_files,
+        counts.duplicate_files,
+        counts.utf16_files,
+        counts.latin1_files,
+        counts.shift_jis_files,
+        counts.bom_files,
+        counts.lockfile_lines,
+        counts.lockfile_bytes,
+        counts.binary_files,
+        counts.binary_bytes,
+        counts.lfs_pointers,
+        counts.lfs_bytes,
+    ]
+}
+
+pub fn decode(number: &mut impl FnMut() -> Option<u128>) -> Option<Counts> {
+    Some(Counts {
+        lines: number()?,
+        bytes: number()?,
+        statements: number()?,
+        logical: number()?,
+        code: number()?,
+        comments: number()?,
+        docs: number()?,
+        blanks: number()?,
+        text: number()?,
+        longest_line: number()?,
+        line_chars: number()?,
+        long_lines: number()?,
+        tab_indented: number()?,
+        space_indented: number()?,
+        mixed_indent_files: number()?,
+        lf_endings: number()?,
+        crlf_endings: number()?,
+        cr_endings: number()?,
+        mixed_ending_files: number()?,
+        trailing_whitespace: number()?,
+        words: number()?,
+        chars: number()?,
+        graphemes: number()?,
+        tokens: number()?,
+        todos: number()?,
+        whitespace_files: number()?,
+        duplicate_files: number()?,
+        utf16_files: number()?,
+        latin1_files: number()?,
+        shift_jis_files: number()?,
+        bom_files: number()?,
+        lockfile_lines: number()?,
+        lockfile_bytes: number()?,
+        binary_files: number()?,
+        binary_bytes: number()?,
+        lfs_pointers: number()?,
+        lfs_bytes: number()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cache;
+    use crate::Counts;
+    use std::fs;
+
+    #[test]
+    fn round_trips_and_invalidates() {
+        let dir = std::env::temp_dir().join(format!("lc-cache-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (cache_path, file) = (dir.join("cache"), dir.join("file.rs"));
+        fs::write(&file, "fn main() {}\n").unwrap();
+        let metadata = fs::metadata(&file).unwrap();
+        let counts = Counts {
+            lines: 1,
+            bytes: 13,
+            ..Counts::default()
+        };
+
+        let cache = Cache::load(&cache_path, "statements=0".into());
+        assert_eq!(cache.get(&file, &metadata), None);
+        cache.insert(&file, &metadata, counts);
+        cache.save().unwrap();
+
+        assert_eq!(
+            Cache::load(&cache_path, "statements=0".into()).get(&file, &metadata),
+            Some(counts)
+        );
+        assert_eq!(
+            Cache::load(&cache_path, "statements=1".into()).get(&file, &metadata),
+            None
+        );
+
+        fs::write(&file, "fn main() {}\n\n").unwrap();
+        let changed = fs::metadata(&file).unwrap();
+        assert_eq!(
+            Cache::load(&cache_path, "statements=0".into()).get(&file, &changed),
+            None
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{Scan, count_file, git};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+//how many files the churn report lists.
+const CHURN_FILES: usize = 20;
+
+//what the commits in a window did to one file.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Churn {
+    pub added: u128,
+    pub removed: u128,
+    pub commits: u128,
+}
+
+impl Churn {
+    pub fn changed(&self) -> u128 {
+        self.added + self.removed
+    }
+}
+
+//a file that's still there, with how it churned and how long it is now.
+pub struct FileChurn {
+    pub path: PathBuf,
+    pub churn: Churn,
+    pub lines: u128,
+}
+
+//the files below `root` that changed since `since` and are still around, as the filters see them.
+pub fn history(root: &Path, since: SystemTime, scan: &Scan) -> Result<Vec<FileChurn>> {
+    let changes = parse(&git::numstat(root, since)?);
+    Ok(changes
+        .into_par_iter()
+        .filter(|(path, _)| scan.filter.includes_path(path))
+        .filter_map(|(path, churn)| {
+            let path = root.join(path);
+            let lines = count_file(&path, scan).ok()?.counts.lines;
+            Some(FileChurn { path, churn, lines })
+        })
+        .collect())
+}
+
+//`<added>\t<removed>\t<path>` lines, with blank ones between commits. binary files have - for
+//both counts and only add a commit.
+fn parse(numstat: &[u8]) -> HashMap<PathBuf, Churn> {
+    let mut changes: HashMap<PathBuf, Churn> = HashMap::new();
+    for line in String::from_utf8_lossy(numstat).lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(added), Some(removed), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let churn = changes.entry(PathBuf::from(path)).or_default();
+        churn.added += added.parse::<u128>().unwrap_or_default();
+        churn.removed += removed.parse::<u128>().unwrap_or_default();
+        churn.commits += 1;
+    }
+    changes
+}
+
+//`lc churn`: the files that changed the most over a window, next to how big they are now. churn
+//as a multiple of the current size says how much of a file was rewritten.
+pub fn run(root: &Path, since: SystemTime, scan: &Scan) -> Result<()> {
+    let mut files = history(root, since, scan)?;
+    files.sort_by(|a, b| {
+        b.churn
+            .changed()
+            .cmp(&a.churn.changed())
+            .then(a.path.cmp(&b.path))
+    });
+
+    println!(
+        "{}",
+        format!(
+            "{:<48}{:>10}{:>10}{:>9}{:>10}{:>8}",
+            "File", "+Lines", "-Lines", "Commits", "Lines", "Churn"
+        )
+        .bold()
+    );
+    for file in files.iter().take(CHURN_FILES) {
+        let ratio = match file.lines {
+            0 => "-".to_string(),
+            lines => format!("{:.1}x", file.churn.changed() as f64 / lines as f64),
+        };
+        println!(
+            "{:<48}{}{}{:>9}{:>10}{ratio:>8}",
+            scan.display_path(&file.path),
+            format!("{:>10}", format!("+{}", file.churn.added)).green(),
+            format!("{:>10}", format!("-{}", file.churn.removed)).red(),
+            file.churn.commits,
+            file.lines
+        );
+    }
+    if files.len() > CHURN_FILES {
+        println!("...and {} more files", files.len() - CHURN_FILES);
+    }
+
+    let added = files.iter().map(|file| file.churn.added).sum::<u128>();
+    let removed = files.iter().map(|file| file.churn.removed).sum::<u128>();
+    println!(
+        "{:<48}{}{}{:>9}{:>10}",
+        "Total",
+        format!("{:>10}", format!("+{added}")).green(),
+        format!("{:>10}", format!("-{removed}")).red(),
+        "",
+        files.iter().map(|file| file.lines).sum::<u128>()
+    );
+    Ok(())
+}
+
+//--hotspots: big files that keep changing, where bugs and merge conflicts tend to gather. a file
+//scores its current lines times the commits that touched it in the window.
+pub fn print_hotspot_report(
+    roots: &[PathBuf],
+    since: SystemTime,
+    top: usize,
+    scan: &Scan,
+) -> Result<()> {
+    let mut files = Vec::new();
+    for root in roots {
+        files.extend(history(root, since, scan)?);
+    }
+    let score = |file: &FileChurn| file.lines * file.churn.commits;
+    files.sort_by(|a, b| score(b).cmp(&score(a)).then(a.path.cmp(&b.path)));
+
+    println!("{}", "Hotspots:".bold());
+    for file in files.iter().take(top) {
+        println!(
+            "  {:>10}  {:>6}L x {:>3} commits  {}",
+            score(file),
+            file.lines,
+            file.churn.commits,
+            scan.display_path(&file.path)
+        );
+    }
+    if files.len() > top {
+        println!("  ...and {} more changed files", files.len() - top);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Churn, parse};
+    use std::path::Path;
+
+    #[test]
+    fn sums_numstat_per_file() {
+        let changes =
+            parse(b"10\t2\tsrc/main.rs\n-\t-\tlogo.png\n\n3\t0\tsrc/main.rs\n1\t1\tdocs/a\tb.md\n");
+        assert_eq!(
+            changes[Path::new("src/main.rs")],
+            Churn {
+                added: 13,
+                removed: 2,
+                commits: 2,
+            }
+        );
+        assert_eq!(changes[Path::new("logo.png")].changed(), 0);
+        assert_eq!(changes[Path::new("docs/a\tb.md")].commits, 1);
+    }
+}
@@ -0,0 +1,86 @@
+//basic cocomo (boehm, 1981), the same model scc and sloccount print: effort in person-months from
+//thousands of source lines, and how long that takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Model {
+    //a small team on familiar ground.
+    Organic,
+    SemiDetached,
+    //tight constraints, hardware or regulation.
+    Embedded,
+}
+
+impl Model {
+    pub fn parse(name: &str) -> Result<Model, String> {
+        match name {
+            "organic" => Ok(Model::Organic),
+            "semi-detached" => Ok(Model::SemiDetached),
+            "embedded" => Ok(Model::Embedded),
+            _ => Err(format!(
+                "unknown model '{name}', expected organic, semi-detached or embedded"
+            )),
+        }
+    }
+
+    //(a, b, c, d): effort = a * KSLOC^b, schedule = c * effort^d.
+    fn coefficients(self) -> (f64, f64, f64, f64) {
+        match self {
+            Model::Organic => (2.4, 1.05, 2.5, 0.38),
+            Model::SemiDetached => (3.0, 1.12, 2.5, 0.35),
+            Model::Embedded => (3.6, 1.20, 2.5, 0.32),
+        }
+    }
+}
+
+pub struct Cocomo {
+    pub model: Model,
+    //a developer's yearly salary.
+    pub wage: f64,
+    //what a developer costs on top of the salary (office, benefits, management), as a multiple.
+    pub overhead: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Estimate {
+    pub effort: f64,
+    pub months: f64,
+    pub people: f64,
+    pub cost: f64,
+}
+
+impl Cocomo {
+    pub fn estimate(&self, sloc: u128) -> Estimate {
+        let (a, b, c, d) = self.model.coefficients();
+        let effort = a * (sloc as f64 / 1000.).powf(b);
+        let months = c * effort.powf(d);
+        Estimate {
+            effort,
+            months,
+            people: match months {
+                0. => 0.,
+                _ => effort / months,
+            },
+            cost: effort * self.wage / 12. * self.overhead,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cocomo, Model};
+
+    #[test]
+    fn estimates_like_scc() {
+        let cocomo = Cocomo {
+            model: Model::Organic,
+            wage: 56286.,
+            overhead: 2.4,
+        };
+        let estimate = cocomo.estimate(10_000);
+        assert_eq!(format!("{:.2}", estimate.effort), "26.93");
+        assert_eq!(format!("{:.2}", estimate.months), "8.74");
+        assert_eq!(format!("{:.2}", estimate.people), "3.08");
+        assert_eq!(format!("{:.0}", estimate.cost), "303139");
+        assert_eq!(cocomo.estimate(0).people, 0.);
+        assert!(Model::parse("agile").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use crate::Counts;
+use glob::{MatchOptions, Pattern};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+//where github and gitlab look for the file, in the order they look.
+const LOCATIONS: [&str; 4] = [
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+//what files nobody owns are listed under.
+pub const UNOWNED: &str = "(unowned)";
+
+struct Rule {
+    pattern: Pattern,
+    //`docs/*` is only the files right in docs/, everything else that matches a directory takes
+    //what's beneath it too.
+    nested: bool,
+    owners: Vec<String>,
+}
+
+//--owners: files and counts per owner in the CODEOWNERS file. a file with several owners counts
+//for each of them.
+pub struct Codeowners {
+    rules: Vec<Rule>,
+    totals: Mutex<HashMap<String, (u128, Counts)>>,
+    //every file once, however many owners it has.
+    counted: Mutex<Counts>,
+}
+
+impl Codeowners {
+    //the first CODEOWNERS found under `root`, along with where it was.
+    pub fn load(root: &Path) -> Option<(Codeowners, PathBuf)> {
+        LOCATIONS.iter().find_map(|location| {
+            let path = root.join(location);
+            let content = fs::read_to_string(&path).ok()?;
+            Some((Codeowners::parse(&content), path))
+        })
+    }
+
+    fn parse(content: &str) -> Codeowners {
+        let rules = content
+            .lines()
+            .filter_map(|line| {
+                let line = line.split_once('#').map_or(line, |(rule, _)| rule);
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                //a pattern with a / anywhere but the end is anchored at the root, one without
+                //matches at any depth.
+                let trimmed = pattern.trim_end_matches('/');
+                let glob = match trimmed.strip_prefix('/') {
+                    Some(anchored) => anchored.to_string(),
+                    None if trimmed.contains('/') => trimmed.to_string(),
+                    None => format!("**/{trimmed}"),
+                };
+                Some(Rule {
+                    pattern: Pattern::new(&glob).ok()?,
+                    nested: !trimmed.ends_with("/*"),
+                    owners: fields.map(str::to_string).collect(),
+                })
+            })
+            .collect();
+        Codeowners {
+            rules,
+            totals: Mutex::default(),
+            counted: Mutex::default(),
+        }
+    }
+
+    //the owners of a path relative to the root. the last rule that matches wins, even one without
+    //owners, which is how a CODEOWNERS file carves out an unowned corner.
+    pub fn owners(&self, relative: &Path) -> &[String] {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::default()
+        };
+        let matches = |rule: &Rule| {
+            rule.pattern.matches_path_with(relative, options)
+                || (rule.nested
+                    && relative
+                        .ancestors()
+                        .skip(1)
+                        .filter(|dir| !dir.as_os_str().is_empty())
+                        .any(|dir| rule.pattern.matches_path_with(dir, options)))
+        };
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| matches(rule))
+            .map_or(&[], |rule| &rule.owners)
+    }
+
+    pub fn record(&self, relative: &Path, counts: &Counts) {
+        let owners = self.owners(relative);
+        *self.counted.lock().unwrap() += *counts;
+        let mut totals = self.totals.lock().unwrap();
+        let unowned = [UNOWNED.to_string()];
+        for owner in if owners.is_empty() { &unowned } else { owners } {
+            let (files, total) = totals.entry(owner.clone()).or_default();
+            *files += 1;
+            *total += *counts;
+        }
+    }
+
+    pub fn totals(&self) -> HashMap<String, (u128, Counts)> {
+        self.totals.lock().unwrap().clone()
+    }
+
+    pub fn counted(&self) -> Counts {
+        *self.counted.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Codeowners;
+    use std::path::Path;
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let codeowners = Codeowners::parse(
+            "# default\n* @org/everyone\n*.rs @org/rust\n/docs/* @org/docs\nbuild/ @org/infra # ci\n/src/generated/\n",
+        );
+        let owners = |path| codeowners.owners(Path::new(path)).join(" ");
+        assert_eq!(owners("README.md"), "@org/everyone");
+        assert_eq!(owners("src/main.rs"), "@org/rust");
+        assert_eq!(owners("docs/intro.md"), "@org/docs");
+        assert_eq!(owners("docs/api/index.md"), "@org/everyone");
+        assert_eq!(owners("tools/build/run.sh"), "@org/infra");
+        assert_eq!(owners("src/generated/schema.rs"), "");
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DAY: u64 = 24 * 60 * 60;
+
+//a point in time given either as an age ("30d", "12h", "2w", "6m", "1y", counted back from now)
+//or as a calendar date ("2024-03-01", midnight UTC).
+pub fn parse_time_spec(spec: &str) -> Result<SystemTime, String> {
+    if let Some(age) = parse_age(spec) {
+        return SystemTime::now()
+            .checked_sub(age)
+            .ok_or_else(|| format!("'{spec}' reaches back before the epoch"));
+    }
+    parse_date(spec).ok_or_else(|| {
+        format!("'{spec}' is neither an age like 30d/12h/2w/6m/1y nor a date like 2024-03-01")
+    })
+}
+
+pub fn parse_age(spec: &str) -> Option<Duration> {
+    let unit_at = spec.find(|c: char| !c.is_ascii_digit())?;
+    let amount = spec[..unit_at].parse::<u64>().ok()?;
+    let seconds = match &spec[unit_at..] {
+        "s" => 1,
+        "h" => 60 * 60,
+        "d" => DAY,
+        "w" => 7 * DAY,
+        "m" => 30 * DAY,
+        "y" => 365 * DAY,
+        _ => return None,
+    };
+    Some(Duration::from_secs(amount * seconds))
+}
+
+pub fn parse_date(spec: &str) -> Option<SystemTime> {
+    let mut parts = spec.splitn(3, '-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?.parse::<u32>().ok()?;
+    let day = parts.next()?.parse::<u32>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let seconds = u64::try_from(days).ok()? * DAY;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+//days since 1970-01-01 for a proleptic gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+//"2024-03-01 14:05" in UTC, for showing when something happened.
+pub fn format_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((seconds / DAY) as i64);
+    let minutes = seconds % DAY / 60;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+//how long ago something was, roughly: "just now", "5 minutes ago", "2 days ago".
+pub fn format_ago(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    let (amount, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3600 => (seconds / 60, "minute"),
+        3600..DAY => (seconds / 3600, "hour"),
+        _ => (seconds / DAY, "day"),
+    };
+    match amount {
+        1 => format!("1 {unit} ago"),
+        _ => format!("{amount} {unit}s ago"),
+    }
+}
+
+//the other way around, from days since 1970-01-01 back to a date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_ago, format_time, parse_age, parse_date};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn parses_ages_and_dates() {
+        assert_eq!(parse_age("30d"), Some(Duration::from_secs(30 * 86_400)));
+        assert_eq!(parse_age("30"), None);
+        assert_eq!(parse_age("d"), None);
+        assert_eq!(
+            parse_date("2024-03-01"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_251_200))
+        );
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(
+            format_time(parse_date("2024-02-29").unwrap() + Duration::from_secs(50_700)),
+            "2024-02-29 14:05"
+        );
+        assert_eq!(format_ago(Duration::from_secs(59)), "just now");
+        assert_eq!(format_ago(Duration::from_secs(3600)), "1 hour ago");
+        assert_eq!(
+            format_ago(Duration::from_secs(2 * 86_400 + 5)),
+            "2 days ago"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use crate::git::{self, Blob};
+use crate::{
+    Counts, FileStat, Scan, collect_files, count_content, count_file, format_byte_count, languages,
+};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+//how many files the per-file listing shows.
+const CHANGED_FILES: usize = 30;
+
+//every counted file of one side, by its path below the root.
+pub type Snapshot = HashMap<PathBuf, Counts>;
+
+//how a group of files changed from one side to the other. a file that's on both sides counts as
+//added or removed lines by how much it grew or shrank, there's no line by line diff.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Delta {
+    pub added_lines: u128,
+    pub removed_lines: u128,
+    pub added_bytes: u128,
+    pub removed_bytes: u128,
+    pub added_files: u128,
+    pub removed_files: u128,
+}
+
+impl Delta {
+    fn between(old: Option<&Counts>, new: Option<&Counts>) -> Delta {
+        let (old_lines, old_bytes) = old.map_or((0, 0), |counts| (counts.lines, counts.bytes));
+        let (new_lines, new_bytes) = new.map_or((0, 0), |counts| (counts.lines, counts.bytes));
+        Delta {
+            added_lines: new_lines.saturating_sub(old_lines),
+            removed_lines: old_lines.saturating_sub(new_lines),
+            added_bytes: new_bytes.saturating_sub(old_bytes),
+            removed_bytes: old_bytes.saturating_sub(new_bytes),
+            added_files: u128::from(old.is_none()),
+            removed_files: u128::from(new.is_none()),
+        }
+    }
+
+    pub fn add(&mut self, other: Delta) {
+        self.added_lines += other.added_lines;
+        self.removed_lines += other.removed_lines;
+        self.added_bytes += other.added_bytes;
+        self.removed_bytes += other.removed_bytes;
+        self.added_files += other.added_files;
+        self.removed_files += other.removed_files;
+    }
+
+    pub fn net_lines(&self) -> i128 {
+        self.added_lines as i128 - self.removed_lines as i128
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Delta::default()
+    }
+}
+
+//`lc diff <old> <new>`: both trees are counted with the same filters and compared file by file,
+//with the changes summed up per directory.
+pub fn run(old: &Path, new: &Path, scan: &Scan) -> Result<()> {
+    let old = count_tree(old, scan)?;
+    let new = count_tree(new, scan)?;
+    print_reports(&old, &new);
+    Ok(())
+}
+
+//`lc diff --git <old> <new>`: the same between two revisions, read from the object database so
+//neither has to be checked out. files with the same blob on both sides can't have changed and
+//aren't read at all.
+pub fn run_git(old: &str, new: &str, scan: &Scan) -> Result<()> {
+    let (old, new) = count_changed(git::tree(old)?, git::tree(new)?, scan)?;
+    print_reports(&old, &new);
+    Ok(())
+}
+
+//both sides of a change between two sets of blobs, counting only the blobs that differ.
+pub fn count_changed(
+    mut old: Vec<Blob>,
+    mut new: Vec<Blob>,
+    scan: &Scan,
+) -> Result<(Snapshot, Snapshot)> {
+    let before = old.iter().cloned().collect::<HashSet<_>>();
+    let unchanged = new
+        .iter()
+        .filter(|blob| before.contains(blob))
+        .cloned()
+        .collect::<HashSet<_>>();
+    old.retain(|blob| !unchanged.contains(blob));
+    new.retain(|blob| !unchanged.contains(blob));
+    Ok((count_blobs(old, scan)?, count_blobs(new, scan)?))
+}
+
+pub fn print_reports(old: &Snapshot, new: &Snapshot) {
+    print_report("Directory", &diff(old, new, directory));
+    println!();
+    let total = print_report("Language", &diff(old, new, language));
+    println!("net {:+} lines", total.net_lines());
+}
+
+//the files that were added, removed or changed size, the biggest change first.
+pub fn print_files(old: &Snapshot, new: &Snapshot) {
+    let lines = |snapshot: &Snapshot, path| snapshot.get(path).map(|counts: &Counts| counts.lines);
+    let mut changed = old
+        .keys()
+        .chain(new.keys().filter(|path| !old.contains_key(*path)))
+        .filter(|path| old.get(*path) != new.get(*path))
+        .map(|path| (path, lines(old, path), lines(new, path)))
+        .collect::<Vec<_>>();
+    let net = |before: Option<u128>, after: Option<u128>| {
+        after.unwrap_or_default() as i128 - before.unwrap_or_default() as i128
+    };
+    changed.sort_by(|a, b| {
+        net(b.1, b.2)
+            .abs()
+            .cmp(&net(a.1, a.2).abs())
+            .then(a.0.cmp(b.0))
+    });
+
+    println!(
+        "{}",
+        format!(
+            "{:<9}{:<48}{:>10}{:>10}{:>10}",
+            "Status", "File", "Before", "After", "Change"
+        )
+        .bold()
+    );
+    let side = |lines: Option<u128>| lines.map_or("-".to_string(), |lines| lines.to_string());
+    for &(path, before, after) in changed.iter().take(CHANGED_FILES) {
+        let status = match (before, after) {
+            (None, _) => "new".green(),
+            (_, None) => "removed".red(),
+            _ => "changed".normal(),
+        };
+        let change = format!("{:>10}", format!("{:+}", net(before, after)));
+        println!(
+            "{status:<9}{:<48}{:>10}{:>10}{}",
+            path.display(),
+            side(before),
+            side(after),
+            match net(before, after) {
+                0 => change.normal(),
+                1.. => change.green(),
+                _ => change.red(),
+            }
+        );
+    }
+    if changed.len() > CHANGED_FILES {
+        println!("...and {} more files", changed.len() - CHANGED_FILES);
+    }
+}
+
+pub fn count_tree(root: &Path, scan: &Scan) -> Result<Snapshot> {
+    let mut files = Vec::new();
+    collect_files(root, scan, &mut files)?;
+    Ok(files
+        .par_iter()
+        .filter_map(|(path, _)| {
+            let counts = count_file(path, scan).ok()?.counts;
+            Some((path.strip_prefix(root).ok()?.to_path_buf(), counts))
+        })
+        .collect())
+}
+
+//the blobs the filters let through, counted as if they were files at their paths.
+pub fn count_blobs(mut blobs: Vec<Blob>, scan: &Scan) -> Result<Snapshot> {
+    blobs.retain(|blob| scan.filter.includes_path(&blob.path));
+    Ok(count_each(&blobs, scan)?
+        .into_iter()
+        .map(|file_stat| (file_stat.path, file_stat.counts))
+        .collect())
+}
+
+//every blob counted, in the same order. they're read a batch at a time and each batch is counted
+//in parallel before the next one is read.
+pub fn count_each(blobs: &[Blob], scan: &Scan) -> Result<Vec<FileStat>> {
+    let mut stats = Vec::with_capacity(blobs.len());
+    git::read_batches(blobs, |batch, contents| {
+        stats.par_extend(
+            batch
+                .par_iter()
+                .zip(contents)
+                .map(|(blob, content)| count_content(&blob.path, &content, scan)),
+        );
+    })?;
+    Ok(stats)
+}
+
+//the directory a file is in, "." for the root.
+pub fn directory(path: &Path) -> String {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_string_lossy().into_owned(),
+        _ => ".".to_string(),
+    }
+}
+
+//the language a file's name says it's in.
+pub fn language(path: &Path) -> String {
+    languages::detect_by_name(path)
+        .map_or("Other", |lang| lang.name)
+        .to_string()
+}
+
+//the changes per group, `group` says which one a file falls into. groups that didn't change are
+//left out.
+pub fn diff(
+    old: &Snapshot,
+    new: &Snapshot,
+    group: impl Fn(&Path) -> String,
+) -> BTreeMap<String, Delta> {
+    let mut groups: BTreeMap<String, Delta> = BTreeMap::new();
+    for path in old
+        .keys()
+        .chain(new.keys().filter(|path| !old.contains_key(*path)))
+    {
+        let delta = Delta::between(old.get(path), new.get(path));
+        if !delta.is_empty() {
+            groups.entry(group(path)).or_default().add(delta);
+        }
+    }
+    groups
+}
+
+//one row per group and a total, which comes back.
+fn print_report(by: &str, groups: &BTreeMap<String, Delta>) -> Delta {
+    println!(
+        "{}",
+        format!(
+            "{:<40}{:>10}{:>10}{:>12}{:>12}{:>8}",
+            by, "+Lines", "-Lines", "+Bytes", "-Bytes", "Files"
+        )
+        .bold()
+    );
+    let row = |name: &str, delta: &Delta| {
+        println!(
+            "{name:<40}{}{}{:>12}{:>12}{:>8}",
+            format!("{:>10}", format!("+{}", delta.added_lines)).green(),
+            format!("{:>10}", format!("-{}", delta.removed_lines)).red(),
+            format!("+{}", format_byte_count(delta.added_bytes)),
+            format!("-{}", format_byte_count(delta.removed_bytes)),
+            format!("+{}/-{}", delta.added_files, delta.removed_files)
+        );
+    };
+
+    let mut total = Delta::default();
+    for (name, delta) in groups {
+        row(name, delta);
+        total.add(*delta);
+    }
+    row("Total", &total);
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Delta, Snapshot, diff, directory};
+    use crate::Counts;
+    use std::path::PathBuf;
+
+    #[test]
+    fn sums_changes_per_directory() {
+        let counts = |lines, bytes| Counts {
+            lines,
+            bytes,
+            ..Counts::default()
+        };
+        let old = Snapshot::from([
+            (PathBuf::from("src/main.rs"), counts(100, 1000)),
+            (PathBuf::from("src/old.rs"), counts(20, 200)),
+            (PathBuf::from("README.md"), counts(10, 100)),
+        ]);
+        let new = Snapshot::from([
+            (PathBuf::from("src/main.rs"), counts(90, 950)),
+            (PathBuf::from("src/new.rs"), counts(30, 300)),
+            (PathBuf::from("README.md"), counts(10, 100)),
+        ]);
+
+        let groups = diff(&old, &new, directory);
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["src"]);
+        assert_eq!(
+            groups["src"],
+            Delta {
+                added_lines: 30,
+                removed_lines: 30,
+                added_bytes: 300,
+                removed_bytes: 250,
+                added_files: 1,
+                removed_files: 1,
+            }
+        );
+    }
+}
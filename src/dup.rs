@@ -0,0 +1,199 @@
+use crate::{encoding, languages};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+//how many consecutive (non-blank) lines have to repeat before they count as a copy.
+pub const DUP_BLOCK_LINES: usize = 6;
+const TOP_BLOCKS: usize = 10;
+
+//copy-paste detector fed with file contents during the count. every file is reduced to the
+//hashes of its normalized non-blank lines, and any window of DUP_BLOCK_LINES hashes seen more
+//than once marks the lines it covers as duplicated.
+pub struct DupIndex {
+    ignore_whitespace: bool,
+    //by path, so a file counted twice (--verify-parallel recounts some) is only in here once.
+    files: Mutex<BTreeMap<PathBuf, DupFile>>,
+}
+
+struct DupFile {
+    path: PathBuf,
+    language: String,
+    //(1-based line number, hash of the normalized line) for every non-blank line
+    lines: Vec<(usize, u64)>,
+    //the same lines trimmed, what a block is shown as. kept from the content we were handed so the
+    //report doesn't have to read the file again.
+    text: Vec<Box<str>>,
+}
+
+pub struct DupReport {
+    //language -> (duplicated lines, non-blank lines)
+    pub languages: BTreeMap<String, (usize, usize)>,
+    pub blocks: Vec<DupBlock>,
+}
+
+pub struct DupBlock {
+    pub locations: Vec<(PathBuf, usize)>,
+    pub preview: String,
+}
+
+impl DupIndex {
+    pub fn new(ignore_whitespace: bool) -> Self {
+        DupIndex {
+            ignore_whitespace,
+            files: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn add(&self, path: &Path, content: &[u8]) {
+        let (_, decoded) = encoding::decode(content);
+        let content = String::from_utf8_lossy(&decoded);
+        let (lines, text) = content
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                let normalized = self.normalize(line);
+                if normalized.is_empty() {
+                    return None;
+                }
+
+                let mut hasher = DefaultHasher::new();
+                normalized.hash(&mut hasher);
+                Some(((idx + 1, hasher.finish()), Box::from(line.trim())))
+            })
+            .unzip();
+
+        //like --languages, text without a language goes under "Other".
+        let language = languages::detect(path)
+            .map_or("Other", |lang| lang.name)
+            .to_string();
+
+        self.files.lock().unwrap().insert(
+            path.to_path_buf(),
+            DupFile {
+                path: path.to_path_buf(),
+                language,
+                lines,
+                text,
+            },
+        );
+    }
+
+    fn normalize(&self, line: &str) -> String {
+        if self.ignore_whitespace {
+            line.split_whitespace().collect()
+        } else {
+            line.trim().to_string()
+        }
+    }
+
+    pub fn report(&self) -> DupReport {
+        let files = self.files.lock().unwrap();
+        let files = files.values().collect::<Vec<_>>();
+
+        //window hash -> every (file, position in its non-blank lines) it starts at
+        let mut windows: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+        for (file_idx, file) in files.iter().enumerate() {
+            for (pos, window) in file.lines.windows(DUP_BLOCK_LINES).enumerate() {
+                let mut hasher = DefaultHasher::new();
+                for (_, line_hash) in window {
+                    line_hash.hash(&mut hasher);
+                }
+                windows
+                    .entry(hasher.finish())
+                    .or_default()
+                    .push((file_idx, pos));
+            }
+        }
+
+        let mut duplicated = files
+            .iter()
+            .map(|file| vec![false; file.lines.len()])
+            .collect::<Vec<_>>();
+        let mut repeated = windows
+            .into_values()
+            .filter(|occurrences| occurrences.len() > 1)
+            .collect::<Vec<_>>();
+
+        for occurrences in &repeated {
+            for &(file_idx, pos) in occurrences {
+                duplicated[file_idx][pos..pos + DUP_BLOCK_LINES].fill(true);
+            }
+        }
+
+        let mut languages: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for (file, marks) in files.iter().zip(&duplicated) {
+            let entry = languages.entry(file.language.clone()).or_default();
+            entry.0 += marks.iter().filter(|&&dup| dup).count();
+            entry.1 += marks.len();
+        }
+
+        //a long copied region shows up as many overlapping windows. keep the most repeated ones
+        //and drop any window that starts inside a block that was already picked.
+        repeated.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        let mut picked: Vec<Vec<(usize, usize)>> = Vec::new();
+        for occurrences in repeated {
+            if picked.len() == TOP_BLOCKS {
+                break;
+            }
+
+            let (file_idx, pos) = occurrences[0];
+            let overlaps = picked.iter().flatten().any(|&(other_file, other_pos)| {
+                other_file == file_idx && pos.abs_diff(other_pos) < DUP_BLOCK_LINES
+            });
+            if !overlaps {
+                picked.push(occurrences);
+            }
+        }
+
+        let blocks = picked
+            .into_iter()
+            .map(|occurrences| {
+                let locations = occurrences
+                    .iter()
+                    .map(|&(file_idx, pos)| {
+                        let file = &files[file_idx];
+                        (file.path.clone(), file.lines[pos].0)
+                    })
+                    .collect::<Vec<_>>();
+                let (file_idx, pos) = occurrences[0];
+                let preview = files[file_idx].text[pos].to_string();
+                DupBlock { locations, preview }
+            })
+            .collect();
+
+        DupReport { languages, blocks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DupIndex;
+    use std::path::Path;
+
+    #[test]
+    fn finds_copied_blocks_per_language() {
+        let index = DupIndex::new(false);
+        let block =
+            "fn a() {\n    let x = 1;\n    let y = 2;\n\n    let z = 3;\n    x + y + z\n}\n";
+        index.add(Path::new("src/a.rs"), block.as_bytes());
+        index.add(Path::new("src/b.rs"), format!("// b\n{block}").as_bytes());
+        index.add(Path::new("src/a.rs"), block.as_bytes());
+        index.add(Path::new("notes.txt"), b"one\ntwo\n");
+
+        let report = index.report();
+        assert_eq!(report.languages["Rust"], (12, 13));
+        assert_eq!(report.languages["Other"], (0, 2));
+        assert_eq!(report.blocks.len(), 1);
+        assert_eq!(report.blocks[0].preview, "fn a() {");
+        assert_eq!(
+            report.blocks[0].locations,
+            [
+                (Path::new("src/a.rs").into(), 1),
+                (Path::new("src/b.rs").into(), 2)
+            ]
+        );
+    }
+}
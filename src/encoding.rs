@@ -0,0 +1,164 @@
+use std::borrow::Cow;
+
+//how much of the start of a file the utf-16 guess looks at, same as the binary sniff.
+const SNIFF_LEN: usize = 8 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    ShiftJis,
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin-1",
+            Encoding::ShiftJis => "shift-jis",
+        }
+    }
+}
+
+//the byte order mark a file starts with, and how many bytes of it there are. it says how the
+//text is encoded but isn't part of it, so it's left out of everything that gets counted.
+pub fn bom(content: &[u8]) -> Option<(Encoding, usize)> {
+    match content {
+        [0xEF, 0xBB, 0xBF, ..] => Some((Encoding::Utf8, 3)),
+        [0xFF, 0xFE, ..] => Some((Encoding::Utf16Le, 2)),
+        [0xFE, 0xFF, ..] => Some((Encoding::Utf16Be, 2)),
+        _ => None,
+    }
+}
+
+//a byte order mark, or text that's mostly ascii with every other byte a NUL. a binary file has
+//NULs all over the place, so as soon as a whole code unit is 0 it isn't taken for utf-16.
+pub fn utf16(head: &[u8]) -> Option<Encoding> {
+    if let Some((encoding @ (Encoding::Utf16Le | Encoding::Utf16Be), _)) = bom(head) {
+        return Some(encoding);
+    }
+    let pairs = head.chunks_exact(2);
+    if pairs.len() < 2 || !head.contains(&0) || pairs.clone().any(|pair| pair == [0, 0]) {
+        return None;
+    }
+    let (even, odd) = pairs.clone().fold((0, 0), |(even, odd), pair| {
+        (
+            even + usize::from(pair[0] == 0),
+            odd + usize::from(pair[1] == 0),
+        )
+    });
+    //most of a utf-16 file in a latin script has a NUL in the same half of every code unit.
+    let mostly = |zeros: usize| zeros * 10 >= pairs.len() * 4;
+    match (mostly(even), mostly(odd)) {
+        (false, true) if even == 0 => Some(Encoding::Utf16Le),
+        (true, false) if odd == 0 => Some(Encoding::Utf16Be),
+        _ => None,
+    }
+}
+
+//what a text file is most likely written in. anything that's valid utf-8 is taken for it, then
+//shift-jis if every byte past ascii pairs up the way it does there, and latin-1 otherwise since
+//every byte is valid latin-1.
+pub fn detect(content: &[u8]) -> Encoding {
+    if let Some(encoding) = utf16(&content[..content.len().min(SNIFF_LEN)]) {
+        return encoding;
+    }
+    if std::str::from_utf8(content).is_ok() {
+        return Encoding::Utf8;
+    }
+    if looks_like_shift_jis(content) {
+        return Encoding::ShiftJis;
+    }
+    Encoding::Latin1
+}
+
+//a latin-1 é followed by a letter also passes for a shift-jis pair, but japanese text is mostly
+//pairs where both bytes are past ascii (all of hiragana, most kanji).
+fn looks_like_shift_jis(content: &[u8]) -> bool {
+    let (mut pairs, mut wide) = (0, 0);
+    let mut bytes = content.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            0x00..=0x7F | 0xA1..=0xDF => {}
+            0x81..=0x9F | 0xE0..=0xFC => match bytes.next() {
+                Some(&trail @ (0x40..=0x7E | 0x80..=0xFC)) => {
+                    pairs += 1;
+                    wide += usize::from(trail >= 0x80);
+                }
+                _ => return false,
+            },
+            _ => return false,
+        }
+    }
+    pairs > 0 && wide * 2 >= pairs
+}
+
+//the content as utf-8, so lines and characters are counted the same whatever it was written in.
+//there's no shift-jis table in here: half-width katakana map over exactly, every double-byte
+//character becomes U+FFFD. that keeps the line and character counts right, not the text. a byte
+//order mark is dropped.
+pub fn decode(content: &[u8]) -> (Encoding, Cow<'_, [u8]>) {
+    let encoding = detect(content);
+    let content = &content[bom(content).map_or(0, |(_, len)| len)..];
+    let decoded = match encoding {
+        Encoding::Utf8 => return (encoding, Cow::Borrowed(content)),
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = content.chunks_exact(2).map(|pair| match encoding {
+                Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            });
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect::<String>()
+        }
+        Encoding::Latin1 => content.iter().map(|&byte| byte as char).collect(),
+        Encoding::ShiftJis => {
+            let mut decoded = String::with_capacity(content.len());
+            let mut bytes = content.iter();
+            while let Some(&byte) = bytes.next() {
+                decoded.push(match byte {
+                    0x00..=0x7F => byte as char,
+                    0xA1..=0xDF => char::from_u32(0xFF61 + u32::from(byte - 0xA1)).unwrap(),
+                    _ => {
+                        bytes.next();
+                        char::REPLACEMENT_CHARACTER
+                    }
+                });
+            }
+            decoded
+        }
+    };
+    (encoding, Cow::Owned(decoded.into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encoding, decode, utf16};
+
+    #[test]
+    fn detects_and_decodes() {
+        assert_eq!(
+            decode(b"\xFF\xFEa\0\n\0b\0"),
+            (Encoding::Utf16Le, b"a\nb"[..].into())
+        );
+        assert_eq!(decode(b"\0a\0\n\0b").0, Encoding::Utf16Be);
+        assert_eq!(decode("café".as_bytes()).0, Encoding::Utf8);
+        assert_eq!(
+            decode(b"\xEF\xBB\xBFx\n"),
+            (Encoding::Utf8, b"x\n"[..].into())
+        );
+        assert_eq!(
+            decode(b"caf\xe9 cr\xe8me"),
+            (Encoding::Latin1, "café crème".as_bytes().into())
+        );
+        //"こんにちは" and a half-width katakana.
+        let (encoding, decoded) = decode(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd\n\xb1");
+        assert_eq!(encoding, Encoding::ShiftJis);
+        assert_eq!(std::str::from_utf8(&decoded).unwrap().chars().count(), 7);
+        assert_eq!(utf16(b"\x7fELF\x02\x01\x01\0\0\0\0\0"), None);
+    }
+}
@@ -0,0 +1,74 @@
+//z-score for a 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+//a total extrapolated from a sample, give or take `margin` at 95% confidence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub total: f64,
+    pub margin: f64,
+}
+
+//"0.05" or "5%", anything in (0, 1].
+pub fn parse_fraction(spec: &str) -> Result<f64, String> {
+    let fraction = match spec.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().map(|p| p / 100.0),
+        None => spec.parse::<f64>(),
+    }
+    .map_err(|_| format!("'{spec}' is not a fraction like 0.05 or 5%"))?;
+
+    if fraction > 0.0 && fraction <= 1.0 {
+        Ok(fraction)
+    } else {
+        Err(format!(
+            "'{spec}' has to be more than 0 and at most 1 (100%)"
+        ))
+    }
+}
+
+//simple random sampling without replacement: the sample mean scaled up to the population, with
+//the finite population correction so a sample covering every file has no margin at all.
+pub fn extrapolate(samples: &[u128], population: usize) -> Estimate {
+    let n = samples.len() as f64;
+    let population = population as f64;
+    if samples.is_empty() {
+        return Estimate {
+            total: 0.0,
+            margin: 0.0,
+        };
+    }
+
+    let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / n;
+    let variance = if samples.len() > 1 {
+        samples
+            .iter()
+            .map(|&x| (x as f64 - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.0)
+    } else {
+        0.0
+    };
+    let correction = ((population - n) / population).max(0.0);
+
+    Estimate {
+        total: mean * population,
+        margin: Z_95 * population * (variance / n * correction).sqrt(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extrapolate, parse_fraction};
+
+    #[test]
+    fn extrapolates_with_finite_population_correction() {
+        let estimate = extrapolate(&[10, 20, 30], 30);
+        assert_eq!(estimate.total, 600.0);
+        assert!(estimate.margin > 0.0);
+        assert_eq!(extrapolate(&[10, 20, 30], 3).margin, 0.0);
+
+        assert_eq!(parse_fraction("5%"), Ok(0.05));
+        assert_eq!(parse_fraction("0.5"), Ok(0.5));
+        assert!(parse_fraction("0").is_err());
+        assert!(parse_fraction("1.5").is_err());
+    }
+}
@@ -0,0 +1,110 @@
+pub use crate::budget::Permit;
+use crate::budget::{self, Budget};
+use crate::timings::{self, Phase};
+use std::fs::File;
+use std::io::{Error, Read, Result};
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::LazyLock;
+use std::thread;
+use std::time::Duration;
+
+//descriptors left alone for stdio, the directories being listed and whatever the runtime holds.
+const RESERVED_FDS: usize = 64;
+//when the system runs out of descriptors anyway (other processes count too), an open is retried
+//with a doubling pause until the pause would pass this.
+const MAX_BACKOFF: Duration = Duration::from_millis(500);
+
+//how many files the counters may have open at once, everything opened for counting goes through
+//here. workers that would go over the budget wait for someone to close a file instead of failing
+//with EMFILE.
+static OPEN_FILES: LazyLock<Budget> = LazyLock::new(|| Budget::new(fd_budget()));
+
+//a file opened within the budget, it holds on to its permit until it's closed.
+pub struct OpenFile {
+    file: File,
+    _permit: Permit,
+}
+
+impl Deref for OpenFile {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        &self.file
+    }
+}
+
+impl DerefMut for OpenFile {
+    fn deref_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+}
+
+//room for n more open files, for callers that open them some other way (io_uring).
+pub fn reserve(n: usize) -> Permit {
+    OPEN_FILES.acquire(n)
+}
+
+pub fn open(path: &Path) -> Result<OpenFile> {
+    let permit = OPEN_FILES.acquire(1);
+    let mut backoff = Duration::from_millis(1);
+    loop {
+        match timings::time(Phase::Reading, || File::open(path)) {
+            Err(err) if is_out_of_fds(&err) && backoff <= MAX_BACKOFF => {
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            result => {
+                return result.map(|file| OpenFile {
+                    file,
+                    _permit: permit,
+                });
+            }
+        }
+    }
+}
+
+//a whole file read into memory, it counts against --max-mem for as long as it's around.
+pub struct Content {
+    bytes: Vec<u8>,
+    _memory: Option<Permit>,
+}
+
+impl Deref for Content {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+//fs::read through the budget.
+pub fn read(path: &Path) -> Result<Content> {
+    let mut file = open(path)?;
+    let len = file.metadata().map_or(0, |m| m.len() as usize);
+    let memory = budget::reserve_memory(len);
+    let mut bytes = Vec::with_capacity(len);
+    timings::time(Phase::Reading, || file.read_to_end(&mut bytes))?;
+    Ok(Content {
+        bytes,
+        _memory: memory,
+    })
+}
+
+fn is_out_of_fds(err: &Error) -> bool {
+    matches!(err.raw_os_error(), Some(libc::EMFILE | libc::ENFILE))
+}
+
+//whatever `ulimit -n` allows, minus the reserve.
+fn fd_budget() -> usize {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    let soft = if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } == 0 {
+        usize::try_from(limit.rlim_cur).unwrap_or(usize::MAX)
+    } else {
+        1024
+    };
+    soft.saturating_sub(RESERVED_FDS).max(1)
+}
@@ -0,0 +1,549 @@
+use crate::gitattributes::{Attributes, Linguist};
+use crate::{Content, ContentType, Visible};
+use glob::Pattern;
+use regex::Regex;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const VCS_DIRS: [&str; 3] = [".git", ".hg", ".svn"];
+
+const RUST_ARTIFACTS: [&str; 1] = ["target"];
+const NODE_ARTIFACTS: [&str; 5] = ["node_modules", "dist", "build", ".next", "coverage"];
+const PYTHON_ARTIFACTS: [&str; 8] = [
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".pytest_cache",
+    ".mypy_cache",
+    ".tox",
+    "dist",
+    "build",
+];
+
+//decides which files get counted and which directories get descended into. the whitelisting
+//rules (--include, --include-re) only ever hold back individual files, since a directory that
+//doesn't match might still contain files that do. hidden entries are skipped unless `all` is set,
+//version control directories unless `include_vcs` is, and editor droppings unless `include_noise` is.
+//files a root's .gitattributes marks linguist-generated or linguist-vendored are skipped unless
+//`include_generated` is set, the same files github leaves out of its stats.
+#[derive(Default)]
+pub struct Filter {
+    pub roots: Vec<PathBuf>,
+    pub all: bool,
+    pub include_vcs: bool,
+    pub include_noise: bool,
+    pub include_generated: bool,
+    //each root's .gitattributes, in the same order as the roots.
+    pub attributes: Vec<Attributes>,
+    pub preset_dirs: Vec<&'static str>,
+    pub min_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub newer_than: Option<SystemTime>,
+    pub older_than: Option<SystemTime>,
+    pub types: Vec<ContentType>,
+    pub exclude_types: Vec<ContentType>,
+    pub extensions: Vec<String>,
+    pub include: Vec<Pattern>,
+    pub include_re: Vec<Regex>,
+    pub exclude_re: Vec<Regex>,
+    pub prioritize: Vec<Pattern>,
+}
+
+//the rule that kept a path out of the count, in the order the filter checks them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Skip {
+    Hidden,
+    Vcs,
+    Noise,
+    Preset(String),
+    TooShallow(usize),
+    TooDeep(usize),
+    NotIncluded,
+    NotNewer,
+    NotOlder,
+    Extension,
+    Type(ContentType),
+    NotIncludedRe,
+    ExcludedRe(String),
+    Symlink,
+    Revisited,
+    OtherFilesystem,
+    Submodule,
+    HardLink,
+    Special(&'static str),
+    Linguist(Linguist),
+}
+
+impl fmt::Display for Skip {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Skip::Hidden => write!(f, "hidden (use -a/--all to count it)"),
+            Skip::Vcs => write!(f, "version control directory (use --include-vcs)"),
+            Skip::Noise => write!(f, "editor backup/swap file (use --include-noise)"),
+            Skip::Preset(dir) => write!(f, "'{dir}' build artifacts, skipped by --preset"),
+            Skip::TooShallow(min) => write!(f, "shallower than --min-depth {min}"),
+            Skip::TooDeep(max) => write!(f, "deeper than --max-depth {max}"),
+            Skip::NotIncluded => write!(f, "name doesn't match any --include glob"),
+            Skip::NotNewer => write!(f, "last modified before the --newer-than cutoff"),
+            Skip::NotOlder => write!(f, "last modified after the --older-than cutoff"),
+            Skip::Extension => write!(f, "extension isn't one of --ext"),
+            Skip::Type(content_type) => write!(
+                f,
+                "content type '{}' left out by --type/--exclude-type",
+                content_type.name()
+            ),
+            Skip::NotIncludedRe => write!(f, "path doesn't match any --include-re"),
+            Skip::ExcludedRe(re) => write!(f, "path matches --exclude-re '{re}'"),
+            Skip::Symlink => write!(f, "symbolic link (use --follow-symlinks)"),
+            Skip::Revisited => write!(f, "directory already reached through another link"),
+            Skip::Submodule => write!(
+                f,
+                "git submodule, left out by --submodules skip or separate"
+            ),
+            Skip::OtherFilesystem => write!(
+                f,
+                "mount point of another filesystem, left out by --one-file-system"
+            ),
+            Skip::HardLink => write!(f, "hard link to a file already counted (use --count-links)"),
+            Skip::Special(kind) => write!(f, "{kind}, only regular files are read"),
+            Skip::Linguist(Linguist::Generated) => write!(
+                f,
+                "marked linguist-generated in .gitattributes (use --include-generated)"
+            ),
+            Skip::Linguist(Linguist::Vendored) => write!(
+                f,
+                "marked linguist-vendored in .gitattributes (use --include-generated)"
+            ),
+        }
+    }
+}
+
+impl Filter {
+    pub fn includes_file(&self, path: &Path) -> bool {
+        self.file_skip(path).is_none()
+    }
+
+    pub fn includes_dir(&self, path: &Path) -> bool {
+        self.dir_skip(path).is_none()
+    }
+
+    //a path out of a git tree, which is listed rather than walked: the file and every directory
+    //above it have to get through on their own.
+    pub fn includes_path(&self, path: &Path) -> bool {
+        self.includes_file(path)
+            && path
+                .ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .all(|dir| self.includes_dir(dir))
+    }
+
+    pub fn file_skip(&self, path: &Path) -> Option<Skip> {
+        if !self.all && !path.is_visible() {
+            return Some(Skip::Hidden);
+        }
+        if !self.include_noise && is_noise(path) {
+            return Some(Skip::Noise);
+        }
+
+        let depth = self.depth(path);
+        if let Some(min) = self.min_depth.filter(|&min| depth < min) {
+            return Some(Skip::TooShallow(min));
+        }
+        if let Some(max) = self.max_depth.filter(|&max| depth > max) {
+            return Some(Skip::TooDeep(max));
+        }
+
+        let filename = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        if !self.include.is_empty() && !self.include.iter().any(|p| p.matches(filename)) {
+            return Some(Skip::NotIncluded);
+        }
+
+        if (self.newer_than.is_some() || self.older_than.is_some())
+            && let Ok(modified) = path.metadata().and_then(|metadata| metadata.modified())
+        {
+            if self.newer_than.is_some_and(|cutoff| modified < cutoff) {
+                return Some(Skip::NotNewer);
+            }
+            if self.older_than.is_some_and(|cutoff| modified > cutoff) {
+                return Some(Skip::NotOlder);
+            }
+        }
+
+        if !self.extensions.is_empty()
+            && !path
+                .file_extension()
+                .is_some_and(|ext| self.extensions.iter().any(|wanted| wanted == ext))
+        {
+            return Some(Skip::Extension);
+        }
+
+        if !self.types.is_empty() || !self.exclude_types.is_empty() {
+            let content_type = path.content_type();
+            if (!self.types.is_empty() && !self.types.contains(&content_type))
+                || self.exclude_types.contains(&content_type)
+            {
+                return Some(Skip::Type(content_type));
+            }
+        }
+
+        let relative = self.relative(path);
+        if !self.include_re.is_empty() && !self.include_re.iter().any(|re| re.is_match(&relative)) {
+            return Some(Skip::NotIncludedRe);
+        }
+
+        if !self.include_generated
+            && let Some(linguist) = self.linguist(path)
+        {
+            return Some(Skip::Linguist(linguist));
+        }
+
+        self.exclude_re
+            .iter()
+            .find(|re| re.is_match(&relative))
+            .map(|re| Skip::ExcludedRe(re.to_string()))
+    }
+
+    pub fn dir_skip(&self, path: &Path) -> Option<Skip> {
+        let filename = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        //asking for vcs directories is enough on its own, they don't need --all on top.
+        if VCS_DIRS.contains(&filename) {
+            if !self.include_vcs {
+                return Some(Skip::Vcs);
+            }
+        } else if !self.all && !path.is_visible() {
+            return Some(Skip::Hidden);
+        }
+        if self.preset_dirs.contains(&filename) {
+            return Some(Skip::Preset(filename.to_string()));
+        }
+        //a directory at max depth would only hold entries past it, so it isn't entered at all.
+        if let Some(max) = self.max_depth.filter(|&max| self.depth(path) >= max) {
+            return Some(Skip::TooDeep(max));
+        }
+
+        let relative = self.relative(path);
+        self.exclude_re
+            .iter()
+            .find(|re| re.is_match(&relative))
+            .map(|re| Skip::ExcludedRe(re.to_string()))
+    }
+
+    //a directory counts as prioritized when a pattern could match something beneath it, so
+    //"src/**" pulls the src/ directory itself to the front too.
+    pub fn is_prioritized(&self, path: &Path) -> bool {
+        let relative = self.relative(path);
+        let as_dir = format!("{relative}/");
+        self.prioritize
+            .iter()
+            .any(|p| p.matches(&relative) || (path.is_dir() && p.as_str().starts_with(&as_dir)))
+    }
+
+    //a path out of a git tree isn't under any root, it goes by the first one's .gitattributes.
+    fn linguist(&self, path: &Path) -> Option<Linguist> {
+        let (attributes, relative) = self
+            .roots
+            .iter()
+            .zip(&self.attributes)
+            .find_map(|(root, attributes)| Some((attributes, path.strip_prefix(root).ok()?)))
+            .or_else(|| Some((self.attributes.first()?, path)))?;
+        attributes.linguist(relative)
+    }
+
+    //entries directly inside a root are at depth 1, their children at depth 2 and so on.
+    fn depth(&self, path: &Path) -> usize {
+        Path::new(&self.relative(path)).components().count()
+    }
+
+    //paths as the rules see them: relative to the scan root they were found under, e.g.
+    //"src/filter.rs".
+    pub fn relative(&self, path: &Path) -> String {
+        self.roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+//directory names skipped by a --preset. "auto" looks for the marker files of each ecosystem in
+//the scan root and combines the presets of everything it finds.
+pub fn preset_dirs(preset: &str, root: &Path) -> Vec<&'static str> {
+    match preset {
+        "rust" => RUST_ARTIFACTS.to_vec(),
+        "node" => NODE_ARTIFACTS.to_vec(),
+        "python" => PYTHON_ARTIFACTS.to_vec(),
+        "auto" => {
+            let mut dirs = Vec::new();
+            if root.join("Cargo.toml").exists() {
+                dirs.extend(RUST_ARTIFACTS);
+            }
+            if root.join("package.json").exists() {
+                dirs.extend(NODE_ARTIFACTS);
+            }
+            if [
+                "pyproject.toml",
+                "setup.py",
+                "setup.cfg",
+                "requirements.txt",
+            ]
+            .iter()
+            .any(|marker| root.join(marker).exists())
+            {
+                dirs.extend(PYTHON_ARTIFACTS);
+            }
+            dirs
+        }
+        _ => Vec::new(),
+    }
+}
+
+//backup and swap files left behind by editors and patch tools (foo~, foo.swp, foo.orig, foo.rej,
+//#foo#). whether they exist depends on whose machine ran the scan, so they don't count by default.
+pub fn is_noise(path: &Path) -> bool {
+    let filename = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+
+    filename.ends_with('~')
+        || filename.ends_with(".swp")
+        || filename.ends_with(".orig")
+        || filename.ends_with(".rej")
+        || (filename.len() > 1 && filename.starts_with('#') && filename.ends_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filter, Skip};
+    use crate::ContentType;
+    use crate::gitattributes::{Attributes, Linguist};
+    use glob::Pattern;
+    use regex::Regex;
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    fn filter() -> Filter {
+        Filter {
+            roots: vec![PathBuf::from("r")],
+            ..Filter::default()
+        }
+    }
+
+    #[test]
+    fn file_rules_in_order() {
+        let filter = Filter {
+            max_depth: Some(2),
+            exclude_re: vec![Regex::new("^gen/").unwrap()],
+            ..filter()
+        };
+        let skip = |path| filter.file_skip(Path::new(path));
+        assert_eq!(skip("r/src/main.rs"), None);
+        assert_eq!(skip("r/.hidden.rs"), Some(Skip::Hidden));
+        assert_eq!(skip("r/main.rs~"), Some(Skip::Noise));
+        assert_eq!(skip("r/a/b/deep.rs"), Some(Skip::TooDeep(2)));
+        assert_eq!(
+            skip("r/gen/out.rs"),
+            Some(Skip::ExcludedRe("^gen/".to_string()))
+        );
+    }
+
+    #[test]
+    fn ext_keeps_listed_extensions() {
+        let filter = Filter {
+            extensions: vec!["rs".to_string(), "md".to_string()],
+            ..filter()
+        };
+        assert_eq!(filter.file_skip(Path::new("r/lib.rs")), None);
+        assert_eq!(filter.file_skip(Path::new("r/README.md")), None);
+        assert_eq!(
+            filter.file_skip(Path::new("r/lib.c")),
+            Some(Skip::Extension)
+        );
+    }
+
+    #[test]
+    fn ext_leaves_out_names_without_one() {
+        let filter = Filter {
+            extensions: vec!["rs".to_string()],
+            ..filter()
+        };
+        assert_eq!(
+            filter.file_skip(Path::new("r/Makefile")),
+            Some(Skip::Extension)
+        );
+    }
+
+    #[test]
+    fn type_keeps_listed_types() {
+        let filter = Filter {
+            types: vec![ContentType::CODE, ContentType::TEXT],
+            ..filter()
+        };
+        assert_eq!(filter.file_skip(Path::new("r/lib.rs")), None);
+        assert_eq!(filter.file_skip(Path::new("r/notes.txt")), None);
+        assert_eq!(
+            filter.file_skip(Path::new("r/logo.png")),
+            Some(Skip::Type(ContentType::MEDIA))
+        );
+    }
+
+    #[test]
+    fn exclude_type_leaves_out_types() {
+        let filter = Filter {
+            exclude_types: vec![ContentType::MEDIA, ContentType::EXECUTABLE],
+            ..filter()
+        };
+        assert_eq!(filter.file_skip(Path::new("r/lib.rs")), None);
+        assert_eq!(
+            filter.file_skip(Path::new("r/logo.png")),
+            Some(Skip::Type(ContentType::MEDIA))
+        );
+        assert_eq!(
+            filter.file_skip(Path::new("r/build.sh")),
+            Some(Skip::Type(ContentType::EXECUTABLE))
+        );
+    }
+
+    #[test]
+    fn include_globs_match_file_names() {
+        let filter = Filter {
+            include: vec![
+                Pattern::new("*.rs").unwrap(),
+                Pattern::new("Cargo.*").unwrap(),
+            ],
+            ..filter()
+        };
+        assert_eq!(filter.file_skip(Path::new("r/src/main.rs")), None);
+        assert_eq!(filter.file_skip(Path::new("r/Cargo.toml")), None);
+        assert_eq!(
+            filter.file_skip(Path::new("r/README.md")),
+            Some(Skip::NotIncluded)
+        );
+    }
+
+    #[test]
+    fn include_globs_still_descend() {
+        let filter = Filter {
+            include: vec![Pattern::new("*.rs").unwrap()],
+            ..filter()
+        };
+        assert_eq!(filter.dir_skip(Path::new("r/docs")), None);
+    }
+
+    #[test]
+    fn non_utf8_names() {
+        let filter = filter();
+        let path = |bytes| Path::new("r").join(OsStr::from_bytes(bytes));
+        assert_eq!(filter.file_skip(&path(b"bad\xff.rs")), None);
+        assert_eq!(filter.file_skip(&path(b".bad\xff")), Some(Skip::Hidden));
+        assert_eq!(filter.dir_skip(&path(b"dir\xff")), None);
+
+        let included = Filter {
+            include: vec![Pattern::new("*.rs").unwrap()],
+            ..self::filter()
+        };
+        assert_eq!(
+            included.file_skip(&path(b"bad\xff.rs")),
+            Some(Skip::NotIncluded)
+        );
+    }
+
+    #[test]
+    fn dir_rules() {
+        let filter = Filter {
+            max_depth: Some(2),
+            preset_dirs: vec!["target"],
+            ..filter()
+        };
+        let skip = |path| filter.dir_skip(Path::new(path));
+        assert_eq!(skip("r/src"), None);
+        assert_eq!(skip("r/.git"), Some(Skip::Vcs));
+        assert_eq!(skip("r/.cache"), Some(Skip::Hidden));
+        assert_eq!(skip("r/target"), Some(Skip::Preset("target".to_string())));
+        assert_eq!(skip("r/src/x"), Some(Skip::TooDeep(2)));
+
+        //--include-vcs is enough for .git without --all.
+        let vcs = Filter {
+            include_vcs: true,
+            ..self::filter()
+        };
+        assert_eq!(vcs.dir_skip(Path::new("r/.git")), None);
+        assert_eq!(vcs.dir_skip(Path::new("r/.cache")), Some(Skip::Hidden));
+    }
+
+    #[test]
+    fn paths_need_every_directory() {
+        let filter = Filter {
+            roots: Vec::new(),
+            ..filter()
+        };
+        assert!(filter.includes_path(Path::new("src/main.rs")));
+        assert!(!filter.includes_path(Path::new(".github/build.rs")));
+        assert!(!filter.includes_path(Path::new("src/.git/config.rs")));
+        assert!(!filter.includes_path(Path::new("src/.env")));
+    }
+
+    #[test]
+    fn prioritized_files_and_dirs() {
+        let root = std::env::temp_dir().join(format!("lc-prioritize-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("tests")).unwrap();
+        let filter = Filter {
+            roots: vec![root.clone()],
+            prioritize: vec![
+                Pattern::new("src/**").unwrap(),
+                Pattern::new("*.md").unwrap(),
+            ],
+            ..Filter::default()
+        };
+        assert!(filter.is_prioritized(&root.join("src/main.rs")));
+        assert!(filter.is_prioritized(&root.join("README.md")));
+        assert!(!filter.is_prioritized(&root.join("tests/a.rs")));
+        //"src/**" could match beneath src/, so the directory itself goes first too.
+        assert!(filter.is_prioritized(&root.join("src")));
+        assert!(!filter.is_prioritized(&root.join("tests")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn linguist_attributes() {
+        let root = std::env::temp_dir().join(format!("lc-filter-test-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join(".gitattributes"),
+            "*.pb.go linguist-generated\nvendor/** linguist-vendored\n",
+        )
+        .unwrap();
+        let filter = Filter {
+            roots: vec![root.clone()],
+            attributes: vec![Attributes::load(&root)],
+            ..Filter::default()
+        };
+        assert_eq!(filter.file_skip(&root.join("main.go")), None);
+        assert_eq!(
+            filter.file_skip(&root.join("api/user.pb.go")),
+            Some(Skip::Linguist(Linguist::Generated))
+        );
+        assert_eq!(
+            filter.file_skip(&root.join("vendor/lib.c")),
+            Some(Skip::Linguist(Linguist::Vendored))
+        );
+        let generated = Filter {
+            include_generated: true,
+            ..filter
+        };
+        assert_eq!(generated.file_skip(&root.join("api/user.pb.go")), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
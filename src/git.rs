@@ -0,0 +1,279 @@
+use crate::budget;
+use std::io::{BufRead, BufReader, Error, Read, Result, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//a file as a revision has it, read out of the object database rather than the working tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Blob {
+    pub path: PathBuf,
+    pub id: String,
+}
+
+//every regular file in `rev`, by its path from the top of the repository. symlinks and submodules
+//are left out, the same as a walk that doesn't follow links.
+pub fn tree(rev: &str) -> Result<Vec<Blob>> {
+    let listing = git(Path::new(""), &["ls-tree", "-r", "-z", "--full-tree", rev])?;
+    Ok(listing
+        .split(|&byte| byte == 0)
+        .filter_map(|entry| parse_entry(&String::from_utf8_lossy(entry)))
+        .collect())
+}
+
+//every regular file staged in the index, as the next commit would have it.
+pub fn index() -> Result<Vec<Blob>> {
+    let listing = git(Path::new(""), &["ls-files", "--stage", "-z"])?;
+    Ok(listing
+        .split(|&byte| byte == 0)
+        .filter_map(|entry| parse_index_entry(&String::from_utf8_lossy(entry)))
+        .collect())
+}
+
+//`100644 <id> <stage>\t<path>`, conflicted paths have a stage other than 0 and aren't staged yet.
+fn parse_index_entry(entry: &str) -> Option<Blob> {
+    let (meta, path) = entry.split_once('\t')?;
+    let mut fields = meta.split(' ');
+    let mode = fields.next()?;
+    let id = fields.next()?;
+    let stage = fields.next()?;
+    (stage == "0" && mode.starts_with("100")).then(|| Blob {
+        path: PathBuf::from(path),
+        id: id.to_string(),
+    })
+}
+
+//`100644 blob <id>\t<path>`
+fn parse_entry(entry: &str) -> Option<Blob> {
+    let (meta, path) = entry.split_once('\t')?;
+    let mut fields = meta.split(' ');
+    let mode = fields.next()?;
+    let kind = fields.next()?;
+    let id = fields.next()?;
+    (kind == "blob" && mode != "120000").then(|| Blob {
+        path: PathBuf::from(path),
+        id: id.to_string(),
+    })
+}
+
+//how much blob content is held at once without --max-mem.
+const BATCH_LEN: usize = 64 << 20;
+
+//`git cat-file --batch` and the thread feeding it ids. however reading stops, the child is waited
+//on and the thread joined: one left early is killed first, so a writer stuck on a full pipe gets
+//an error instead of hanging.
+struct CatFile {
+    child: Child,
+    writer: Option<JoinHandle<Result<()>>>,
+}
+
+impl CatFile {
+    fn finish(mut self) -> Result<()> {
+        let written = self
+            .writer
+            .take()
+            .map_or(Ok(()), |writer| writer.join().unwrap());
+        self.child.wait()?;
+        written
+    }
+}
+
+impl Drop for CatFile {
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            let _ = self.child.kill();
+            let _ = writer.join();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+//the content of each blob, in the same order, handed to `each` a batch at a time along with the
+//blobs it's for. one `git cat-file --batch` serves all of them. a batch holds at most BATCH_LEN
+//bytes, or what --max-mem allows if that's less, with a memory permit per blob until `each` is
+//done with it, so a revision is never in memory all at once. a blob bigger than that is a batch
+//of its own.
+pub fn read_batches(blobs: &[Blob], mut each: impl FnMut(&[Blob], Vec<Vec<u8>>)) -> Result<()> {
+    let batch_len = budget::memory_limit().map_or(BATCH_LEN, |limit| limit.min(BATCH_LEN));
+    let mut child = Command::new("git")
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let ids = blobs.iter().map(|blob| blob.id.clone()).collect::<Vec<_>>();
+    //written from a thread so a full stdout pipe can't stall us writing ids into a full stdin.
+    let writer = thread::spawn(move || -> Result<()> {
+        for id in ids {
+            writeln!(stdin, "{id}")?;
+        }
+        Ok(())
+    });
+    let cat_file = CatFile {
+        child,
+        writer: Some(writer),
+    };
+
+    let mut contents = Vec::new();
+    let mut permits = Vec::new();
+    let (mut start, mut held) = (0, 0);
+    let mut header = String::new();
+    for (idx, blob) in blobs.iter().enumerate() {
+        header.clear();
+        stdout.read_line(&mut header)?;
+        //`<id> blob <size>`, or `<id> missing`.
+        let size = header
+            .trim_end()
+            .rsplit(' ')
+            .next()
+            .and_then(|size| size.parse::<usize>().ok())
+            .ok_or_else(|| Error::other(format!("can't read {}", blob.path.display())))?;
+        //the batch so far is counted and let go of before this one would take it over the limit,
+        //so the permits held never add up to more than the budget and waiting on one can't hang.
+        if held + size > batch_len && !contents.is_empty() {
+            each(&blobs[start..idx], std::mem::take(&mut contents));
+            permits.clear();
+            (start, held) = (idx, 0);
+        }
+        permits.push(budget::reserve_memory(size));
+        held += size;
+        let mut content = vec![0; size + 1];
+        stdout.read_exact(&mut content)?;
+        content.pop();
+        contents.push(content);
+    }
+    if !contents.is_empty() {
+        each(&blobs[start..], contents);
+    }
+    drop(permits);
+    cat_file.finish()
+}
+
+//`git blame --line-porcelain` of a file in the working tree, run from the file's own directory so
+//it can be anywhere in the repository.
+pub fn blame(path: &Path) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path.parent().unwrap_or(Path::new(".")))
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(path.file_name().unwrap_or_default())
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::other(format!("can't blame {}", path.display())));
+    }
+    Ok(output.stdout)
+}
+
+//`git log --numstat` of everything below `dir` since `since`, one `<added>\t<removed>\t<path>` per
+//file a commit touched, paths relative to `dir`. renames show up as a removal and an
+//addition, like they would to a walk.
+pub fn numstat(dir: &Path, since: SystemTime) -> Result<Vec<u8>> {
+    let since = since
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    git(
+        dir,
+        &[
+            "log",
+            "--numstat",
+            "--no-renames",
+            "--relative",
+            "--format=",
+            &format!("--since=@{since}"),
+            "--",
+            ".",
+        ],
+    )
+}
+
+//the commit checked out in the repository `dir` is in.
+pub fn head(dir: &Path) -> Result<String> {
+    let output = git(dir, &["rev-parse", "HEAD"])?;
+    Ok(String::from_utf8_lossy(&output).trim_end().to_string())
+}
+
+//the top of the repository we're in, what paths in a tree are relative to.
+pub fn toplevel() -> Result<PathBuf> {
+    let output = git(Path::new(""), &["rev-parse", "--show-toplevel"])?;
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output).trim_end().to_string(),
+    ))
+}
+
+//where `rev` branched off `base`, what a pull request is compared against.
+pub fn merge_base(base: &str, rev: &str) -> Result<String> {
+    let output = git(Path::new(""), &["merge-base", base, rev])?;
+    Ok(String::from_utf8_lossy(&output).trim_end().to_string())
+}
+
+//where git looks for the hooks of the repository we're in.
+pub fn hooks_dir() -> Result<PathBuf> {
+    let output = git(Path::new(""), &["rev-parse", "--git-path", "hooks"])?;
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output).trim_end().to_string(),
+    ))
+}
+
+//an empty `dir` is the current directory.
+fn git(dir: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::other(format!(
+            "git {}: {}",
+            args[0],
+            message.lines().next().unwrap_or_default()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Blob, parse_entry, parse_index_entry, read_batches};
+    use std::path::PathBuf;
+
+    #[test]
+    fn keeps_regular_files() {
+        assert_eq!(
+            parse_entry("100644 blob 3b18e51\tsrc/main.rs"),
+            Some(Blob {
+                path: PathBuf::from("src/main.rs"),
+                id: "3b18e51".into(),
+            })
+        );
+        assert_eq!(
+            parse_entry("100755 blob a1\tsome dir/run.sh").unwrap().path,
+            PathBuf::from("some dir/run.sh")
+        );
+        assert_eq!(parse_entry("120000 blob 9f2\tlink"), None);
+        assert_eq!(parse_entry("160000 commit 5c4\tvendor/lib"), None);
+        assert_eq!(
+            parse_index_entry("100644 3b18e51 0\tsrc/main.rs")
+                .unwrap()
+                .id,
+            "3b18e51"
+        );
+        assert_eq!(parse_index_entry("100644 3b18e51 2\tsrc/main.rs"), None);
+    }
+
+    #[test]
+    fn stops_at_a_missing_blob() {
+        //more ids than a pipe holds, so the writer is still blocked on them when reading stops.
+        let blobs = (0..100_000)
+            .map(|n| Blob {
+                path: PathBuf::from(format!("missing{n}")),
+                id: format!("{n:040x}"),
+            })
+            .collect::<Vec<_>>();
+        let mut batches = 0;
+        let read = read_batches(&blobs, |_, _| batches += 1);
+        assert_eq!(read.unwrap_err().to_string(), "can't read missing0");
+        assert_eq!(batches, 0);
+    }
+}
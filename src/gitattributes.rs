@@ -0,0 +1,121 @@
+use glob::{MatchOptions, Pattern};
+use std::fs;
+use std::path::Path;
+
+//the linguist attributes github goes by when it leaves files out of a repository's language
+//stats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Linguist {
+    Generated,
+    Vendored,
+}
+
+struct Rule {
+    pattern: Pattern,
+    //Some(false) for `-linguist-generated`, `!linguist-generated` or `=false`, which take back what
+    //an earlier line set.
+    generated: Option<bool>,
+    vendored: Option<bool>,
+}
+
+//the linguist-generated and linguist-vendored lines of a root's .gitattributes.
+#[derive(Default)]
+pub struct Attributes {
+    rules: Vec<Rule>,
+}
+
+impl Attributes {
+    //the .gitattributes at the top of `root`, nothing when there isn't one.
+    pub fn load(root: &Path) -> Attributes {
+        fs::read_to_string(root.join(".gitattributes"))
+            .map(|content| Attributes::parse(&content))
+            .unwrap_or_default()
+    }
+
+    fn parse(content: &str) -> Attributes {
+        let rules = content
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                if line.starts_with('#') {
+                    return None;
+                }
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                //like .gitignore, a pattern with a / is anchored at the root and one without
+                //matches a name at any depth. unlike it, matching a directory says nothing about
+                //the files inside, that takes `vendor/**`.
+                let glob = match pattern.strip_prefix('/') {
+                    Some(anchored) => anchored.to_string(),
+                    None if pattern.contains('/') => pattern.to_string(),
+                    None => format!("**/{pattern}"),
+                };
+                let mut rule = Rule {
+                    pattern: Pattern::new(&glob).ok()?,
+                    generated: None,
+                    vendored: None,
+                };
+                for attribute in fields {
+                    let (name, set) = match attribute.split_once('=') {
+                        Some((name, value)) => (name, Some(value != "false")),
+                        None => match attribute.strip_prefix(['-', '!']) {
+                            Some(name) => (name, Some(false)),
+                            None => (attribute, Some(true)),
+                        },
+                    };
+                    match name {
+                        "linguist-generated" => rule.generated = set,
+                        "linguist-vendored" => rule.vendored = set,
+                        _ => {}
+                    }
+                }
+                (rule.generated.is_some() || rule.vendored.is_some()).then_some(rule)
+            })
+            .collect();
+        Attributes { rules }
+    }
+
+    //what keeps a path relative to the root out of github's stats, if anything. for each attribute
+    //the last line that says something about it wins.
+    pub fn linguist(&self, relative: &Path) -> Option<Linguist> {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::default()
+        };
+        let last = |attribute: fn(&Rule) -> Option<bool>| {
+            self.rules
+                .iter()
+                .rev()
+                .filter(|rule| rule.pattern.matches_path_with(relative, options))
+                .find_map(attribute)
+                .unwrap_or(false)
+        };
+        if last(|rule| rule.generated) {
+            Some(Linguist::Generated)
+        } else if last(|rule| rule.vendored) {
+            Some(Linguist::Vendored)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Attributes, Linguist};
+    use std::path::Path;
+
+    #[test]
+    fn last_line_wins() {
+        let attributes = Attributes::parse(
+            "# stats\n*.pb.go linguist-generated=true\n/vendor/** linguist-vendored\nvendor/ours/** -linguist-vendored\n*.rs text eol=lf\n/api/*.json linguist-generated\n",
+        );
+        let linguist = |path| attributes.linguist(Path::new(path));
+        assert_eq!(linguist("proto/user.pb.go"), Some(Linguist::Generated));
+        assert_eq!(linguist("vendor/lib/a.c"), Some(Linguist::Vendored));
+        assert_eq!(linguist("vendor/ours/b.c"), None);
+        assert_eq!(linguist("src/main.rs"), None);
+        assert_eq!(linguist("api/v1/schema.json"), None);
+        assert_eq!(linguist("api/schema.json"), Some(Linguist::Generated));
+    }
+}
@@ -0,0 +1,49 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+//an extended grapheme cluster, the rules from unicode's uax #29 written out as a regex over the
+//Grapheme_Cluster_Break property: a base with whatever combines onto it, a hangul syllable, a
+//flag made of two regional indicators or an emoji zwj sequence.
+static GRAPHEME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?x)
+        \r\n
+      | [\r\n]
+      | \p{gcb=Control}
+      | \p{gcb=Prepend}*
+        (
+            \p{gcb=L}* (\p{gcb=V}+ | \p{gcb=LV} \p{gcb=V}* | \p{gcb=LVT}) \p{gcb=T}*
+          | \p{gcb=L}+
+          | \p{gcb=T}+
+          | \p{gcb=RI} \p{gcb=RI}
+          | \p{Extended_Pictographic} (\p{gcb=Extend}* \p{gcb=ZWJ} \p{Extended_Pictographic})*
+          | [^\p{gcb=Control}\r\n]
+        )
+        [\p{gcb=Extend}\p{gcb=ZWJ}\p{gcb=SpacingMark}]*
+        ",
+    )
+    .unwrap()
+});
+
+//--chars: unicode scalar values and what a reader would call characters. bytes that aren't
+//valid utf-8 count as one of each, the way they'd show up as a replacement character.
+pub fn count(content: &[u8]) -> (u128, u128) {
+    let text = String::from_utf8_lossy(content);
+    (
+        text.chars().count() as u128,
+        GRAPHEME.find_iter(&text).count() as u128,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::count;
+
+    #[test]
+    fn counts_scalars_and_graphemes() {
+        assert_eq!(count("e\u{301}x".as_bytes()), (3, 2));
+        assert_eq!(count("👨‍👩‍👧 🇩🇪".as_bytes()), (8, 3));
+        assert_eq!(count("한국어\r\n".as_bytes()), (5, 4));
+        assert_eq!(count(b"caf\xe9\n\n"), (6, 6));
+    }
+}
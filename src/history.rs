@@ -0,0 +1,494 @@
+use crate::{Counts, SPARKLINE_BARS, dates, format_byte_count, git};
+use colored::Colorize;
+use rusqlite::{Connection, TransactionBehavior, params};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+//how many rows and at most how many columns `lc history --graph` draws.
+const GRAPH_HEIGHT: usize = 8;
+const GRAPH_WIDTH: usize = 60;
+
+//what the whole run is recorded under, next to its top-level directories.
+pub const TOTAL: &str = ".";
+
+//how long a run waits for another one saving to the database at the same moment.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+//a row in runs for every recorded run, and a row in dirs for each directory it counted. roots is
+//what runs over the same paths are found by.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY,
+        time INTEGER NOT NULL,
+        sha TEXT NOT NULL,
+        roots TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS dirs (
+        run INTEGER NOT NULL REFERENCES runs(id),
+        dir TEXT NOT NULL,
+        files INTEGER NOT NULL,
+        lines INTEGER NOT NULL,
+        bytes INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS runs_by_roots ON runs(roots, time);
+";
+
+//files, lines and bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Totals {
+    pub files: u128,
+    pub lines: u128,
+    pub bytes: u128,
+}
+
+//one recorded run of lc over the same roots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub time: SystemTime,
+    pub commit: String,
+    //by top-level directory, TOTAL for everything.
+    pub dirs: BTreeMap<String, Totals>,
+}
+
+impl Run {
+    pub fn total(&self) -> Totals {
+        self.dirs.get(TOTAL).copied().unwrap_or_default()
+    }
+}
+
+//--record: totals per top-level directory collected during a run, saved to the history database
+//afterwards.
+#[derive(Default)]
+pub struct History {
+    dirs: Mutex<BTreeMap<String, Totals>>,
+}
+
+impl History {
+    pub fn record(&self, relative: &Path, counts: &Counts) {
+        let mut dirs = self.dirs.lock().unwrap();
+        let mut add = |dir: String| {
+            let totals = dirs.entry(dir).or_default();
+            totals.files += 1;
+            totals.lines += counts.lines;
+            totals.bytes += counts.bytes;
+        };
+        add(TOTAL.to_string());
+        //files right in a root only count towards the total.
+        let mut components = relative.components();
+        if let Some(top) = components.next()
+            && components.next().is_some()
+        {
+            add(top.as_os_str().to_string_lossy().into_owned());
+        }
+    }
+
+    //with --record, how this run compares with the last one recorded over the same roots, a line
+    //to go under the totals. nothing the first time.
+    pub fn since_last_run(&self, roots: &[PathBuf]) -> Option<String> {
+        let last = load(roots).pop()?;
+        let lines = self.dirs.lock().unwrap().get(TOTAL).copied()?.lines;
+        let elapsed = SystemTime::now()
+            .duration_since(last.time)
+            .unwrap_or_default();
+        Some(format!(
+            "{} L since last run ({})",
+            group_digits(lines as i128 - last.total().lines as i128),
+            dates::format_ago(elapsed)
+        ))
+    }
+
+    pub fn save(&self, roots: &[PathBuf]) -> Result<()> {
+        let key = key(roots);
+        let commit = roots
+            .first()
+            .and_then(|root| git::head(root).ok())
+            .unwrap_or_else(|| "-".to_string());
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let path = location();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        open(&path)
+            .and_then(|mut db| insert(&mut db, &key, &commit, time, &self.dirs.lock().unwrap()))
+            .map_err(io::Error::other)
+    }
+}
+
+fn open(path: &Path) -> rusqlite::Result<Connection> {
+    let db = Connection::open(path)?;
+    db.busy_timeout(BUSY_TIMEOUT)?;
+    db.execute_batch(SCHEMA)?;
+    Ok(db)
+}
+
+//one run and its directories in a single transaction, so runs saved at the same time can't get
+//mixed up with each other.
+fn insert(
+    db: &mut Connection,
+    key: &str,
+    commit: &str,
+    time: u64,
+    dirs: &BTreeMap<String, Totals>,
+) -> rusqlite::Result<()> {
+    let tx = db.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    tx.execute(
+        "INSERT INTO runs (time, sha, roots) VALUES (?1, ?2, ?3)",
+        params![sql_int(time.into()), commit, key],
+    )?;
+    let run = tx.last_insert_rowid();
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO dirs (run, dir, files, lines, bytes) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (dir, totals) in dirs {
+            insert.execute(params![
+                run,
+                dir,
+                sql_int(totals.files),
+                sql_int(totals.lines),
+                sql_int(totals.bytes)
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+//sqlite integers are 64-bit and signed.
+fn sql_int(n: u128) -> i64 {
+    i64::try_from(n).unwrap_or(i64::MAX)
+}
+
+//+1,204 or -56, the sign always shown.
+fn group_digits(delta: i128) -> String {
+    let digits = delta.unsigned_abs().to_string();
+    let mut grouped = String::new();
+    for (at, digit) in digits.chars().enumerate() {
+        if at > 0 && (digits.len() - at).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{}{grouped}", if delta < 0 { '-' } else { '+' })
+}
+
+//$XDG_DATA_HOME/lc/history.db, which is ~/.local/share/lc/history.db by default.
+pub fn location() -> PathBuf {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .unwrap_or_default()
+        .join("lc/history.db")
+}
+
+//runs are told apart by the roots they counted, with their full paths so it doesn't matter where
+//lc was run from.
+fn key(roots: &[PathBuf]) -> String {
+    roots
+        .iter()
+        .map(|root| {
+            fs::canonicalize(root)
+                .unwrap_or_else(|_| root.clone())
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+//every run recorded over these roots, oldest first. none when nothing has been recorded yet, or
+//the database can't be read.
+pub fn load(roots: &[PathBuf]) -> Vec<Run> {
+    let path = location();
+    if !path.exists() {
+        return Vec::new();
+    }
+    open(&path)
+        .and_then(|db| select(&db, &key(roots)))
+        .unwrap_or_default()
+}
+
+fn select(db: &Connection, key: &str) -> rusqlite::Result<Vec<Run>> {
+    let mut query = db.prepare(
+        "SELECT runs.id, runs.time, runs.sha, dirs.dir, dirs.files, dirs.lines, dirs.bytes
+         FROM runs JOIN dirs ON dirs.run = runs.id
+         WHERE runs.roots = ?1
+         ORDER BY runs.time, runs.id",
+    )?;
+    let mut rows = query.query([key])?;
+    let mut runs: Vec<(i64, Run)> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id = row.get::<_, i64>(0)?;
+        if runs.last().is_none_or(|(last, _)| *last != id) {
+            runs.push((
+                id,
+                Run {
+                    time: UNIX_EPOCH + Duration::from_secs(row.get::<_, i64>(1)?.max(0) as u64),
+                    commit: row.get(2)?,
+                    dirs: BTreeMap::new(),
+                },
+            ));
+        }
+        let count = |at| Ok::<_, rusqlite::Error>(row.get::<_, i64>(at)?.max(0) as u128);
+        runs.last_mut().unwrap().1.dirs.insert(
+            row.get(3)?,
+            Totals {
+                files: count(4)?,
+                lines: count(5)?,
+                bytes: count(6)?,
+            },
+        );
+    }
+    Ok(runs.into_iter().map(|(_, run)| run).collect())
+}
+
+//`lc history`: the recorded runs over these roots, or one top-level directory of them.
+pub fn run(roots: &[PathBuf], dir: Option<&str>, graph: bool) -> Result<()> {
+    let runs = load(roots);
+    if runs.is_empty() {
+        println!(
+            "no runs of {} recorded yet, count with --record to start",
+            key(roots)
+        );
+        return Ok(());
+    }
+
+    let dir = dir.unwrap_or(TOTAL);
+    if graph {
+        print_graph(&runs, dir);
+        return Ok(());
+    }
+    println!(
+        "{}",
+        format!(
+            "{:<18}{:<10}{:>8}{:>12}{:>12}{:>16}",
+            "Date", "Commit", "Files", "Lines", "Change", "Bytes"
+        )
+        .bold()
+    );
+    let mut previous: Option<u128> = None;
+    for run in &runs {
+        let Some(totals) = run.dirs.get(dir) else {
+            continue;
+        };
+        let change = previous.map_or(String::new(), |previous| {
+            format!("{:+}", totals.lines as i128 - previous as i128)
+        });
+        println!(
+            "{:<18}{:<10}{:>8}{:>12}{change:>12}{:>16}",
+            dates::format_time(run.time),
+            &run.commit[..run.commit.len().min(8)],
+            totals.files,
+            totals.lines,
+            format_byte_count(totals.bytes)
+        );
+        previous = Some(totals.lines);
+    }
+    Ok(())
+}
+
+//--graph: lines over the recorded runs as a chart, one column per run (or per group of runs when
+//there are more than fit), scaled between the smallest and largest count so growth shows.
+fn print_graph(runs: &[Run], dir: &str) {
+    let points = runs
+        .iter()
+        .filter_map(|run| Some((run.time, run.dirs.get(dir)?.lines)))
+        .collect::<Vec<_>>();
+    let Some((&(first, _), &(last, _))) = points.first().zip(points.last()) else {
+        println!("no recorded run has a {dir} directory");
+        return;
+    };
+    let lines = resample(
+        &points.iter().map(|&(_, lines)| lines).collect::<Vec<_>>(),
+        GRAPH_WIDTH,
+    );
+    let (min, max) = (
+        lines.iter().min().copied().unwrap_or_default(),
+        lines.iter().max().copied().unwrap_or_default(),
+    );
+
+    println!("{}", format!("Lines over {} runs", points.len()).bold());
+    let label_width = max.to_string().len();
+    for (row, bars) in chart(&lines).iter().enumerate() {
+        let label = match row {
+            0 => max.to_string(),
+            _ if row == GRAPH_HEIGHT - 1 => min.to_string(),
+            _ => String::new(),
+        };
+        println!("{label:>label_width$} │{bars}");
+    }
+    println!("{:>label_width$} └{}", "", "─".repeat(lines.len()));
+    println!(
+        "{:>label_width$}  {} to {}",
+        "",
+        dates::format_time(first),
+        dates::format_time(last)
+    );
+}
+
+//at most `width` values, with more than that each one stands for the last of its share.
+fn resample(values: &[u128], width: usize) -> Vec<u128> {
+    let columns = values.len().min(width);
+    (0..columns)
+        .map(|column| values[((column + 1) * values.len()).div_ceil(columns) - 1])
+        .collect()
+}
+
+//the rows of the chart, top first. each column is filled up to its value in eighths of a row.
+fn chart(values: &[u128]) -> Vec<String> {
+    let (min, max) = (
+        values.iter().min().copied().unwrap_or_default(),
+        values.iter().max().copied().unwrap_or_default(),
+    );
+    let eighths = GRAPH_HEIGHT * 8;
+    //the smallest value still gets a sliver, so every run shows up.
+    let levels = values
+        .iter()
+        .map(|&value| match max - min {
+            0 => eighths / 2,
+            range => 1 + ((value - min) * (eighths as u128 - 1) / range) as usize,
+        })
+        .collect::<Vec<_>>();
+
+    (0..GRAPH_HEIGHT)
+        .map(|row| {
+            let base = (GRAPH_HEIGHT - 1 - row) * 8;
+            levels
+                .iter()
+                .map(|&level| match level.saturating_sub(base).min(8) {
+                    0 => ' ',
+                    fill => SPARKLINE_BARS[fill - 1],
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GRAPH_HEIGHT, History, TOTAL, Totals, chart, group_digits, insert, open, resample, select,
+    };
+    use crate::Counts;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn database(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lc-history-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("history.db")
+    }
+
+    fn dirs(rows: &[(&str, u128)]) -> BTreeMap<String, Totals> {
+        rows.iter()
+            .map(|&(dir, lines)| {
+                let totals = Totals {
+                    files: 1,
+                    lines,
+                    bytes: lines * 10,
+                };
+                (dir.to_string(), totals)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_runs_by_roots() {
+        let path = database("roots");
+        let mut db = open(&path).unwrap();
+        insert(
+            &mut db,
+            "/repo",
+            "abc",
+            100,
+            &dirs(&[(TOTAL, 30), ("src", 20)]),
+        )
+        .unwrap();
+        insert(&mut db, "/other", "-", 100, &dirs(&[(TOTAL, 1)])).unwrap();
+        insert(&mut db, "/repo", "def", 200, &dirs(&[(TOTAL, 45)])).unwrap();
+
+        let runs = select(&db, "/repo").unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].dirs.len(), 2);
+        assert_eq!(runs[1].commit, "def");
+        assert_eq!(runs[1].total().lines, 45);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn runs_not_led_by_their_total() {
+        let path = database("order");
+        let mut db = open(&path).unwrap();
+        //"#gen" sorts ahead of the total, and a run at the same second keeps its own rows.
+        insert(
+            &mut db,
+            "/repo",
+            "abc",
+            100,
+            &dirs(&[("#gen", 5), (TOTAL, 30)]),
+        )
+        .unwrap();
+        insert(
+            &mut db,
+            "/repo",
+            "def",
+            100,
+            &dirs(&[("src", 20), (TOTAL, 40)]),
+        )
+        .unwrap();
+
+        let runs = select(&db, "/repo").unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].commit, "abc");
+        assert_eq!(runs[0].dirs.keys().collect::<Vec<_>>(), ["#gen", TOTAL]);
+        assert_eq!(runs[1].dirs.keys().collect::<Vec<_>>(), [TOTAL, "src"]);
+        assert_eq!(runs[1].total().lines, 40);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn charts_between_min_and_max() {
+        let rows = chart(&[100, 150, 200]);
+        assert_eq!(rows.len(), GRAPH_HEIGHT);
+        assert_eq!(rows[0], "  █");
+        assert_eq!(rows[GRAPH_HEIGHT - 1], "▁██");
+        assert_eq!(resample(&[100, 150, 200, 300, 400], 2), [200, 400]);
+    }
+
+    #[test]
+    fn signed_and_grouped_changes() {
+        assert_eq!(group_digits(1204), "+1,204");
+        assert_eq!(group_digits(-1_000_000), "-1,000,000");
+        assert_eq!(group_digits(0), "+0");
+    }
+
+    #[test]
+    fn records_top_level_dirs() {
+        let history = History::default();
+        let counts = Counts {
+            lines: 10,
+            bytes: 100,
+            ..Counts::default()
+        };
+        history.record(Path::new("src/main.rs"), &counts);
+        history.record(Path::new("README.md"), &counts);
+        let dirs = history.dirs.lock().unwrap();
+        assert_eq!(dirs.keys().collect::<Vec<_>>(), [TOTAL, "src"]);
+        assert_eq!(
+            dirs["src"],
+            Totals {
+                files: 1,
+                lines: 10,
+                bytes: 100
+            }
+        );
+    }
+}
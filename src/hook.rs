@@ -0,0 +1,108 @@
+use crate::budget_file::BUDGETS_FILE;
+use crate::diff;
+use crate::{Scan, git};
+use colored::Colorize;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::fs::PermissionsExt;
+
+//the hooks install-hook writes, by the name git runs them under.
+pub const HOOKS: [&str; 2] = ["pre-commit", "pre-push"];
+
+//the line that marks a hook as lc's own, so installing again can replace it.
+const MARKER: &str = "# installed by lc install-hook";
+
+//`lc hook pre-commit`: what the staged changes do to the line count, compared with HEAD. meant to
+//be run from .git/hooks/pre-commit, so it's one line, and with --max-lines a commit that grows the
+//tree by more than that is turned down.
+pub fn pre_commit(max_lines: Option<u128>, scan: &Scan) -> Result<()> {
+    let staged = git::index()?;
+    //the first commit has nothing to compare with.
+    let head = git::tree("HEAD").unwrap_or_default();
+    let (old, new) = diff::count_changed(head, staged, scan)?;
+    let total = diff::diff(&old, &new, |_| String::new())
+        .into_values()
+        .next()
+        .unwrap_or_default();
+
+    let net = total.net_lines();
+    println!(
+        "lc: {} {} lines (net {net:+}) in {} files",
+        format!("+{}", total.added_lines).green(),
+        format!("-{}", total.removed_lines).red(),
+        old.keys()
+            .chain(new.keys().filter(|path| !old.contains_key(*path)))
+            .count()
+    );
+    if let Some(max) = max_lines
+        && net > max as i128
+    {
+        eprintln!(
+            "{} this commit adds {net} lines, over the limit of {max} (commit with --no-verify to go ahead anyway)",
+            "error:".red().bold()
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+//`lc install-hook <hook>`: writes the script git runs into the repository's hooks directory, which
+//is .git/hooks unless core.hooksPath says otherwise. a hook lc didn't write is left alone unless
+//`force` is set.
+pub fn install(hook: &str, max_lines: Option<u128>, force: bool) -> Result<()> {
+    let path = git::hooks_dir()?.join(hook);
+    if let Ok(existing) = fs::read_to_string(&path)
+        && !existing.contains(MARKER)
+        && !force
+    {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!(
+                "{} is already there, use --force to replace it",
+                path.display()
+            ),
+        ));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, script(hook, max_lines))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    println!("installed {}", path.display());
+    Ok(())
+}
+
+//pre-commit reports (and with --max-lines limits) what the staged changes add, pre-push holds the
+//tree to lc-budgets.toml, with growth measured from what the remote already has.
+fn script(hook: &str, max_lines: Option<u128>) -> String {
+    let run = match hook {
+        "pre-push" => format!(
+            "[ -f {BUDGETS_FILE} ] || exit 0\n\
+             base=$(git rev-parse --verify --quiet '@{{upstream}}' 2>/dev/null || echo HEAD)\n\
+             exec lc check --base \"$base\"\n"
+        ),
+        _ => match max_lines {
+            Some(max) => format!("exec lc hook pre-commit --max-lines {max}\n"),
+            None => "exec lc hook pre-commit\n".to_string(),
+        },
+    };
+    format!("#!/bin/sh\n{MARKER}, run it again to update this file.\n{run}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MARKER, script};
+
+    #[test]
+    fn writes_hook_scripts() {
+        assert_eq!(
+            script("pre-commit", Some(500)),
+            format!(
+                "#!/bin/sh\n{MARKER}, run it again to update this file.\nexec lc hook pre-commit --max-lines 500\n"
+            )
+        );
+        let pre_push = script("pre-push", None);
+        assert!(pre_push.contains("[ -f lc-budgets.toml ] || exit 0\n"));
+        assert!(pre_push.ends_with("exec lc check --base \"$base\"\n"));
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+//--dupes: files with exactly the same content, vendored copies and generated files checked in
+//twice. unlike dup.rs this is whole files, byte for byte.
+#[derive(Default)]
+pub struct IdenticalFiles {
+    //(length, hash) of the content -> every file that has it.
+    copies: Mutex<HashMap<(usize, u64), CopySet>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopySet {
+    pub paths: Vec<PathBuf>,
+    //what one copy counts for.
+    pub lines: u128,
+    pub bytes: u128,
+}
+
+impl CopySet {
+    //everything past the first copy.
+    pub fn wasted(&self) -> (u128, u128) {
+        let extra = self.paths.len() as u128 - 1;
+        (self.lines * extra, self.bytes * extra)
+    }
+}
+
+impl IdenticalFiles {
+    //true for the first file with this content, false for a copy of one already seen. a path that
+    //was added before (--verify-parallel counts files twice) gets the same answer as the first time.
+    pub fn add(&self, path: &Path, content: &[u8], lines: u128, bytes: u128) -> bool {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let mut copies = self.copies.lock().unwrap();
+        let set = copies
+            .entry((content.len(), hasher.finish()))
+            .or_insert_with(|| CopySet {
+                paths: Vec::new(),
+                lines,
+                bytes,
+            });
+        if let Some(seen) = set.paths.iter().position(|seen| seen == path) {
+            return seen == 0;
+        }
+        set.paths.push(path.to_path_buf());
+        set.paths.len() == 1
+    }
+
+    //the contents more than one file has, the most wasteful first.
+    pub fn sets(&self) -> Vec<CopySet> {
+        let mut sets = self
+            .copies
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|set| set.paths.len() > 1)
+            .cloned()
+            .collect::<Vec<_>>();
+        for set in &mut sets {
+            set.paths.sort();
+        }
+        sets.sort_by(|a, b| b.wasted().1.cmp(&a.wasted().1).then(a.paths.cmp(&b.paths)));
+        sets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdenticalFiles;
+    use std::path::Path;
+
+    #[test]
+    fn groups_identical_files() {
+        let dupes = IdenticalFiles::default();
+        assert!(dupes.add(Path::new("b/LICENSE"), b"MIT\n", 1, 4));
+        assert!(dupes.add(Path::new("x.rs"), b"fn x() {}\n", 1, 10));
+        assert!(!dupes.add(Path::new("a/LICENSE"), b"MIT\n", 1, 4));
+        assert!(!dupes.add(Path::new("c/LICENSE"), b"MIT\n", 1, 4));
+        assert!(dupes.add(Path::new("b/LICENSE"), b"MIT\n", 1, 4));
+        assert!(!dupes.add(Path::new("a/LICENSE"), b"MIT\n", 1, 4));
+
+        let sets = dupes.sets();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(
+            sets[0].paths,
+            [
+                Path::new("a/LICENSE"),
+                Path::new("b/LICENSE"),
+                Path::new("c/LICENSE")
+            ]
+        );
+        assert_eq!(sets[0].wasted(), (2, 8));
+    }
+}
@@ -0,0 +1,47 @@
+use std::io::{Error, ErrorKind, Result};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+//how far the run got, for the note printed when it's cut short.
+static FILES: AtomicU64 = AtomicU64::new(0);
+static DIRS: AtomicU64 = AtomicU64::new(0);
+
+//the first Ctrl-C asks the walk to wind down: no more directories get listed or files opened, and
+//whatever was counted so far is printed as usual. a second one exits right away.
+pub fn install() {
+    let handler = on_sigint as extern "C" fn(libc::c_int);
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+extern "C" fn on_sigint(_: libc::c_int) {
+    //only async-signal-safe calls in here: an atomic swap and _exit.
+    if REQUESTED.swap(true, Ordering::Relaxed) {
+        unsafe { libc::_exit(130) };
+    }
+}
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+//for the spots that would start on something new, the error makes the walks leave it out.
+pub fn check() -> Result<()> {
+    if requested() {
+        return Err(Error::from(ErrorKind::Interrupted));
+    }
+    Ok(())
+}
+
+pub fn record_file() {
+    FILES.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_dir() {
+    DIRS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn progress() -> (u64, u64) {
+    (FILES.load(Ordering::Relaxed), DIRS.load(Ordering::Relaxed))
+}
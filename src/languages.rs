@@ -0,0 +1,382 @@
+use crate::fd;
+use std::io::Read;
+use std::path::Path;
+use std::sync::OnceLock;
+
+//from languages.toml, see user_languages.rs. looked through before the built in ones so they can
+//take an extension over.
+static USER: OnceLock<Vec<Language>> = OnceLock::new();
+
+//how much of an extensionless file is read looking for a #! line.
+const SHEBANG_LEN: usize = 128;
+
+//what it takes to tell a language's comments from its code. strings are only recognized when
+//they're double quoted, so a comment marker inside single quotes still starts a comment. doc
+//comments are told apart from the rest for languages that have them (rust's /// and //!).
+pub struct Language {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    pub file_names: &'static [&'static str],
+    pub line_comments: &'static [&'static str],
+    pub block_comments: &'static [(&'static str, &'static str)],
+    pub doc_comments: &'static [&'static str],
+    pub doc_blocks: &'static [(&'static str, &'static str)],
+}
+
+const C_LINE: &[&str] = &["//"];
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+const HASH: &[&str] = &["#"];
+const NONE: &[&str] = &[];
+const NO_BLOCK: &[(&str, &str)] = &[];
+const MARKUP_BLOCK: &[(&str, &str)] = &[("<!--", "-->")];
+
+macro_rules! language {
+    ($name:expr, [$($ext:expr),*], $line:expr, $block:expr) => {
+        language!($name, [$($ext),*], [], $line, $block)
+    };
+    ($name:expr, [$($ext:expr),*], [$($file:expr),*], $line:expr, $block:expr) => {
+        Language {
+            name: $name,
+            extensions: &[$($ext),*],
+            file_names: &[$($file),*],
+            line_comments: $line,
+            block_comments: $block,
+            doc_comments: &[],
+            doc_blocks: &[],
+        }
+    };
+}
+
+pub const LANGUAGES: &[Language] = &[
+    Language {
+        doc_comments: &["///", "//!"],
+        doc_blocks: &[("/**", "*/"), ("/*!", "*/")],
+        ..language!("Rust", ["rs"], C_LINE, C_BLOCK)
+    },
+    language!("C", ["c", "h"], C_LINE, C_BLOCK),
+    language!(
+        "C++",
+        ["cpp", "hpp", "cc", "cxx", "hh", "hxx"],
+        C_LINE,
+        C_BLOCK
+    ),
+    language!("C#", ["cs"], C_LINE, C_BLOCK),
+    language!("Java", ["java"], C_LINE, C_BLOCK),
+    language!("Kotlin", ["kt", "kts"], C_LINE, C_BLOCK),
+    language!("JavaScript", ["js", "jsx", "mjs", "cjs"], C_LINE, C_BLOCK),
+    language!("TypeScript", ["ts", "tsx"], C_LINE, C_BLOCK),
+    language!("Go", ["go"], C_LINE, C_BLOCK),
+    language!("Swift", ["swift"], C_LINE, C_BLOCK),
+    language!("Dart", ["dart"], C_LINE, C_BLOCK),
+    language!("Scala", ["scala"], C_LINE, C_BLOCK),
+    language!("PHP", ["php"], &["//", "#"], C_BLOCK),
+    language!("CSS", ["css"], NONE, C_BLOCK),
+    language!("SCSS", ["scss", "sass"], C_LINE, C_BLOCK),
+    language!("Python", ["py"], HASH, &[("\"\"\"", "\"\"\"")]),
+    language!("Ruby", ["rb"], HASH, &[("=begin", "=end")]),
+    language!("Perl", ["pl", "pm"], HASH, NO_BLOCK),
+    language!("R", ["r"], HASH, NO_BLOCK),
+    language!("Shell", ["sh", "bash", "zsh"], HASH, NO_BLOCK),
+    language!("PowerShell", ["ps1", "psm1", "psd1"], HASH, &[("<#", "#>")]),
+    language!("Batch", ["bat", "cmd"], &["REM", "rem", "::"], NO_BLOCK),
+    language!("Lua", ["lua"], &["--"], &[("--[[", "]]")]),
+    language!("SQL", ["sql"], &["--"], C_BLOCK),
+    language!("HTML", ["html", "htm", "xhtml", "erb"], NONE, MARKUP_BLOCK),
+    language!("XML", ["xml", "svg"], NONE, MARKUP_BLOCK),
+    language!("Markdown", ["md"], NONE, MARKUP_BLOCK),
+    language!("reStructuredText", ["rst"], NONE, NO_BLOCK),
+    language!("JSON", ["json"], NONE, NO_BLOCK),
+    //the cells of one, see notebook.rs. code cells are python unless the kernel says otherwise.
+    language!("Jupyter Notebook", ["ipynb"], HASH, &[("\"\"\"", "\"\"\"")]),
+    language!("YAML", ["yaml", "yml"], HASH, NO_BLOCK),
+    language!("TOML", ["toml"], HASH, NO_BLOCK),
+    language!("INI", ["ini", "cfg", "env"], &["#", ";"], NO_BLOCK),
+    language!(
+        "Makefile",
+        ["mk"],
+        ["Makefile", "makefile", "GNUmakefile"],
+        HASH,
+        NO_BLOCK
+    ),
+    language!("CMake", ["cmake"], ["CMakeLists.txt"], HASH, NO_BLOCK),
+    language!("Dockerfile", ["dockerfile"], ["Dockerfile"], HASH, NO_BLOCK),
+];
+
+//the program a #! line runs -> the language it's written in. version suffixes are dropped first,
+//so python3 and python3.12 are both python.
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "Python"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("zsh", "Shell"),
+    ("dash", "Shell"),
+    ("ksh", "Shell"),
+    ("node", "JavaScript"),
+    ("deno", "TypeScript"),
+    ("ruby", "Ruby"),
+    ("perl", "Perl"),
+    ("php", "PHP"),
+    ("lua", "Lua"),
+    ("pwsh", "PowerShell"),
+    ("Rscript", "R"),
+    ("make", "Makefile"),
+];
+
+//by file name first (Makefile, CMakeLists.txt), then by extension. scripts like `build` or
+//`deploy` have neither, so a file without an extension gets its first line read for a #!.
+pub fn detect(path: &Path) -> Option<&'static Language> {
+    detect_by_name(path).or_else(|| {
+        if path.extension().is_some() {
+            return None;
+        }
+        let mut head = [0; SHEBANG_LEN];
+        let read = fd::open(path).ok()?.read(&mut head).ok()?;
+        from_shebang(&head[..read])
+    })
+}
+
+//the same, for when the content has been read already.
+pub fn detect_in(path: &Path, content: &[u8]) -> Option<&'static Language> {
+    detect_by_name(path).or_else(|| {
+        path.extension()
+            .is_none()
+            .then(|| from_shebang(content))
+            .flatten()
+    })
+}
+
+pub fn add_user(languages: Vec<Language>) {
+    let _ = USER.set(languages);
+}
+
+fn all() -> impl Iterator<Item = &'static Language> {
+    USER.get().into_iter().flatten().chain(LANGUAGES)
+}
+
+//a language as a notebook kernel names it, "python" or "R".
+pub fn by_name(name: &str) -> Option<&'static Language> {
+    all().find(|lang| lang.name.eq_ignore_ascii_case(name))
+}
+
+pub fn detect_by_name(path: &Path) -> Option<&'static Language> {
+    let name = path.file_name()?.to_str()?;
+    if let Some(language) = all().find(|lang| lang.file_names.contains(&name)) {
+        return Some(language);
+    }
+    let ext = path.extension()?.to_str()?;
+    all().find(|lang| lang.extensions.contains(&ext))
+}
+
+//`#!/usr/bin/python3`, `#!/bin/sh -e` or `#!/usr/bin/env -S node --flags`.
+fn from_shebang(head: &[u8]) -> Option<&'static Language> {
+    let line = head
+        .strip_prefix(b"#!")?
+        .split(|&byte| byte == b'\n')
+        .next()?;
+    let mut words = std::str::from_utf8(line).ok()?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    let (_, name) = INTERPRETERS.iter().find(|(known, _)| *known == program)?;
+    all().find(|lang| lang.name == *name)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Breakdown {
+    pub code: u128,
+    pub comments: u128,
+    pub docs: u128,
+    pub blanks: u128,
+}
+
+//every line is exactly one of the three, the same way cloc and tokei see it: a line with any code
+//on it is code, even with a comment after it; a line with nothing but comment is a comment; a
+//line with nothing but whitespace is blank, even inside a block comment. a doc comment line is a
+//doc instead of a comment.
+pub fn classify(content: &[u8], language: &Language) -> Breakdown {
+    let mut breakdown = Breakdown::default();
+    //the end of the block comment a line starts in, and whether it's a doc comment.
+    let mut open_block: Option<(&str, bool)> = None;
+
+    for line in content.split(|&byte| byte == b'\n') {
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            breakdown.blanks += 1;
+            continue;
+        }
+
+        let (mut has_code, mut has_comment, mut has_doc) = (false, false, false);
+        let mut rest = line;
+        while !rest.is_empty() {
+            if let Some((end, doc)) = open_block {
+                if doc {
+                    has_doc = true;
+                } else {
+                    has_comment = true;
+                }
+                match find(rest, end) {
+                    Some(idx) => {
+                        rest = &rest[idx + end.len()..];
+                        open_block = None;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+
+            rest = rest.trim_ascii_start();
+            if rest.is_empty() {
+                break;
+            }
+            //doc comments before plain ones, /// would pass for a // line comment. block comments
+            //before line comments, lua's --[[ would pass for a -- line comment.
+            if let Some(&(start, end)) = language
+                .doc_blocks
+                .iter()
+                .find(|(start, _)| is_doc(rest, start))
+            {
+                rest = &rest[start.len()..];
+                open_block = Some((end, true));
+                has_doc = true;
+                continue;
+            }
+            if let Some(&(start, end)) = language
+                .block_comments
+                .iter()
+                .find(|(start, _)| rest.starts_with(start.as_bytes()))
+            {
+                rest = &rest[start.len()..];
+                open_block = Some((end, false));
+                has_comment = true;
+                continue;
+            }
+            if language
+                .doc_comments
+                .iter()
+                .any(|start| is_doc(rest, start))
+            {
+                has_doc = true;
+                break;
+            }
+            if language
+                .line_comments
+                .iter()
+                .any(|start| rest.starts_with(start.as_bytes()))
+            {
+                has_comment = true;
+                break;
+            }
+
+            has_code = true;
+            rest = skip_token(rest);
+        }
+
+        if has_code {
+            breakdown.code += 1;
+        } else if has_doc {
+            breakdown.docs += 1;
+        } else if has_comment {
+            breakdown.comments += 1;
+        }
+    }
+
+    //a trailing newline doesn't start another (blank) line, same as str::lines.
+    if content.last().is_none_or(|&last| last == b'\n') {
+        breakdown.blanks -= 1;
+    }
+    breakdown
+}
+
+//past one character of code, or a whole double quoted string so comment markers inside it
+//don't count.
+fn skip_token(rest: &[u8]) -> &[u8] {
+    if rest[0] != b'"' {
+        return &rest[1..];
+    }
+    let mut i = 1;
+    while i < rest.len() {
+        match rest[i] {
+            b'\\' => i += 2,
+            b'"' => return &rest[i + 1..],
+            _ => i += 1,
+        }
+    }
+    &[]
+}
+
+//`////` and `/***` are plain comments again, and `/**/` is an empty one.
+fn is_doc(rest: &[u8], marker: &str) -> bool {
+    let last = marker.as_bytes()[marker.len() - 1];
+    rest.starts_with(marker.as_bytes())
+        && rest
+            .get(marker.len())
+            .is_none_or(|&next| next != b'/' && next != last)
+}
+
+fn find(haystack: &[u8], needle: &str) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Breakdown, classify, detect, from_shebang};
+    use std::path::Path;
+
+    #[test]
+    fn splits_code_comments_and_blanks() {
+        let rust = detect(Path::new("src/main.rs")).unwrap();
+        let source = b"//! docs\nfn main() { // trailing\n\n    let s = \"/* not a comment\";\n    /* block\n\n    still */ let x = 1;\n}\n";
+        assert_eq!(
+            classify(source, rust),
+            Breakdown {
+                code: 4,
+                comments: 1,
+                docs: 1,
+                blanks: 2,
+            }
+        );
+        assert_eq!(
+            classify(
+                b"/// doc\n//// plain\n/** doc\n  more */\n/**/ fn f() {}\n",
+                rust
+            ),
+            Breakdown {
+                code: 1,
+                comments: 1,
+                docs: 3,
+                blanks: 0,
+            }
+        );
+
+        let python = detect(Path::new("build.py")).unwrap();
+        assert_eq!(
+            classify(b"# setup\nimport os\n\n\"\"\"\nmodule docs\n\"\"\"", python),
+            Breakdown {
+                code: 1,
+                comments: 4,
+                docs: 0,
+                blanks: 1,
+            }
+        );
+        assert_eq!(detect(Path::new("Makefile")).unwrap().name, "Makefile");
+    }
+
+    #[test]
+    fn detects_shebangs() {
+        let name = |head: &[u8]| from_shebang(head).map(|lang| lang.name);
+        assert_eq!(name(b"#!/usr/bin/env python3\nimport os"), Some("Python"));
+        assert_eq!(name(b"#!/bin/bash -e\n"), Some("Shell"));
+        assert_eq!(
+            name(b"#!/usr/bin/env -S node --no-warnings"),
+            Some("JavaScript")
+        );
+        assert_eq!(name(b"#!/usr/bin/python3.12"), Some("Python"));
+        assert_eq!(name(b"#!/opt/custom/tool"), None);
+        assert_eq!(name(b"import os"), None);
+    }
+}
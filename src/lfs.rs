@@ -0,0 +1,44 @@
+//a git lfs pointer is what's checked in instead of a large file: a few lines naming the object
+//and its size. the spec caps them at 1024 bytes.
+pub const MAX_POINTER_LEN: usize = 1024;
+
+const VERSION: &[u8] = b"version https://git-lfs.github.com/spec/";
+
+//the size of the object a pointer stands for, None when `content` isn't a pointer.
+//
+//    version https://git-lfs.github.com/spec/v1
+//    oid sha256:4d7a2146...
+//    size 12345
+pub fn pointer_size(content: &[u8]) -> Option<u128> {
+    if content.len() > MAX_POINTER_LEN || !content.starts_with(VERSION) {
+        return None;
+    }
+    let content = std::str::from_utf8(content).ok()?;
+    let mut oid = false;
+    let mut size = None;
+    for line in content.lines().skip(1) {
+        let (key, value) = line.split_once(' ')?;
+        match key {
+            "oid" => oid = value.starts_with("sha256:"),
+            "size" => size = value.parse::<u128>().ok(),
+            _ => {}
+        }
+    }
+    size.filter(|_| oid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pointer_size;
+
+    #[test]
+    fn reads_pointers() {
+        let pointer = b"version https://git-lfs.github.com/spec/v1\noid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\nsize 12345\n";
+        assert_eq!(pointer_size(pointer), Some(12345));
+        assert_eq!(
+            pointer_size(b"version https://git-lfs.github.com/spec/v1\nsize 1\n"),
+            None
+        );
+        assert_eq!(pointer_size(b"size 12345\n"), None);
+    }
+}
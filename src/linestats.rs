@@ -0,0 +1,124 @@
+//--line-lengths and --long-lines: lengths are in characters rather than bytes, so a line of cjk
+//text isn't three times longer than the same line in ascii. a tab is one character, and a \r
+//before the newline isn't part of the line.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LineStats {
+    pub longest: u128,
+    //summed over every line, the average is this over the line count.
+    pub chars: u128,
+    //lines longer than the --long-lines limit.
+    pub long: u128,
+    //--indentation: lines indented with a tab or a space first, blank lines don't count.
+    pub tab_indented: u128,
+    pub space_indented: u128,
+    //--line-endings: how lines end. a lone \r is one that isn't followed by a \n.
+    pub lf: u128,
+    pub crlf: u128,
+    pub cr: u128,
+    //--hygiene: lines ending in spaces or tabs. a line of nothing but whitespace counts too.
+    pub trailing_whitespace: u128,
+    //--words: runs of anything but ascii whitespace, like wc -w in the C locale.
+    pub words: u128,
+}
+
+pub fn measure(content: &[u8], limit: Option<u128>) -> LineStats {
+    let mut stats = LineStats::default();
+    let mut lines = content.split(|&byte| byte == b'\n').peekable();
+    while let Some(line) = lines.next() {
+        //every piece but the last was cut off at a \n.
+        let ended = lines.peek().is_some();
+        let crlf = ended && line.ends_with(b"\r");
+        let lone_crs = line.iter().filter(|&&byte| byte == b'\r').count() - usize::from(crlf);
+        stats.cr += lone_crs as u128;
+        match (ended, crlf) {
+            (true, true) => stats.crlf += 1,
+            (true, false) => stats.lf += 1,
+            (false, _) => {}
+        }
+
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        stats.words += line
+            .split(|byte| byte.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .count() as u128;
+        if line.ends_with(b" ") || line.ends_with(b"\t") {
+            stats.trailing_whitespace += 1;
+        }
+        //every byte that doesn't continue a utf-8 sequence starts a character.
+        let chars = line.iter().filter(|&&byte| byte & 0xC0 != 0x80).count() as u128;
+        stats.longest = stats.longest.max(chars);
+        stats.chars += chars;
+        stats.long += u128::from(limit.is_some_and(|limit| chars > limit));
+        if !line.trim_ascii().is_empty() {
+            match line.first() {
+                Some(b'\t') => stats.tab_indented += 1,
+                Some(b' ') => stats.space_indented += 1,
+                _ => {}
+            }
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineStats, measure};
+
+    #[test]
+    fn measures_in_characters() {
+        assert_eq!(
+            measure(
+                "fn main() {}\r\n\tlet größe = 1;\n    \n  }\n".as_bytes(),
+                Some(12)
+            ),
+            LineStats {
+                longest: 15,
+                chars: 34,
+                long: 1,
+                tab_indented: 1,
+                space_indented: 1,
+                lf: 3,
+                crlf: 1,
+                cr: 0,
+                trailing_whitespace: 1,
+                words: 8,
+            }
+        );
+        let endings = measure(b"a\r\nb\rc\nd\r", None);
+        assert_eq!((endings.lf, endings.crlf, endings.cr), (1, 1, 2));
+        assert_eq!(measure(b"", None), LineStats::default());
+    }
+
+    #[test]
+    fn long_lines_over_the_limit() {
+        let content = "12345\n123456\nüüüüü\n".as_bytes();
+        assert_eq!(measure(content, Some(5)).long, 1);
+        assert_eq!(measure(content, Some(6)).long, 0);
+        assert_eq!(measure(content, None).long, 0);
+    }
+
+    #[test]
+    fn indentation_by_first_character() {
+        let stats = measure(b"\tx\n  y\n \ty\nz\n    \n\t\n", None);
+        assert_eq!((stats.tab_indented, stats.space_indented), (1, 2));
+    }
+
+    #[test]
+    fn line_endings_by_kind() {
+        let stats = measure(b"a\r\nb\rc\nd\r\ne", None);
+        assert_eq!((stats.lf, stats.crlf, stats.cr), (1, 2, 1));
+    }
+
+    #[test]
+    fn trailing_whitespace_before_the_line_ending() {
+        let stats = measure(b"a \nb\t\nc\n   \nd \r\ne\r\n", None);
+        assert_eq!(stats.trailing_whitespace, 4);
+    }
+
+    #[test]
+    fn words_split_on_ascii_whitespace() {
+        let stats = measure("héllo wörld\tfoo\n  \nbar-baz\r\n".as_bytes(), None);
+        assert_eq!(stats.words, 4);
+        assert_eq!(measure("a\u{a0}b".as_bytes(), None).words, 1);
+    }
+}
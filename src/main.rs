@@ -1,19 +1,117 @@
 #![allow(dead_code)]
 
+mod adaptive;
+mod age;
+mod authors;
+mod bench;
+mod branches;
+mod budget;
+mod budget_file;
+mod cache;
+mod churn;
+mod cocomo;
+mod codeowners;
+mod dates;
+mod diff;
+mod dup;
+mod encoding;
+mod estimate;
+mod fd;
+mod filter;
+mod git;
+mod gitattributes;
+mod graphemes;
+mod history;
+mod hook;
+mod identical;
+mod interrupt;
+mod languages;
+mod lfs;
+mod linestats;
+mod notebook;
+mod pr;
+mod report;
+mod sequencer;
+mod snapshot;
+mod statements;
+mod testcode;
+mod timings;
+mod todos;
+mod tokens;
+#[cfg(feature = "tokio")]
+mod tokio_count;
+#[cfg(feature = "uring")]
+mod uring;
+mod user_languages;
+mod walk;
+mod workspace;
+
+use adaptive::Throttle;
+use cache::Cache;
 use clap::{Arg, ArgAction, Command};
+use cocomo::{Cocomo, Model};
+use codeowners::Codeowners;
 use colored::Colorize;
-use std::collections::HashSet;
-use std::io::Result;
-use std::os::unix::fs::PermissionsExt;
+use dup::{DUP_BLOCK_LINES, DupIndex};
+use encoding::Encoding;
+use filter::{Filter, Skip};
+use gitattributes::Attributes;
+use history::History;
+use identical::IdenticalFiles;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rayon::prelude::*;
+use regex::Regex;
+use report::Export;
+use sequencer::Sequencer;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, ErrorKind, Read, Result, Write};
+use std::num::NonZeroUsize;
+use std::ops::AddAssign;
+use std::os::unix::fs::{FileExt, FileTypeExt, MetadataExt, PermissionsExt};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use std::{env, fs};
+use testcode::{Split, TestSplit};
+use timings::Phase;
+use todos::Todos;
+use walk::Listing;
+use workspace::Workspace;
 
 const WIDTH: usize = 20;
 const FILENAME_RENDER_LIMIT: usize = 60;
+const SPARKLINE_BUCKETS: usize = 8;
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+//how much of a file is looked at when deciding whether it is binary.
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+//how much of a file is held in memory at a time while its lines are counted.
+const READ_CHUNK_LEN: usize = 64 * 1024;
+//files at least this big get counted by several workers at once, SPLIT_PIECE_LEN bytes each.
+const SPLIT_THRESHOLD: u64 = 256 * 1024 * 1024;
+const SPLIT_PIECE_LEN: u64 = 16 * 1024 * 1024;
+//files at least this big are memory mapped when built with the mmap feature.
+#[cfg(feature = "mmap")]
+const MMAP_THRESHOLD: u64 = 16 * 1024 * 1024;
+//how many files --line-lengths and --long-lines list.
+const LONGEST_LINE_FILES: usize = 10;
+const LONG_LINE_FILES: usize = 20;
+//how many files mixing tabs and spaces --indentation lists.
+const MIXED_INDENT_FILES: usize = 20;
+//how many files with mixed line endings --line-endings lists.
+const MIXED_ENDING_FILES: usize = 20;
+//how many files with trailing whitespace --hygiene lists.
+const TRAILING_WHITESPACE_FILES: usize = 20;
+//how many files with TODO markers --todos lists.
+const TODO_FILES: usize = 20;
+//how many empty and whitespace-only files --empty lists.
+const EMPTY_FILES: usize = 20;
+//how many sets of identical files --dupes lists, the ones wasting the most bytes.
+const DUPE_SETS: usize = 10;
 
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum ContentType {
     CODE,
     MEDIA,
@@ -22,63 +120,148 @@ enum ContentType {
     TEXT,
     LICENSE,
     MAKEFILE,
+    LOCKFILE,
 }
 
-lazy_static::lazy_static! {
-    static ref CODE_EXTENSIONS: HashSet<&'static str> = [
-        "c", "h", "cpp", "hpp", "cc", "cxx", "hh", "hxx", "cs", "java", "class", "jar", "kt", "kts",
-        "js", "jsx", "mjs", "cjs", "ts", "tsx", "py", "pyc", "pyd", "pyo", "rb", "erb",
-        "php", "phar", "go", "rs", "rlib", "swift", "dart", "scala", "lua", "r", "pl", "pm", "sql",
-        "html", "htm", "xhtml", "xml", "css", "scss", "sass", "json", "yaml", "yml", "toml",
-        "env", "ini", "cfg", "md", "rst", "cmake", "mk", "dockerfile", "dockerignore",
-        "gitignore", "gitattributes"
-    ].iter().copied().collect();
+impl ContentType {
+    const NAMES: [&'static str; 8] = [
+        "code",
+        "media",
+        "executable",
+        "normal",
+        "text",
+        "license",
+        "makefile",
+        "lockfile",
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            ContentType::CODE => "code",
+            ContentType::MEDIA => "media",
+            ContentType::EXECUTABLE => "executable",
+            ContentType::NORMAL => "normal",
+            ContentType::TEXT => "text",
+            ContentType::LICENSE => "license",
+            ContentType::MAKEFILE => "makefile",
+            ContentType::LOCKFILE => "lockfile",
+        }
+    }
+
+    fn from_name(name: &str) -> std::result::Result<ContentType, String> {
+        match name {
+            "code" => Ok(ContentType::CODE),
+            "media" => Ok(ContentType::MEDIA),
+            "executable" => Ok(ContentType::EXECUTABLE),
+            "normal" => Ok(ContentType::NORMAL),
+            "text" => Ok(ContentType::TEXT),
+            "license" => Ok(ContentType::LICENSE),
+            "makefile" => Ok(ContentType::MAKEFILE),
+            "lockfile" => Ok(ContentType::LOCKFILE),
+            _ => Err(format!(
+                "unknown content type, expected one of: {}",
+                ContentType::NAMES.join(", ")
+            )),
+        }
+    }
+}
 
-    static ref MEDIA_EXTENSIONS: HashSet<&'static str> = [
-        "png", "jpg", "jpeg", "gif", "bmp", "tiff", "tif", "webp", "svg", "ico", "heic", "avif",
-        "mp3", "wav", "flac", "aac", "ogg", "opus", "m4a", "wma", "aiff", "alac", "amr",
-        "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "mpeg", "mpg", "3gp", "ogv"
-    ].iter().copied().collect();
+//extension -> content type, compiled down to plain string comparisons so classifying a file
+//doesn't hash anything or touch a lazily built set. an extension only appears under one type: "jar"
+//is code rather than an executable, and "md" is code rather than text.
+fn extension_type(ext: &str) -> Option<ContentType> {
+    match ext {
+        "c" | "h" | "cpp" | "hpp" | "cc" | "cxx" | "hh" | "hxx" | "cs" | "java" | "class"
+        | "jar" | "kt" | "kts" | "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "py" | "pyc"
+        | "pyd" | "pyo" | "rb" | "erb" | "php" | "phar" | "go" | "rs" | "rlib" | "swift"
+        | "dart" | "scala" | "lua" | "r" | "pl" | "pm" | "sql" | "html" | "htm" | "xhtml"
+        | "xml" | "css" | "scss" | "sass" | "json" | "yaml" | "yml" | "toml" | "env" | "ini"
+        | "cfg" | "md" | "rst" | "cmake" | "mk" | "dockerfile" | "dockerignore" | "gitignore"
+        | "gitattributes" => Some(ContentType::CODE),
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tiff" | "tif" | "webp" | "svg" | "ico"
+        | "heic" | "avif" | "mp3" | "wav" | "flac" | "aac" | "ogg" | "opus" | "m4a" | "wma"
+        | "aiff" | "alac" | "amr" | "mp4" | "mkv" | "avi" | "mov" | "wmv" | "flv" | "webm"
+        | "m4v" | "mpeg" | "mpg" | "3gp" | "ogv" => Some(ContentType::MEDIA),
+        "exe" | "bat" | "cmd" | "msi" | "run" | "out" | "bin" | "app" | "sh" | "bash" | "zsh"
+        | "ps1" | "psm1" | "psd1" => Some(ContentType::EXECUTABLE),
+        "txt" | "rtf" | "csv" | "log" | "pdf" | "doc" | "docx" | "odt" | "tex" | "pages" => {
+            Some(ContentType::TEXT)
+        }
+        _ => None,
+    }
+}
 
-    static ref EXECUTABLE_EXTENSIONS: HashSet<&'static str> = [
-        "exe", "bat", "cmd", "msi", "run", "out", "bin", "app", "jar", "sh", "bash", "zsh",
-        "ps1", "psm1", "psd1"
-    ].iter().copied().collect();
+//media and compiled executables are counted by their size alone, reading a video only to find out
+//it's binary is most of the runtime on an asset-heavy repo. the text formats filed under those
+//types (svg, scripts) still get read.
+fn is_opaque(path: &Path) -> bool {
+    let Some(ext) = path.file_extension() else {
+        return false;
+    };
+    match extension_type(ext) {
+        Some(ContentType::MEDIA) => ext != "svg",
+        Some(ContentType::EXECUTABLE) => matches!(ext, "exe" | "msi" | "bin" | "app"),
+        _ => false,
+    }
+}
 
-    static ref TEXT_EXTENSIONS: HashSet<&'static str> = [
-        "txt", "md", "rtf", "csv", "log", "pdf", "doc", "docx", "odt", "tex", "pages"
-    ].iter().copied().collect();
+fn is_lockfile_name(name: &str) -> bool {
+    matches!(
+        name,
+        "Cargo.lock"
+            | "package-lock.json"
+            | "yarn.lock"
+            | "pnpm-lock.yaml"
+            | "poetry.lock"
+            | "Pipfile.lock"
+            | "Gemfile.lock"
+            | "composer.lock"
+            | "go.sum"
+            | "flake.lock"
+    )
 }
 
 trait Content {
     fn content_type(&self) -> ContentType;
+    fn file_extension(&self) -> Option<&str>;
+    fn is_lockfile(&self) -> bool;
 }
 
 impl Content for Path {
     fn content_type(&self) -> ContentType {
-        if let Some(ext) = self.extension().and_then(|s| s.to_str()) {
-            if CODE_EXTENSIONS.contains(ext) {
-                return ContentType::CODE;
-            }
-            if MEDIA_EXTENSIONS.contains(ext) {
-                return ContentType::MEDIA;
+        //lockfiles go by their full name, most of them would pass for code by extension alone.
+        if self.is_lockfile() {
+            return ContentType::LOCKFILE;
+        }
+
+        if let Some(ext) = self.file_extension() {
+            if let Some(content_type) = extension_type(ext) {
+                return content_type;
             }
-            if EXECUTABLE_EXTENSIONS.contains(ext)
-                || (self.is_unix_executable().unwrap_or(false) && !TEXT_EXTENSIONS.contains(ext))
-            {
+            if self.is_unix_executable().unwrap_or(false) {
                 return ContentType::EXECUTABLE;
             }
-            if TEXT_EXTENSIONS.contains(ext) {
-                return ContentType::TEXT;
-            }
         }
 
         match self.file_name().and_then(|s| s.to_str()) {
             Some("LICENSE") => ContentType::LICENSE,
             Some("Makefile") => ContentType::MAKEFILE,
+            //a script without an extension is code if its #! line names a known language, and so
+            //is anything languages.toml added.
+            _ if languages::detect(self).is_some() => ContentType::CODE,
             _ => ContentType::NORMAL,
         }
     }
+
+    fn file_extension(&self) -> Option<&str> {
+        self.extension().and_then(|s| s.to_str())
+    }
+
+    fn is_lockfile(&self) -> bool {
+        self.file_name()
+            .and_then(|s| s.to_str())
+            .is_some_and(is_lockfile_name)
+    }
 }
 
 trait Visible {
@@ -86,12 +269,11 @@ trait Visible {
 }
 
 impl Visible for Path {
+    //the raw bytes are checked, a name that isn't utf-8 can still be hidden.
     fn is_visible(&self) -> bool {
-        //this may be a bit much. but it works well.
-        if self.file_name().unwrap().to_str().unwrap().chars().nth(0) == Some('.') {
-            return false;
-        }
-        true
+        !self
+            .file_name()
+            .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
     }
 }
 
@@ -127,398 +309,4056 @@ fn fetch_gitignore(path: &Path) -> Result<Vec<String>> {
     Ok(list_to_ignore)
 }
 
-fn linecount_async(dir: Option<PathBuf>) -> Result<(u128, u128)> {
-    let total_lines = Arc::new(Mutex::new(0));
-    let total_bytes = Arc::new(Mutex::new(0));
-    let dir_path_binding = dir.unwrap_or(env::current_dir()?);
-    let dir_path = dir_path_binding.as_path();
-    //let ignore_vec = fetch_gitignore(&dir_path)?;
-    let mut handles = Vec::new();
+#[derive(Debug, Clone, PartialEq)]
+struct FileStat {
+    path: PathBuf,
+    counts: Counts,
+}
 
-    let entries = fs::read_dir(dir_path)
-        .expect("Failed to read directory")
-        .map(|entry| entry.unwrap().path())
-        .collect::<Vec<_>>();
+//running totals for a file, directory or whole run. lockfiles count towards lines and bytes like
+//everything else, and are tallied on the side as well so reports can show how much they make up.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Counts {
+    lines: u128,
+    bytes: u128,
+    statements: u128,
+    //--logical, see statements.rs.
+    logical: u128,
+    //--breakdown: code + comments + docs + blanks is lines for every file in a known language.
+    code: u128,
+    comments: u128,
+    docs: u128,
+    blanks: u128,
+    //markdown and raw cells of notebooks, which aren't code, comments or blank either.
+    text: u128,
+    //--line-lengths, see linestats.rs. the longest line of a sum is the longest of any file in it.
+    longest_line: u128,
+    line_chars: u128,
+    long_lines: u128,
+    //--indentation: a file indented both ways counts once in mixed_indent_files.
+    tab_indented: u128,
+    space_indented: u128,
+    mixed_indent_files: u128,
+    //--line-endings: a file ending lines more than one way counts once in mixed_ending_files.
+    lf_endings: u128,
+    crlf_endings: u128,
+    cr_endings: u128,
+    mixed_ending_files: u128,
+    //--hygiene
+    trailing_whitespace: u128,
+    //--words
+    words: u128,
+    //--chars: unicode scalar values and grapheme clusters.
+    chars: u128,
+    graphemes: u128,
+    //--tokens, an estimate, see tokens.rs.
+    tokens: u128,
+    //--todos: TODO, FIXME and the like, see todos.rs.
+    todos: u128,
+    //--empty: text files with lines, but nothing on them besides whitespace.
+    whitespace_files: u128,
+    //--dedupe: copies of a file that was counted already, they add nothing else.
+    duplicate_files: u128,
+    //text files that weren't utf-8 and were decoded before counting, see encoding.rs.
+    utf16_files: u128,
+    latin1_files: u128,
+    shift_jis_files: u128,
+    //text files that started with a byte order mark. it's left out of bytes and everything else.
+    bom_files: u128,
+    lockfile_lines: u128,
+    lockfile_bytes: u128,
+    binary_files: u128,
+    binary_bytes: u128,
+    //git lfs pointers, and how big the objects they stand for are. bytes only has the pointers.
+    lfs_pointers: u128,
+    lfs_bytes: u128,
+}
 
-    for entry in entries {
-        let path = entry.as_path();
-        let filetype = fs::metadata(path)?.file_type();
-
-        if filetype.is_file() {
-            let content = fs::read(&path)?; // Read the raw bytes
-            let content_len = content.len() as u128;
-            let content_str = std::str::from_utf8(&content).unwrap_or("");
-
-            //let content = String::from_utf8_lossy(&fs::read(&path)?).into_owned();
-            let file_linecount = content_str.lines().count() as u128;
-            let file_bytes = content_len;
-
-            *total_lines.lock().unwrap() += file_linecount;
-            *total_bytes.lock().unwrap() += file_bytes;
-        } else if filetype.is_dir() {
-            let handle = {
-                let total_lines = Arc::clone(&total_lines);
-                let total_bytes = Arc::clone(&total_bytes);
-                let path = PathBuf::from(path);
-
-                thread::spawn(move || {
-                    let recursive_lc = linecount_async(Some(path));
-
-                    if let Ok((lines, bytes)) = recursive_lc {
-                        *total_lines.lock().unwrap() += lines;
-                        *total_bytes.lock().unwrap() += bytes;
-                    }
-                })
-            };
-            handles.push(handle);
+impl Counts {
+    fn of_file(path: &Path, lines: u128, bytes: u128, statements: u128) -> Counts {
+        let lockfile = path.is_lockfile();
+        Counts {
+            lines,
+            bytes,
+            statements,
+            lockfile_lines: if lockfile { lines } else { 0 },
+            lockfile_bytes: if lockfile { bytes } else { 0 },
+            ..Counts::default()
         }
     }
-    for handle in handles {
-        handle.join().unwrap();
+
+    //binary files only contribute their size, a "line" in an object file or image means nothing.
+    fn of_binary(bytes: u128) -> Counts {
+        Counts {
+            bytes,
+            binary_files: 1,
+            binary_bytes: bytes,
+            ..Counts::default()
+        }
     }
 
-    Ok(get_totals(total_lines, total_bytes))
-}
+    //the few lines of a pointer aren't the file's content, so it counts no lines at all.
+    fn of_lfs_pointer(bytes: u128, object: u128) -> Counts {
+        Counts {
+            bytes,
+            lfs_pointers: 1,
+            lfs_bytes: object,
+            ..Counts::default()
+        }
+    }
 
-fn linecount_display(
-    dir: Option<PathBuf>,
-    mut indent_amount: Option<usize>,
-) -> Result<(u128, u128)> {
-    let (mut total_lines, mut total_bytes) = (0, 0);
-    let dir_path_binding = dir.unwrap_or(env::current_dir()?);
-    let dir_path = dir_path_binding.as_path();
-    let mut file_indent_from_zero_size = indent_amount.unwrap_or_default();
-    //let ignore_vec = fetch_gitignore(&dir_path)?;
+    fn encoded(self, encoding: Encoding) -> Counts {
+        match encoding {
+            Encoding::Utf8 => self,
+            Encoding::Utf16Le | Encoding::Utf16Be => Counts {
+                utf16_files: 1,
+                ..self
+            },
+            Encoding::Latin1 => Counts {
+                latin1_files: 1,
+                ..self
+            },
+            Encoding::ShiftJis => Counts {
+                shift_jis_files: 1,
+                ..self
+            },
+        }
+    }
 
-    if indent_amount.is_none() {
-        indent_amount = Some(0);
-    } else if indent_amount.unwrap() > 0 {
-        file_indent_from_zero_size += 1;
+    //bom is the length of the byte order mark the file started with, 0 for none.
+    fn with_bom(self, bom: usize) -> Counts {
+        Counts {
+            bom_files: u128::from(bom > 0),
+            ..self
+        }
     }
 
-    let (dir_indent, file_indent_from_dir, file_ident_from_zero) = (
-        "─".repeat(indent_amount.unwrap_or_default()),
-        "─".repeat(2),
-        " ".repeat(file_indent_from_zero_size),
-    );
-    let dir_path_str = dir_path
-        .file_name()
-        .unwrap()
-        .to_str()
-        .unwrap_or_default()
-        .blue()
-        .bold();
+    //for a single file, what it was decoded from.
+    fn encoding_name(&self) -> Option<&'static str> {
+        match (self.utf16_files, self.latin1_files, self.shift_jis_files) {
+            (1, _, _) => Some("utf-16"),
+            (_, 1, _) => Some(Encoding::Latin1.name()),
+            (_, _, 1) => Some(Encoding::ShiftJis.name()),
+            _ => None,
+        }
+    }
+}
 
-    match indent_amount {
-        Some(0) => println!("{dir_indent}{dir_path_str}/"),
-        _ => println!("├{dir_indent}{dir_path_str}/"),
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Counts) {
+        self.lines += other.lines;
+        self.bytes += other.bytes;
+        self.statements += other.statements;
+        self.logical += other.logical;
+        self.code += other.code;
+        self.comments += other.comments;
+        self.docs += other.docs;
+        self.blanks += other.blanks;
+        self.text += other.text;
+        self.longest_line = self.longest_line.max(other.longest_line);
+        self.line_chars += other.line_chars;
+        self.long_lines += other.long_lines;
+        self.tab_indented += other.tab_indented;
+        self.space_indented += other.space_indented;
+        self.mixed_indent_files += other.mixed_indent_files;
+        self.lf_endings += other.lf_endings;
+        self.crlf_endings += other.crlf_endings;
+        self.cr_endings += other.cr_endings;
+        self.mixed_ending_files += other.mixed_ending_files;
+        self.trailing_whitespace += other.trailing_whitespace;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.graphemes += other.graphemes;
+        self.tokens += other.tokens;
+        self.todos += other.todos;
+        self.whitespace_files += other.whitespace_files;
+        self.duplicate_files += other.duplicate_files;
+        self.utf16_files += other.utf16_files;
+        self.latin1_files += other.latin1_files;
+        self.shift_jis_files += other.shift_jis_files;
+        self.bom_files += other.bom_files;
+        self.lockfile_lines += other.lockfile_lines;
+        self.lockfile_bytes += other.lockfile_bytes;
+        self.binary_files += other.binary_files;
+        self.binary_bytes += other.binary_bytes;
+        self.lfs_pointers += other.lfs_pointers;
+        self.lfs_bytes += other.lfs_bytes;
     }
+}
 
-    let entries = fs::read_dir(dir_path)
-        .expect("Failed to read directory")
-        .map(|entry| entry.unwrap().path())
-        .collect::<Vec<_>>();
-    let (mut files, mut dirs) = (Vec::new(), Vec::new());
+//--metric: what the reports that rank or bucket files go by. sloc is the code lines of
+//--breakdown, so comments, docs and blanks are left out.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Metric {
+    #[default]
+    Lines,
+    Sloc,
+    Bytes,
+}
 
-    for entry in entries {
-        //if ignore_toggle {
-        //    if ignore_vec.contains(&entry.file_name().unwrap().to_string_lossy().to_string()) {
-        //        continue;
-        //    }
-        //}
+impl Metric {
+    fn from_name(name: &str) -> std::result::Result<Metric, String> {
+        match name {
+            "lines" => Ok(Metric::Lines),
+            "sloc" => Ok(Metric::Sloc),
+            "bytes" => Ok(Metric::Bytes),
+            _ => Err("unknown metric, expected one of: lines, sloc, bytes".to_string()),
+        }
+    }
 
-        if entry.is_file() {
-            files.push(entry);
-        } else {
-            dirs.push(entry);
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::Lines => "Lines",
+            Metric::Sloc => "SLOC",
+            Metric::Bytes => "Bytes",
         }
     }
-    files.sort();
-    dirs.sort();
-    let sorted_entries = files.iter().chain(dirs.iter());
 
-    for (idx, entry) in sorted_entries.enumerate() {
-        let mut connector = "├";
-        let path = entry.as_path();
-        let filetype = fs::metadata(path)?.file_type();
+    fn of(&self, counts: &Counts) -> u128 {
+        match self {
+            Metric::Lines => counts.lines,
+            Metric::Sloc => counts.code,
+            Metric::Bytes => counts.bytes,
+        }
+    }
+}
 
-        if filetype.is_file() {
-            let content = String::from_utf8_lossy(&fs::read(&path)?).into_owned();
-            let file_linecount = content.lines().count() as u128;
-            let file_bytes = content.as_bytes().len() as u128;
+//--submodules: what happens to a git submodule (or any repository checked out inside another) met
+//during a walk.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Submodules {
+    #[default]
+    Include,
+    Skip,
+    //left out of the totals and counted on their own afterwards.
+    Separate,
+}
+
+impl Submodules {
+    fn from_name(name: &str) -> std::result::Result<Submodules, String> {
+        match name {
+            "include" => Ok(Submodules::Include),
+            "skip" => Ok(Submodules::Skip),
+            "separate" => Ok(Submodules::Separate),
+            _ => Err("unknown mode, expected one of: include, skip, separate".to_string()),
+        }
+    }
+}
 
-            total_lines += file_linecount;
-            total_bytes += file_bytes;
+//everything a walk needs besides the directory it's in: the rules for what to count, and the
+//optional collectors that only get set up when a flag asks for them.
+#[derive(Default)]
+struct Scan {
+    filter: Filter,
+    sparkline: bool,
+    metric: Metric,
+    statements: bool,
+    logical: bool,
+    breakdown: bool,
+    //--percent: each file's, directory's and table row's share of the total, by the metric.
+    percent: bool,
+    //--languages: files and counts per detected language, text files without one go under "Other".
+    languages: Option<Mutex<HashMap<&'static str, (u128, Counts)>>>,
+    tests: Option<TestSplit>,
+    owners: Option<Codeowners>,
+    workspace: Option<Workspace>,
+    //--record: totals per top-level directory, for the history database.
+    history: Option<History>,
+    //--export FILE: every file's counts, for a json report.
+    export: Option<Export>,
+    //--line-lengths: the files with the longest lines, longest first, at most LONGEST_LINE_FILES.
+    line_lengths: Option<Mutex<Vec<FileStat>>>,
+    //--long-lines N: every file with a line over N characters.
+    long_line_limit: Option<u128>,
+    long_line_files: Mutex<Vec<FileStat>>,
+    //--indentation: the files indented with both tabs and spaces.
+    indentation: Option<Mutex<Vec<FileStat>>>,
+    //--line-endings: the files that end lines more than one way.
+    line_endings: Option<Mutex<Vec<FileStat>>>,
+    //--hygiene: the files with trailing whitespace.
+    hygiene: Option<Mutex<Vec<FileStat>>>,
+    todos: Option<Todos>,
+    cocomo: Option<Cocomo>,
+    //--empty: text files without a line, or with nothing but whitespace in them.
+    empty: Option<Mutex<Vec<FileStat>>>,
+    //--dupes: files with the same content.
+    dupes: Option<IdenticalFiles>,
+    dedupe: bool,
+    //--todos: the files with markers in them.
+    todo_files: Mutex<Vec<FileStat>>,
+    words: bool,
+    chars: bool,
+    tokens: bool,
+    //--verbose: the text files that had to be decoded from something other than utf-8.
+    verbose: Option<Mutex<Vec<FileStat>>>,
+    //--line-breaks any: a lone \r ends a line too, not just \n.
+    cr_breaks: bool,
+    //--no-blank: lines with nothing but whitespace don't count.
+    no_blank: bool,
+    //--show-bom: the files that started with a byte order mark.
+    boms: Option<Mutex<Vec<FileStat>>>,
+    stats: Option<Mutex<Vec<FileStat>>>,
+    dups: Option<DupIndex>,
+    cache: Option<Cache>,
+    uring: bool,
+    throttle: Option<Throttle>,
+    noise: Mutex<Vec<PathBuf>>,
+    special: Mutex<Vec<PathBuf>>,
+    ignored: Option<Mutex<Vec<(PathBuf, Skip)>>>,
+    relative_to: Option<PathBuf>,
+    follow_symlinks: bool,
+    one_file_system: bool,
+    submodules: Submodules,
+    //every submodule the walks came across, whatever was done with it.
+    submodule_dirs: Mutex<Vec<PathBuf>>,
+    count_links: bool,
+    //(device, inode) of every file with more than one link that has been counted already.
+    linked_files: Mutex<HashSet<(u64, u64)>>,
+    //(device, inode) of every directory entered, only tracked with --follow-symlinks.
+    visited: Mutex<HashSet<(u64, u64)>>,
+}
 
-            let filename = entry
-                .file_name()
-                .unwrap()
-                .to_str()
-                .unwrap_or("?")
-                .to_string();
+impl Scan {
+    fn skip_file(&self, path: &Path) -> bool {
+        let Some(skip) = self.filter.file_skip(path) else {
+            return false;
+        };
 
-            let filename = if filename.len() > FILENAME_RENDER_LIMIT {
-                format!("{}...", &filename[..FILENAME_RENDER_LIMIT])
-            } else {
-                filename
-            };
-
-            //if last file in head/sub-directory
-            if idx == files.len() - 1 {
-                connector = "└";
-            }
-
-            let formatted_indent: String = match indent_amount {
-                Some(0) => format!("{file_ident_from_zero}{connector}{file_indent_from_dir}"),
-                _ => format!("|{file_ident_from_zero}{connector}{file_indent_from_dir}"),
-            };
-
-            let formatted_output = format!(
-                "{:width$} ({}L, {}B)",
-                {
-                    match path.content_type() {
-                        ContentType::MEDIA => filename.bright_magenta().to_string(),
-                        ContentType::CODE => filename.cyan().to_string(),
-                        ContentType::EXECUTABLE => filename.green().to_string(),
-                        ContentType::TEXT => filename.truecolor(217, 50, 122).to_string(),
-                        ContentType::LICENSE => filename.truecolor(0, 0, 255).to_string(),
-                        ContentType::MAKEFILE => filename.red().to_string(),
-                        _ => filename.to_string(),
-                    }
-                },
-                file_linecount,
-                file_bytes,
-                width = WIDTH
-            );
-            println!("{formatted_indent}{formatted_output}");
-        } else if filetype.is_dir() {
-            if let Ok((lines, bytes)) = linecount_display(
-                Some(PathBuf::from(&path)),
-                Some(indent_amount.unwrap_or_default() + 2),
-            ) {
-                total_lines += lines;
-                total_bytes += bytes;
-            }
-        };
-    }
-    Ok((total_lines, total_bytes))
-}
-
-//EXPERIMENTAL: runs linecount_display via paralellization. has significant increase in speed.
-//   -BUGS: since the function operates in parrell, printing the treemap is unreliable since order is not guaranteed.
-//          because of this the output looks scattered and disorganized.
-//
-//
-fn linecount_display_async(
-    dir: Option<PathBuf>,
-    mut indent_amount: Option<usize>,
-) -> Result<(u128, u128)> {
-    let total_lines = Arc::new(Mutex::new(0));
-    let total_bytes = Arc::new(Mutex::new(0));
-    let dir_path_binding = dir.unwrap_or(env::current_dir()?);
-    let dir_path = dir_path_binding.as_path();
-    let mut file_indent_from_zero_size = indent_amount.unwrap_or_default();
-    //let ignore_vec = fetch_gitignore(&dir_path)?;
-    let mut handles = Vec::new();
-
-    if indent_amount.is_none() {
-        indent_amount = Some(0);
-    } else if indent_amount.unwrap() > 0 {
-        file_indent_from_zero_size += 1;
+        if filter::is_noise(path) {
+            self.noise.lock().unwrap().push(path.to_path_buf());
+        }
+        self.record_skip(path, skip);
+        true
     }
 
-    let (dir_indent, file_indent_from_dir, file_ident_from_zero) = (
-        "─".repeat(indent_amount.unwrap_or_default()),
-        "─".repeat(2),
-        " ".repeat(file_indent_from_zero_size),
-    );
-    let dir_path_str = dir_path
-        .file_name()
-        .unwrap()
-        .to_str()
-        .unwrap_or_default()
-        .blue()
-        .bold();
+    fn skip_dir(&self, path: &Path) -> bool {
+        let skip = match self.filter.dir_skip(path) {
+            Some(skip) => skip,
+            None if self.one_file_system && is_mount_point(path) => Skip::OtherFilesystem,
+            None if is_submodule(path) && self.leave_out_submodule(path) => Skip::Submodule,
+            None => return false,
+        };
 
-    match indent_amount {
-        Some(0) => println!("{dir_indent}{dir_path_str}/"),
-        _ => println!("├{dir_indent}{dir_path_str}/"),
+        self.record_skip(path, skip);
+        true
     }
 
-    let entries = fs::read_dir(dir_path)
-        .expect("Failed to read directory")
-        .map(|entry| entry.unwrap().path())
-        .collect::<Vec<_>>();
-    let (mut files, mut dirs) = (Vec::new(), Vec::new());
+    fn leave_out_submodule(&self, path: &Path) -> bool {
+        self.submodule_dirs.lock().unwrap().push(path.to_path_buf());
+        self.submodules != Submodules::Include
+    }
 
-    for entry in entries {
-        //if ignore_toggle {
-        //    if ignore_vec.contains(&entry.file_name().unwrap().to_string_lossy().to_string()) {
-        //        continue;
-        //    }
-        //}
+    //which name of a hard-linked file or which way into a directory counts depends on which one
+    //comes first, so these two are only asked once a directory's entries are sorted.
+    fn skip_hard_link(&self, path: &Path) -> bool {
+        let linked = !self.count_links && !self.first_link(path);
+        if linked {
+            self.record_skip(path, Skip::HardLink);
+        }
+        linked
+    }
 
-        if entry.is_file() {
-            files.push(entry);
-        } else {
-            dirs.push(entry);
+    fn skip_revisit(&self, path: &Path) -> bool {
+        let revisit = !self.first_visit(path);
+        if revisit {
+            self.record_skip(path, Skip::Revisited);
         }
+        revisit
     }
-    files.sort();
-    dirs.sort();
-    let sorted_entries = files.iter().chain(dirs.iter());
 
-    for (idx, entry) in sorted_entries.enumerate() {
-        let mut connector = "├";
-        let path = entry.as_path();
-        let filetype = fs::metadata(path)?.file_type();
+    fn skip_special(&self, path: &Path, kind: &'static str) {
+        self.special.lock().unwrap().push(path.to_path_buf());
+        self.record_skip(path, Skip::Special(kind));
+    }
 
-        if filetype.is_file() {
-            let content = String::from_utf8_lossy(&fs::read(&path)?).into_owned();
-            let file_linecount = content.lines().count() as u128;
-            let file_bytes = content.as_bytes().len() as u128;
+    fn record_skip(&self, path: &Path, skip: Skip) {
+        if let Some(ignored) = &self.ignored {
+            ignored.lock().unwrap().push((path.to_path_buf(), skip));
+        }
+    }
 
-            *total_lines.lock().unwrap() += file_linecount;
-            *total_bytes.lock().unwrap() += file_bytes;
+    //entries are looked at as they are on disk, so a symlink is neither a file nor a directory
+    //unless --follow-symlinks asks for it to be treated as whatever it points to.
+    fn file_type(&self, entry: &fs::DirEntry) -> Result<fs::FileType> {
+        if self.follow_symlinks {
+            Ok(fs::metadata(entry.path())?.file_type())
+        } else {
+            entry.file_type()
+        }
+    }
 
-            let filename = entry
-                .file_name()
+    //hard-linked files are the same data under several names (snapshot trees are full of them), so
+    //only the first name found gets counted.
+    fn first_link(&self, path: &Path) -> bool {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.nlink() > 1 => self
+                .linked_files
+                .lock()
                 .unwrap()
-                .to_str()
-                .unwrap_or("?")
-                .to_string();
+                .insert((metadata.dev(), metadata.ino())),
+            _ => true,
+        }
+    }
 
-            let filename = if filename.len() > FILENAME_RENDER_LIMIT {
-                format!("{}...", &filename[..FILENAME_RENDER_LIMIT])
-            } else {
-                filename
-            };
-            if idx == files.len() - 1 {
-                connector = "└";
-            }
-
-            let formatted_indent = match indent_amount {
-                Some(0) => format!("{file_ident_from_zero}{connector}{file_indent_from_dir}"),
-                _ => format!("│{file_ident_from_zero}{connector}{file_indent_from_dir}"),
-            };
-
-            let formatted_output = format!(
-                "{:width$} ({}L, {}B)",
-                {
-                    match path.content_type() {
-                        ContentType::MEDIA => filename.bright_magenta().to_string(),
-                        ContentType::CODE => filename.cyan().to_string(),
-                        ContentType::EXECUTABLE => filename.green().to_string(),
-                        ContentType::TEXT => filename.truecolor(217, 50, 122).to_string(),
-                        ContentType::LICENSE => filename.truecolor(0, 0, 255).to_string(),
-                        ContentType::MAKEFILE => filename.red().to_string(),
-                        _ => filename.to_string(),
-                    }
-                },
-                file_linecount,
-                file_bytes,
-                width = WIDTH
-            );
-            println!("{formatted_indent}{formatted_output}");
-        } else if filetype.is_dir() {
-            let handle = {
-                let total_lines = Arc::clone(&total_lines);
-                let total_bytes = Arc::clone(&total_bytes);
-                let path = PathBuf::from(path);
-
-                thread::spawn(move || {
-                    let recursive_lc =
-                        linecount_display_async(Some(path), Some(indent_amount.unwrap() + 2));
-
-                    if let Ok((lines, bytes)) = recursive_lc {
-                        *total_lines.lock().unwrap() += lines;
-                        *total_bytes.lock().unwrap() += bytes;
-                    }
-                })
-            };
-            handles.push(handle);
+    //forgets everything the last walk saw, for walking the same roots again (lc bench). roots
+    //count as visited up front, so a link back up to one doesn't walk it a second time.
+    fn reset(&self) {
+        self.linked_files.lock().unwrap().clear();
+        self.visited.lock().unwrap().clear();
+        self.noise.lock().unwrap().clear();
+        self.special.lock().unwrap().clear();
+        self.submodule_dirs.lock().unwrap().clear();
+        if let Some(ignored) = &self.ignored {
+            ignored.lock().unwrap().clear();
+        }
+        for root in &self.filter.roots {
+            self.first_visit(root);
         }
     }
-    for handle in handles {
-        handle.join().unwrap();
+
+    //once links are followed the same directory can be reached more than once, or from inside
+    //itself, so each one is only entered the first time its inode comes up.
+    fn first_visit(&self, path: &Path) -> bool {
+        if !self.follow_symlinks {
+            return true;
+        }
+        match fs::metadata(path) {
+            Ok(metadata) => self
+                .visited
+                .lock()
+                .unwrap()
+                .insert((metadata.dev(), metadata.ino())),
+            Err(_) => false,
+        }
     }
 
-    Ok(get_totals(total_lines, total_bytes))
-}
+    //how the walks count a file. with --cache a file that hasn't changed since the last run isn't
+    //read at all, unless dup detection needs its content anyway.
+    fn count(&self, path: &Path) -> Result<FileStat> {
+        interrupt::check()?;
+        let file_stat = match &self.cache {
+            Some(cache) => self.count_cached(path, cache)?,
+            None => self.count_throttled(path)?,
+        };
+        self.record(&file_stat);
+        Ok(file_stat)
+    }
+
+    //every counted file goes by here once, whichever way it was read.
+    fn record(&self, file_stat: &FileStat) {
+        interrupt::record_file();
+        if let Some(languages) = &self.languages
+            && file_stat.counts.binary_files == 0
+        {
+            let name = languages::detect(&file_stat.path).map_or("Other", |lang| lang.name);
+            let mut languages = languages.lock().unwrap();
+            let (files, counts) = languages.entry(name).or_default();
+            *files += 1;
+            *counts += file_stat.counts;
+        }
+        if let Some(longest) = &self.line_lengths
+            && file_stat.counts.binary_files == 0
+        {
+            let mut longest = longest.lock().unwrap();
+            let at = longest.partition_point(|other| {
+                other.counts.longest_line >= file_stat.counts.longest_line
+            });
+            if at < LONGEST_LINE_FILES {
+                longest.insert(at, file_stat.clone());
+                longest.truncate(LONGEST_LINE_FILES);
+            }
+        }
+        if file_stat.counts.long_lines > 0 {
+            self.long_line_files.lock().unwrap().push(file_stat.clone());
+        }
+        if let Some(mixed) = &self.indentation
+            && file_stat.counts.mixed_indent_files > 0
+        {
+            mixed.lock().unwrap().push(file_stat.clone());
+        }
+        if let Some(mixed) = &self.line_endings
+            && file_stat.counts.mixed_ending_files > 0
+        {
+            mixed.lock().unwrap().push(file_stat.clone());
+        }
+        if let Some(untidy) = &self.hygiene
+            && file_stat.counts.trailing_whitespace > 0
+        {
+            untidy.lock().unwrap().push(file_stat.clone());
+        }
+        if let Some(empty) = &self.empty
+            && file_stat.counts.binary_files == 0
+            && file_stat.counts.duplicate_files == 0
+            && file_stat.counts.lfs_pointers == 0
+            && (file_stat.counts.lines == 0 || file_stat.counts.whitespace_files > 0)
+        {
+            empty.lock().unwrap().push(file_stat.clone());
+        }
+        if file_stat.counts.todos > 0 {
+            self.todo_files.lock().unwrap().push(file_stat.clone());
+        }
+        if let Some(decoded) = &self.verbose
+            && file_stat.counts.encoding_name().is_some()
+        {
+            decoded.lock().unwrap().push(file_stat.clone());
+        }
+        if let Some(boms) = &self.boms
+            && file_stat.counts.bom_files > 0
+        {
+            boms.lock().unwrap().push(file_stat.clone());
+        }
+        if let Some(owners) = &self.owners {
+            let relative = self.filter.relative(&file_stat.path);
+            owners.record(Path::new(&relative), &file_stat.counts);
+        }
+        if let Some(workspace) = &self.workspace {
+            let relative = self.filter.relative(&file_stat.path);
+            workspace.record(Path::new(&relative), &file_stat.counts);
+        }
+        if let Some(history) = &self.history {
+            let relative = self.filter.relative(&file_stat.path);
+            history.record(Path::new(&relative), &file_stat.counts);
+        }
+        if let Some(export) = &self.export {
+            let relative = self.filter.relative(&file_stat.path);
+            export.record(Path::new(&relative), &file_stat.counts);
+        }
+        if let Some(tests) = &self.tests
+            && file_stat.counts.binary_files == 0
+        {
+            let measure = self.metric.of(&file_stat.counts);
+            tests.record(&self.filter.roots, &file_stat.path, measure);
+        }
+    }
+
+    fn count_cached(&self, path: &Path, cache: &Cache) -> Result<FileStat> {
+        let metadata = fs::metadata(path)?;
+        let file_stat = match cache.get(path, &metadata) {
+            Some(counts) if self.dups.is_none() && self.dupes.is_none() => FileStat {
+                path: path.to_path_buf(),
+                counts,
+            },
+            _ => self.count_throttled(path)?,
+        };
+        cache.insert(path, &metadata, file_stat.counts);
+        Ok(file_stat)
+    }
+
+    //with --adaptive only so many files are read at once, and how fast they went feeds back into
+    //how many that is. cache hits skip this, they say nothing about the storage.
+    fn count_throttled(&self, path: &Path) -> Result<FileStat> {
+        let Some(throttle) = &self.throttle else {
+            return self.count_uncached(path);
+        };
+
+        throttle.start();
+        let file_stat = self.count_uncached(path);
+        throttle.finish(
+            file_stat
+                .as_ref()
+                .map_or(0, |file_stat| file_stat.counts.bytes),
+        );
+        file_stat
+    }
+
+    //dup detection needs every file whole, otherwise it's up to count_file whether the file gets
+    //read in one go or streamed. binary content has no lines to copy and stays out of the index.
+    fn count_uncached(&self, path: &Path) -> Result<FileStat> {
+        let Some(dups) = self.dups.as_ref().filter(|_| !is_opaque(path)) else {
+            return count_file(path, self);
+        };
+
+        let content = fd::read(path)?;
+        Ok(timings::time(Phase::Counting, || {
+            if !is_binary(&content) {
+                dups.add(path, &content);
+            }
+            count_content(path, &content, self)
+        }))
+    }
+
+    //--statements and --breakdown look at the whole file rather than a chunk at a time, but only
+    //for the files they apply to. line lengths are measured for every text file, and a notebook
+    //always has to be parsed.
+    fn needs_content(&self, path: &Path) -> bool {
+        notebook::is_notebook(path)
+            || (self.statements && statements::is_c_family(path))
+            || (self.logical && statements::syntax(path).is_some())
+            || (self.breakdown && languages::detect(path).is_some())
+            || self.reads_whole_files()
+    }
+
+    //anything measured over every text file that can't be done a chunk at a time. --verbose
+    //needs every file's encoding, which takes more than its first chunk to be sure of.
+    fn reads_whole_files(&self) -> bool {
+        self.measures_lines()
+            || self.chars
+            || self.tokens
+            || self.todos.is_some()
+            || self.empty.is_some()
+            || self.dupes.is_some()
+            || self.no_blank
+            || self.verbose.is_some()
+    }
+
+    //--line-lengths, --long-lines, --indentation, --line-endings, --hygiene or --words, they're all
+    //measured in one go. --line-breaks any needs the whole file too, a \r\n can be split across two
+    //chunks.
+    fn measures_lines(&self) -> bool {
+        self.line_lengths.is_some()
+            || self.long_line_limit.is_some()
+            || self.indentation.is_some()
+            || self.line_endings.is_some()
+            || self.hygiene.is_some()
+            || self.words
+            || self.cr_breaks
+    }
+
+    //--statements only applies to c-family sources, everything else reports 0.
+    fn count_statements(&self, path: &Path, content: &[u8]) -> u128 {
+        if self.statements && statements::is_c_family(path) {
+            statements::count_statements(content)
+        } else {
+            0
+        }
+    }
+
+    //how paths show up in reports: relative to --relative-to when it's given, otherwise relative
+    //to the root they were found under.
+    fn display_path(&self, path: &Path) -> String {
+        match (&self.relative_to, fs::canonicalize(path)) {
+            (Some(base), Ok(path)) => relative_path(base, &path).to_string_lossy().into_owned(),
+            _ => self.filter.relative(path),
+        }
+    }
 
-fn get_totals(total_lines: Arc<Mutex<u128>>, total_bytes: Arc<Mutex<u128>>) -> (u128, u128) {
-    let lines = total_lines.lock().unwrap();
-    let bytes = total_bytes.lock().unwrap();
-    (*lines, *bytes)
+    fn format_counts(&self, path: &Path, counts: &Counts) -> String {
+        if counts.duplicate_files > 0 {
+            "(duplicate)".to_string()
+        } else if counts.binary_files > 0 {
+            format!("(binary, {}B)", counts.bytes)
+        } else if counts.lfs_pointers > 0 {
+            format!(
+                "(lfs pointer, {}B -> {})",
+                counts.bytes,
+                format_byte_count(counts.lfs_bytes)
+            )
+        } else {
+            let mut formatted = format!("({}L, {}B", counts.lines, counts.bytes);
+            if self.words {
+                formatted.push_str(&format!(", {}W", counts.words));
+            }
+            if self.chars {
+                formatted.push_str(&format!(", {}C, {}G", counts.chars, counts.graphemes));
+            }
+            if self.tokens {
+                formatted.push_str(&format!(", ~{}T", counts.tokens));
+            }
+            if self.verbose.is_some() {
+                let encoding = counts.encoding_name().unwrap_or(Encoding::Utf8.name());
+                formatted.push_str(&format!(", {encoding}"));
+            }
+            if self.statements && statements::is_c_family(path) {
+                formatted.push_str(&format!(", {}S", counts.statements));
+            }
+            if self.logical && statements::syntax(path).is_some() {
+                formatted.push_str(&format!(", {} logical", counts.logical));
+            }
+            if let Some(language) = languages::detect(path).filter(|_| self.breakdown) {
+                formatted.push_str(&format!(
+                    ", {} code, {} comment",
+                    counts.code, counts.comments
+                ));
+                if !language.doc_comments.is_empty() {
+                    formatted.push_str(&format!(", {} doc", counts.docs));
+                }
+                formatted.push_str(&format!(", {} blank", counts.blanks));
+                if notebook::is_notebook(path) {
+                    formatted.push_str(&format!(", {} text", counts.text));
+                }
+            }
+            if self.line_lengths.is_some() {
+                formatted.push_str(&format!(", longest {}", counts.longest_line));
+            }
+            if self.long_line_limit.is_some() {
+                formatted.push_str(&format!(", {} long", counts.long_lines));
+            }
+            if self.indentation.is_some() {
+                formatted.push_str(&format!(
+                    ", {} tab/{} space indented",
+                    counts.tab_indented, counts.space_indented
+                ));
+            }
+            if self.line_endings.is_some() {
+                formatted.push_str(&format!(
+                    ", {} LF/{} CRLF/{} CR",
+                    counts.lf_endings, counts.crlf_endings, counts.cr_endings
+                ));
+            }
+            if self.hygiene.is_some() {
+                formatted.push_str(&format!(
+                    ", {} trailing whitespace",
+                    counts.trailing_whitespace
+                ));
+            }
+            if self.todos.is_some() {
+                formatted.push_str(&format!(", {} TODO", counts.todos));
+            }
+            formatted.push(')');
+            formatted
+        }
+    }
 }
 
-fn format_byte_count(byte_count: u128) -> String {
-    if byte_count / 1_000_000_000 > 1 {
-        return format!("{} GB", byte_count as f64 / 1_000_000_000.);
-    } else if byte_count / 1_000_000 > 1 {
-        return format!("{} MB", byte_count as f64 / 1_000_000.);
-    } else if byte_count / 1_000 > 1 {
-        return format!("{} KB", byte_count as f64 / 1_000.);
+//reading a fifo blocks until something writes to it and a device file can go on forever, so
+//anything that isn't a regular file, directory or link gets named here and left alone.
+fn special_kind(filetype: &fs::FileType) -> Option<&'static str> {
+    if filetype.is_fifo() {
+        Some("fifo")
+    } else if filetype.is_socket() {
+        Some("socket")
+    } else if filetype.is_block_device() {
+        Some("block device")
+    } else if filetype.is_char_device() {
+        Some("character device")
     } else {
-        return format!("{} B", byte_count);
+        None
     }
 }
 
-fn format_and_print_results(lines: u128, bytes: u128, time: Duration) {
-    let f_bytes = format_byte_count(bytes);
-    println!("╭───────────────────────────────────────────────────╮");
-    println!(
-        "│{:<51}│\n│{:<51}│\n│{:<51}│",
-        format!("Lines       :{lines}"),
-        format!("Bytes       :{f_bytes}"),
-        format!("Time Taken  :{:.5} Seconds", time.as_secs_f64())
-    );
-    println!("╰───────────────────────────────────────────────────╯")
+//a directory on a different device than the one holding it is where another filesystem is mounted.
+fn is_mount_point(path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        return false;
+    };
+    match (fs::metadata(path), fs::metadata(parent)) {
+        (Ok(dir), Ok(parent)) => dir.dev() != parent.dev(),
+        _ => false,
+    }
 }
 
-fn main() -> std::io::Result<()> {
-    let calls = Command::new("lc")
-        .version("1.2")
-        .author("Ethan Water")
-        .about("Line Counting Program")
-        .args([
-            Arg::new("path")
-                .short('p')
-                .long("path")
-                .action(ArgAction::Set)
-                .value_name("PATH")
-                .help("Provides a path to lc"),
-            Arg::new("display")
-                .short('d')
-                .long("display")
-                .action(ArgAction::SetTrue)
-                .help("Displays the filetree search"),
-        ])
-        .get_matches();
+//a submodule's working tree has a .git of its own, a file pointing into the parent's .git/modules
+//once it's initialized. one that isn't initialized is an empty directory and doesn't matter.
+fn is_submodule(path: &Path) -> bool {
+    path.join(".git").exists()
+}
 
-    let path = calls.get_one::<String>("path").map(PathBuf::from);
+//the reference serial counter. linecount_async counts through this too, so any disagreement
+//between the two comes from the parallel bookkeeping rather than the counting itself.
+fn count_file(path: &Path, scan: &Scan) -> Result<FileStat> {
+    let len = fs::metadata(path)?.len();
+    if is_opaque(path) {
+        return Ok(FileStat {
+            path: path.to_path_buf(),
+            counts: Counts::of_binary(len as u128),
+        });
+    }
+    if len >= SPLIT_THRESHOLD && !scan.needs_content(path) {
+        return count_split(path, len);
+    }
 
-    if *calls.get_one::<bool>("display").unwrap_or(&false) {
-        let start_time = Instant::now();
-        let (lines, bytes) = linecount_display(path, None)?;
-        let end_time = Instant::now();
-        format_and_print_results(lines, bytes, end_time - start_time);
+    #[cfg(feature = "mmap")]
+    if len >= MMAP_THRESHOLD {
+        return count_mapped(path, scan);
+    }
+
+    if scan.needs_content(path) {
+        let content = fd::read(path)?; // Read the raw bytes
+        Ok(timings::time(Phase::Counting, || {
+            count_content(path, &content, scan)
+        }))
     } else {
-        let start_time = Instant::now();
-        let (lines, bytes) = linecount_async(path)?;
-        let end_time = Instant::now();
-        format_and_print_results(lines, bytes, end_time - start_time);
+        count_stream(path)
+    }
+}
+
+//a single huge file would otherwise keep one worker busy long after the rest of the tree is done,
+//so past SPLIT_THRESHOLD its byte range is cut into pieces whose newlines are counted in parallel.
+//statements can't be split up like that, a piece might start inside a string or comment.
+fn count_split(path: &Path, len: u64) -> Result<FileStat> {
+    let file = fd::open(path)?;
+    let mut head = vec![0; BINARY_SNIFF_LEN];
+    file.read_exact_at(&mut head, 0)?;
+    if encoding::utf16(&head).is_some() {
+        return count_stream(path);
     }
+    if is_binary(&head) {
+        return Ok(FileStat {
+            path: path.to_path_buf(),
+            counts: Counts::of_binary(len as u128),
+        });
+    }
+    let bom = encoding::bom(&head).map_or(0, |(_, len)| len);
 
-    Ok(())
+    let newlines = (0..len.div_ceil(SPLIT_PIECE_LEN))
+        .into_par_iter()
+        .map(|piece| {
+            let end = ((piece + 1) * SPLIT_PIECE_LEN).min(len);
+            let mut offset = piece * SPLIT_PIECE_LEN;
+            let _memory = budget::reserve_memory(READ_CHUNK_LEN);
+            let mut chunk = vec![0; READ_CHUNK_LEN];
+            let mut newlines = 0u128;
+
+            while offset < end {
+                let read = ((end - offset) as usize).min(READ_CHUNK_LEN);
+                timings::time(Phase::Reading, || {
+                    file.read_exact_at(&mut chunk[..read], offset)
+                })?;
+                newlines += timings::time(Phase::Counting, || {
+                    chunk[..read].iter().filter(|&&byte| byte == b'\n').count() as u128
+                });
+                offset += read as u64;
+            }
+            Ok(newlines)
+        })
+        .sum::<Result<u128>>()?;
+
+    let mut last = [0];
+    file.read_exact_at(&mut last, len - 1)?;
+    let lines = newlines + u128::from(last[0] != b'\n');
+    Ok(FileStat {
+        path: path.to_path_buf(),
+        counts: Counts::of_file(path, lines, (len as usize - bom) as u128, 0).with_bom(bom),
+    })
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::linecount_async;
-    use std::time::Instant;
+//past MMAP_THRESHOLD the file is mapped and counted in place, which saves the read syscalls and
+//the copies into a buffer. the mapping is only valid while nobody truncates the file under us, a
+//log being rotated mid-count can take the process down with SIGBUS, hence the feature flag.
+#[cfg(feature = "mmap")]
+fn count_mapped(path: &Path, scan: &Scan) -> Result<FileStat> {
+    let file = fd::open(path)?;
+    let map = unsafe { memmap2::Mmap::map(&*file)? };
+    //the pages are only read in as they're touched, so with a mapping reading shows up as counting.
+    Ok(timings::time(Phase::Counting, || {
+        count_content(path, &map, scan)
+    }))
+}
 
-    const TEST_ITERATIONS: u128 = 1000;
+//lines and bytes without holding the file in memory: newlines are counted a chunk at a time, so a
+//multi-GB log costs READ_CHUNK_LEN of memory like everything else. agrees with count_content,
+//a last line without a trailing newline still counts.
+fn count_stream(path: &Path) -> Result<FileStat> {
+    let mut file = fd::open(path)?;
+    //small files don't need the whole chunk, one spare byte is enough to see the end of the file.
+    let len = file.metadata()?.len() as usize;
+    let _memory = budget::reserve_memory(READ_CHUNK_LEN.min(len + 1));
+    let mut chunk = vec![0; READ_CHUNK_LEN.min(len + 1)];
+    let mut counter = LineCounter::default();
+
+    loop {
+        interrupt::check()?;
+        let read = match timings::time(Phase::Reading, || file.read(&mut chunk)) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if !timings::time(Phase::Counting, || counter.feed(&chunk[..read])) {
+            break;
+        }
+    }
+
+    Ok(FileStat {
+        path: path.to_path_buf(),
+        counts: counter.finish(path)?,
+    })
+}
+
+//newline counting fed a chunk at a time, shared by the readers that never hold a whole file.
+#[derive(Default)]
+struct LineCounter {
+    bytes: u64,
+    newlines: u128,
+    last: u8,
+    binary: bool,
+    //utf-16, only counted once the whole file is decoded.
+    wide: bool,
+    //how long the byte order mark at the start was, if there was one.
+    bom: usize,
+    //the object size, while everything read so far is an lfs pointer.
+    lfs: Option<u128>,
+}
+
+impl LineCounter {
+    //false once the file turns out to be binary or utf-16, there's no point reading the rest of it
+    //a chunk at a time.
+    fn feed(&mut self, chunk: &[u8]) -> bool {
+        let unsniffed = BINARY_SNIFF_LEN.saturating_sub(self.bytes as usize);
+        if unsniffed > 0 {
+            let head = &chunk[..chunk.len().min(unsniffed)];
+            if is_binary(head) {
+                self.binary = true;
+                return false;
+            }
+            if encoding::utf16(head).is_some() {
+                self.wide = true;
+                return false;
+            }
+            if self.bytes == 0 {
+                self.bom = encoding::bom(chunk).map_or(0, |(_, len)| len);
+                self.lfs = lfs::pointer_size(chunk);
+            } else {
+                self.lfs = None;
+            }
+        }
+
+        self.newlines += chunk.iter().filter(|&&byte| byte == b'\n').count() as u128;
+        self.bytes += chunk.len() as u64;
+        if let Some(&last) = chunk.last() {
+            self.last = last;
+        }
+        true
+    }
+
+    //a last line without a trailing newline still counts, same as with str::lines.
+    fn finish(&self, path: &Path) -> Result<Counts> {
+        if self.binary {
+            return Ok(Counts::of_binary(fs::metadata(path)?.len() as u128));
+        }
+        if self.wide {
+            let content = fd::read(path)?;
+            let bom = encoding::bom(&content).map_or(0, |(_, len)| len);
+            let (encoding, decoded) = encoding::decode(&content);
+            let bytes = (content.len() - bom) as u128;
+            let counts = Counts::of_file(path, count_lines(&decoded), bytes, 0);
+            return Ok(counts.encoded(encoding).with_bom(bom));
+        }
+        if let Some(object) = self.lfs {
+            return Ok(Counts::of_lfs_pointer(self.bytes as u128, object));
+        }
+        let bytes = self.bytes - self.bom as u64;
+        let lines = self.newlines + u128::from(bytes > 0 && self.last != b'\n');
+        Ok(Counts::of_file(path, lines, bytes as u128, 0).with_bom(self.bom))
+    }
+}
+
+fn count_content(path: &Path, content: &[u8], scan: &Scan) -> FileStat {
+    let counts = if is_binary(content) {
+        Counts::of_binary(content.len() as u128)
+    } else if let Some(object) = lfs::pointer_size(content) {
+        Counts::of_lfs_pointer(content.len() as u128, object)
+    } else {
+        //bytes are what's on disk less the byte order mark, everything else is counted on the text
+        //once it's utf-8.
+        let bom = encoding::bom(content).map_or(0, |(_, len)| len);
+        let bytes = (content.len() - bom) as u128;
+        let (encoding, decoded) = encoding::decode(content);
+        //a notebook is counted by the source in its cells, not the json holding them.
+        let notebook = notebook::is_notebook(path)
+            .then(|| notebook::parse(&decoded))
+            .flatten();
+        let cells = notebook
+            .as_ref()
+            .map(|notebook| format!("{}{}", notebook.code, notebook.text));
+        let content = cells.as_deref().map_or(&*decoded, str::as_bytes);
+        let lines = match scan.cr_breaks {
+            true => count_lines_any(content),
+            false => count_lines(content),
+        };
+        let counted = match scan.no_blank {
+            true => count_nonblank_lines(content, scan.cr_breaks),
+            false => lines,
+        };
+        let counts = Counts::of_file(path, counted, bytes, scan.count_statements(path, content))
+            .encoded(encoding)
+            .with_bom(bom);
+        let counts = match scan.measures_lines() {
+            true => {
+                let stats = linestats::measure(content, scan.long_line_limit);
+                Counts {
+                    longest_line: stats.longest,
+                    line_chars: stats.chars,
+                    long_lines: stats.long,
+                    tab_indented: stats.tab_indented,
+                    space_indented: stats.space_indented,
+                    mixed_indent_files: u128::from(
+                        stats.tab_indented > 0 && stats.space_indented > 0,
+                    ),
+                    lf_endings: stats.lf,
+                    crlf_endings: stats.crlf,
+                    cr_endings: stats.cr,
+                    mixed_ending_files: u128::from(
+                        [stats.lf, stats.crlf, stats.cr]
+                            .iter()
+                            .filter(|&&endings| endings > 0)
+                            .count()
+                            > 1,
+                    ),
+                    trailing_whitespace: stats.trailing_whitespace,
+                    words: stats.words,
+                    ..counts
+                }
+            }
+            false => counts,
+        };
+        let counts = match scan.chars {
+            true => {
+                let (chars, graphemes) = graphemes::count(content);
+                Counts {
+                    chars,
+                    graphemes,
+                    ..counts
+                }
+            }
+            false => counts,
+        };
+        let counts = Counts {
+            whitespace_files: u128::from(
+                scan.empty.is_some() && lines > 0 && content.trim_ascii().is_empty(),
+            ),
+            ..counts
+        };
+        let counts = match statements::syntax(path).filter(|_| scan.logical) {
+            Some(syntax) => Counts {
+                logical: statements::count_logical(content, syntax),
+                ..counts
+            },
+            None => counts,
+        };
+        let counts = match &scan.todos {
+            Some(todos) => Counts {
+                todos: todos.count(content),
+                ..counts
+            },
+            None => counts,
+        };
+        let counts = match scan.tokens {
+            true => Counts {
+                tokens: tokens::estimate(content),
+                ..counts
+            },
+            false => counts,
+        };
+        let language = notebook
+            .as_ref()
+            .and_then(|notebook| languages::by_name(notebook.language.as_deref()?))
+            .or_else(|| languages::detect_in(path, content));
+        match language.filter(|_| scan.breakdown) {
+            Some(language) => {
+                let (code, text) = match &notebook {
+                    Some(notebook) => (
+                        notebook.code.as_bytes(),
+                        count_lines(notebook.text.as_bytes()),
+                    ),
+                    None => (content, 0),
+                };
+                let breakdown = languages::classify(code, language);
+                Counts {
+                    code: breakdown.code,
+                    comments: breakdown.comments,
+                    docs: breakdown.docs,
+                    blanks: breakdown.blanks,
+                    text,
+                    ..counts
+                }
+            }
+            None => counts,
+        }
+    };
+    //with --dedupe only the first file with some content is counted, whichever that was. empty
+    //files are all the same and waste nothing, --empty is for those.
+    let counts = match &scan.dupes {
+        Some(dupes)
+            if !content.is_empty()
+                && !dupes.add(path, content, counts.lines, counts.bytes)
+                && scan.dedupe =>
+        {
+            Counts {
+                duplicate_files: 1,
+                ..Counts::default()
+            }
+        }
+        _ => counts,
+    };
+
+    FileStat {
+        path: path.to_path_buf(),
+        counts,
+    }
+}
+
+//newlines straight off the bytes, whatever the encoding, plus a last line without one. the same
+//as str::lines would give, without converting anything.
+fn count_lines(content: &[u8]) -> u128 {
+    let newlines = content.iter().filter(|&&byte| byte == b'\n').count() as u128;
+    newlines + u128::from(content.last().is_some_and(|&last| last != b'\n'))
+}
+
+//--line-breaks any: a \r that isn't part of a \r\n ends a line as well, for files from classic
+//mac os. a \r\n still ends just the one line.
+fn count_lines_any(content: &[u8]) -> u128 {
+    let breaks = content
+        .iter()
+        .enumerate()
+        .filter(|&(idx, &byte)| {
+            byte == b'\n' || (byte == b'\r' && content.get(idx + 1) != Some(&b'\n'))
+        })
+        .count() as u128;
+    breaks
+        + u128::from(
+            content
+                .last()
+                .is_some_and(|&last| last != b'\n' && last != b'\r'),
+        )
+}
+
+//--no-blank: only the lines with something besides whitespace on them. with --line-breaks any a
+//lone \r splits lines too, the empty piece between a \r and its \n is blank anyway.
+fn count_nonblank_lines(content: &[u8], cr_breaks: bool) -> u128 {
+    content
+        .split(|&byte| byte == b'\n' || (cr_breaks && byte == b'\r'))
+        .filter(|line| !line.trim_ascii().is_empty())
+        .count() as u128
+}
+
+//the same heuristic git and grep use: text files practically never contain a NUL byte, while
+//object files, images and archives almost always have one near the start. utf-16 text is full of
+//them too, but gets decoded and counted instead.
+fn is_binary(content: &[u8]) -> bool {
+    let head = &content[..content.len().min(BINARY_SNIFF_LEN)];
+    head.contains(&0) && encoding::utf16(head).is_none()
+}
+
+//with io_uring a directory's files are handed to the pool this many at a time, each worker
+//counting its share through its own ring.
+#[cfg(feature = "uring")]
+const URING_CHUNK: usize = 256;
+
+//how many counted files can queue up for the aggregator before workers wait for it to catch up.
+const FILE_CHANNEL_BOUND: usize = 1024;
+
+//workers send one FileStat per counted file to a single aggregator thread, which owns the total
+//and everything else collected per file. nothing on the counting side shares state, and the
+//aggregator is the one place that sees every file go by as it's counted.
+fn linecount_async(dir: Option<PathBuf>, scan: Arc<Scan>) -> Result<Counts> {
+    let dir_path = dir.unwrap_or(env::current_dir()?);
+    let (sender, receiver) = mpsc::sync_channel(FILE_CHANNEL_BOUND);
+
+    thread::scope(|s| {
+        let aggregator = s.spawn(|| aggregate(receiver, &scan));
+        let walked = walk::walk_parallel(&dir_path, &scan, &|listing| {
+            send_file_stats(listing, &scan, &sender)
+        });
+        drop(sender);
+
+        let total = aggregator.join().unwrap();
+        walked.map(|_| total)
+    })
+}
+
+fn aggregate(receiver: Receiver<FileStat>, scan: &Scan) -> Counts {
+    let mut total = Counts::default();
+    for file_stat in receiver {
+        total += file_stat.counts;
+        if let Some(stats) = &scan.stats {
+            stats.lock().unwrap().push(file_stat);
+        }
+    }
+    total
+}
+
+//a file that can't be read is left out rather than taking the rest of its directory with it.
+fn send_file_stats(listing: &Listing, scan: &Scan, sender: &SyncSender<FileStat>) {
+    //io_uring only does plain line counting, and if the ring can't be set up the files are just
+    //read the usual way.
+    #[cfg(feature = "uring")]
+    if scan.uring
+        && scan.dups.is_none()
+        && scan.cache.is_none()
+        && !scan.statements
+        && !scan.logical
+        && !scan.breakdown
+        && !scan.reads_whole_files()
+    {
+        //notebooks have to be parsed, they go the regular way with the files that aren't read.
+        let (opaque, readable) = listing
+            .files
+            .iter()
+            .cloned()
+            .partition::<Vec<_>, _>(|file| is_opaque(file) || notebook::is_notebook(file));
+        readable.par_chunks(URING_CHUNK).for_each(|chunk| {
+            if interrupt::requested() {
+                return;
+            }
+            match uring::count_files(chunk) {
+                Ok(file_stats) => {
+                    for file_stat in file_stats.into_iter().flatten() {
+                        scan.record(&file_stat);
+                        sender.send(file_stat).unwrap();
+                    }
+                }
+                Err(_) => send_counted(chunk, scan, sender),
+            }
+        });
+        send_counted(&opaque, scan, sender);
+        return;
+    }
+
+    send_counted(&listing.files, scan, sender);
+}
+
+//a directory's files are spread over the pool too, a flat directory with 200k files in it
+//shouldn't be counted by one worker.
+fn send_counted(files: &[PathBuf], scan: &Scan, sender: &SyncSender<FileStat>) {
+    files.par_iter().for_each(|file| {
+        if let Ok(file_stat) = scan.count(file) {
+            sender.send(file_stat).unwrap();
+        }
+    });
+}
+
+//the walk --estimate does before sampling: every file that would be counted, with its size, but
+//nothing is read.
+fn collect_files(root: &Path, scan: &Scan, files: &mut Vec<(PathBuf, u64)>) -> Result<()> {
+    walk::walk(root, scan, |listing| {
+        for file in &listing.files {
+            if let Ok(metadata) = fs::metadata(file) {
+                files.push((file.clone(), metadata.len()));
+            }
+        }
+        Ok(())
+    })
+}
+
+//--rev and --staged: every file of a revision or of the index, read from the object database so
+//the working tree is left alone.
+fn linecount_blobs(mut blobs: Vec<git::Blob>, scan: &Scan) -> Result<Counts> {
+    blobs.retain(|blob| scan.filter.includes_path(&blob.path));
+    let stats = diff::count_each(&blobs, scan)?;
+
+    let mut counts = Counts::default();
+    for file_stat in &stats {
+        scan.record(file_stat);
+        counts += file_stat.counts;
+    }
+    Ok(counts)
+}
+
+//counts a random `fraction` of the files and scales the line (and statement) counts up to the
+//whole tree. bytes come from the file sizes, so they're exact.
+fn estimate_counts(roots: &[PathBuf], scan: &Scan, fraction: f64) -> Result<()> {
+    let start_time = Instant::now();
+    let mut files = Vec::new();
+    for root in roots {
+        collect_files(root, scan, &mut files)?;
+    }
+
+    let sample_size = ((files.len() as f64 * fraction).ceil() as usize).min(files.len());
+    let mut lines = Vec::with_capacity(sample_size);
+    let mut statements = Vec::with_capacity(sample_size);
+    let mut logical = Vec::with_capacity(sample_size);
+    //files in a random order, taken until the sample is full. one that can't be read is skipped
+    //like any other mode skips it, and the next one takes its place.
+    let mut order = files.iter().collect::<Vec<_>>();
+    order.shuffle(&mut rand::rng());
+    for (path, _) in order {
+        if lines.len() == sample_size {
+            break;
+        }
+        let Ok(file_stat) = count_file(path, scan) else {
+            continue;
+        };
+        let counts = file_stat.counts;
+        lines.push(counts.lines);
+        statements.push(counts.statements);
+        logical.push(counts.logical);
+    }
+
+    let sampled = lines.len();
+    let bytes = files.iter().map(|&(_, len)| len as u128).sum::<u128>();
+    let lines = estimate::extrapolate(&lines, files.len());
+    println!("╭───────────────────────────────────────────────────╮");
+    println!(
+        "│{:<51}│\n│{:<51}│",
+        format!("Lines       :~{:.0} ±{:.0}", lines.total, lines.margin),
+        format!("Bytes       :{}", format_byte_count(bytes)),
+    );
+    if scan.statements {
+        let statements = estimate::extrapolate(&statements, files.len());
+        println!(
+            "│{:<51}│",
+            format!(
+                "Statements  :~{:.0} ±{:.0}",
+                statements.total, statements.margin
+            )
+        );
+    }
+    if scan.logical {
+        let logical = estimate::extrapolate(&logical, files.len());
+        println!(
+            "│{:<51}│",
+            format!("Logical     :~{:.0} ±{:.0}", logical.total, logical.margin)
+        );
+    }
+    println!(
+        "│{:<51}│\n│{:<51}│",
+        format!("Sampled     :{sampled}/{} files", files.len()),
+        format!(
+            "Time Taken  :{:.5} Seconds",
+            start_time.elapsed().as_secs_f64()
+        )
+    );
+    println!("╰───────────────────────────────────────────────────╯");
+    println!("{}", "± is a 95% confidence interval".dimmed());
+    Ok(())
+}
+
+//sanity check for the parallel counter: recounts a random sample of the files it saw, one by one,
+//and returns every file whose serial count disagrees with what the parallel scan recorded.
+fn verify_parallel(scan: &Scan, sample_size: usize) -> Result<Vec<(FileStat, FileStat)>> {
+    let stats = scan.stats.as_ref().unwrap().lock().unwrap();
+    let mut mismatches = Vec::new();
+
+    for parallel in stats.sample(&mut rand::rng(), sample_size) {
+        let serial = count_file(&parallel.path, scan)?;
+        if serial != *parallel {
+            mismatches.push((parallel.clone(), serial));
+        }
+    }
+
+    Ok(mismatches)
+}
+
+//the same walk as the display without any of the printing, and on one thread. lc bench uses it
+//as the baseline the other modes are compared against.
+fn linecount_serial(root: &Path, scan: &Scan) -> Result<Counts> {
+    let mut total = Counts::default();
+    walk::walk(root, scan, |listing| {
+        for file in &listing.files {
+            if let Ok(file_stat) = scan.count(file) {
+                total += file_stat.counts;
+            }
+        }
+        Ok(())
+    })?;
+    Ok(total)
+}
+
+//one directory of the tree display, counted but not printed yet. a file that can't be read is
+//left out. subdirectories are kept by path, in the order the listing had them, and looked up in
+//the tree when it gets rendered.
+struct DirNode {
+    depth: usize,
+    files: Vec<FileStat>,
+    dirs: Vec<PathBuf>,
+    //everything beneath the directory added up, shown on its line. only known up front when the
+    //whole tree is counted before printing, so --stream goes without.
+    subtotal: Option<Counts>,
+}
+
+//directories are counted in parallel into one tree, which a single thread then prints in walk
+//order, so the output is the same as counting one directory at a time would give.
+fn linecount_display(root: &Path, scan: &Scan, out: &mut impl Write) -> Result<Counts> {
+    let mut tree = count_tree(root, scan)?;
+    render_tree(root, |dir| tree.remove(dir), scan, None, out)
+}
+
+//the trees of all the roots, each counted in full before any of them is printed, so the shares of
+//--percent are of everything there is.
+fn linecount_display_shares(
+    roots: &[PathBuf],
+    scan: &Scan,
+    out: &mut impl Write,
+) -> Result<Counts> {
+    let trees = roots
+        .iter()
+        .map(|root| count_tree(root, scan))
+        .collect::<Result<Vec<_>>>()?;
+    let whole = roots
+        .iter()
+        .zip(&trees)
+        .filter_map(|(root, tree)| tree.get(root)?.subtotal)
+        .map(|subtotal| scan.metric.of(&subtotal))
+        .sum::<u128>();
+    let mut total = Counts::default();
+    for (root, mut tree) in roots.iter().zip(trees) {
+        total += render_tree(root, |dir| tree.remove(dir), scan, Some(whole), out)?;
+    }
+    Ok(total)
+}
+
+fn count_tree(root: &Path, scan: &Scan) -> Result<HashMap<PathBuf, DirNode>> {
+    let tree = Mutex::new(HashMap::new());
+    walk::walk_parallel(root, scan, &|listing| {
+        let node = DirNode {
+            depth: listing.depth,
+            files: count_listing(listing, scan),
+            dirs: listing.dirs.clone(),
+            subtotal: None,
+        };
+        tree.lock().unwrap().insert(listing.dir.clone(), node);
+    })?;
+    let mut tree = tree.into_inner().unwrap();
+    add_subtotals(root, &mut tree);
+    Ok(tree)
+}
+
+//--stream: the same tree, but printed while it's still being counted. each directory is printed
+//as soon as it and everything before it are done, the ones finished ahead of their turn wait in
+//the sequencer, so a huge tree starts showing up right away without its lines getting mixed up.
+fn linecount_display_streamed(root: &Path, scan: &Scan, out: &mut impl Write) -> Result<Counts> {
+    let sequencer = Sequencer::new();
+    thread::scope(|s| {
+        let walker = s.spawn(|| {
+            let walked = walk::walk_parallel(root, scan, &|listing| {
+                let node = DirNode {
+                    depth: listing.depth,
+                    files: count_listing(listing, scan),
+                    dirs: listing.dirs.clone(),
+                    subtotal: None,
+                };
+                sequencer.push(listing.dir.clone(), node);
+            });
+            sequencer.finish();
+            walked
+        });
+
+        let total = render_tree(root, |dir| sequencer.take(dir), scan, None, out);
+        walker.join().unwrap()?;
+        total
+    })
+}
+
+//fills in each directory's subtotal, children before their parents: the directories in walk
+//order, added up from the back.
+fn add_subtotals(root: &Path, tree: &mut HashMap<PathBuf, DirNode>) {
+    let mut order = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        if let Some(node) = tree.get(&dir) {
+            stack.extend(node.dirs.iter().cloned());
+            order.push(dir);
+        }
+    }
+    for dir in order.iter().rev() {
+        let node = &tree[dir];
+        let mut subtotal = Counts::default();
+        for file_stat in &node.files {
+            subtotal += file_stat.counts;
+        }
+        for child in &node.dirs {
+            subtotal += tree
+                .get(child)
+                .and_then(|child| child.subtotal)
+                .unwrap_or_default();
+        }
+        tree.get_mut(dir).unwrap().subtotal = Some(subtotal);
+    }
+}
+
+fn count_listing(listing: &Listing, scan: &Scan) -> Vec<FileStat> {
+    listing
+        .files
+        .par_iter()
+        .filter_map(|file| scan.count(file).ok())
+        .collect()
+}
+
+//pre-order over an explicit stack like walk::walk, `take` hands over each directory's node when
+//its turn comes. a directory that couldn't be listed has no node and is skipped along with
+//everything under it. with `whole`, files and directories are shown as a share of it too.
+fn render_tree(
+    root: &Path,
+    mut take: impl FnMut(&Path) -> Option<DirNode>,
+    scan: &Scan,
+    whole: Option<u128>,
+    out: &mut impl Write,
+) -> Result<Counts> {
+    let mut total = Counts::default();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Some(node) = take(&dir) else {
+            continue;
+        };
+        total += print_dir(&dir, &node, scan, whole, out)?;
+        stack.extend(node.dirs.into_iter().rev());
+    }
+    Ok(total)
+}
+
+//one directory of the tree: its own line, then a line per file.
+fn print_dir(
+    dir: &Path,
+    node: &DirNode,
+    scan: &Scan,
+    whole: Option<u128>,
+    out: &mut impl Write,
+) -> Result<Counts> {
+    let render_start = Instant::now();
+    let mut total = Counts::default();
+    let indent_amount = node.depth * 2;
+    let file_indent_from_zero_size = if indent_amount > 0 {
+        indent_amount + 1
+    } else {
+        0
+    };
+
+    let (dir_indent, file_indent_from_dir, file_ident_from_zero) = (
+        "─".repeat(indent_amount),
+        "─".repeat(2),
+        " ".repeat(file_indent_from_zero_size),
+    );
+    let dir_path_str = dir
+        .file_name()
+        .unwrap_or(dir.as_os_str())
+        .to_str()
+        .unwrap_or_default()
+        .blue()
+        .bold();
+
+    let files = &node.files;
+    let dir_spark = if scan.sparkline && !files.is_empty() {
+        let line_counts = files
+            .iter()
+            .map(|file_stat| scan.metric.of(&file_stat.counts))
+            .collect::<Vec<_>>();
+        format!(" {}", render_sparkline(&line_counts).dimmed())
+    } else {
+        String::new()
+    };
+
+    let dir_subtotal = node.subtotal.map_or(String::new(), |subtotal| {
+        let subtotal = format!(
+            "({}L, {})",
+            subtotal.lines,
+            format_byte_count(subtotal.bytes)
+        );
+        format!(" {}", subtotal.dimmed())
+    });
+    let share = |counts: &Counts| {
+        whole.map_or(String::new(), |whole| {
+            format!(" {}", format_share(scan.metric.of(counts), whole))
+        })
+    };
+    let dir_share = node.subtotal.as_ref().map_or(String::new(), share);
+    match node.depth {
+        0 => writeln!(
+            out,
+            "{dir_indent}{dir_path_str}/{dir_subtotal}{dir_share}{dir_spark}"
+        )?,
+        _ => writeln!(
+            out,
+            "├{dir_indent}{dir_path_str}/{dir_subtotal}{dir_share}{dir_spark}"
+        )?,
+    }
+
+    for (idx, FileStat { path, counts }) in files.iter().enumerate() {
+        let mut connector = "├";
+        total += *counts;
+
+        let filename = path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap_or("?")
+            .to_string();
+
+        let filename = if filename.len() > FILENAME_RENDER_LIMIT {
+            format!("{}...", &filename[..FILENAME_RENDER_LIMIT])
+        } else {
+            filename
+        };
+
+        //if last file in head/sub-directory
+        if idx == files.len() - 1 {
+            connector = "└";
+        }
+
+        let formatted_indent: String = match node.depth {
+            0 => format!("{file_ident_from_zero}{connector}{file_indent_from_dir}"),
+            _ => format!("|{file_ident_from_zero}{connector}{file_indent_from_dir}"),
+        };
+
+        let formatted_output = format!(
+            "{:width$} {}",
+            {
+                match path.content_type() {
+                    ContentType::MEDIA => filename.bright_magenta().to_string(),
+                    ContentType::CODE => filename.cyan().to_string(),
+                    ContentType::EXECUTABLE => filename.green().to_string(),
+                    ContentType::TEXT => filename.truecolor(217, 50, 122).to_string(),
+                    ContentType::LICENSE => filename.truecolor(0, 0, 255).to_string(),
+                    ContentType::MAKEFILE => filename.red().to_string(),
+                    ContentType::LOCKFILE => filename.dimmed().to_string(),
+                    _ => filename.to_string(),
+                }
+            },
+            scan.format_counts(path, counts),
+            width = WIDTH
+        );
+        writeln!(out, "{formatted_indent}{formatted_output}{}", share(counts))?;
+    }
+    timings::add(Phase::Rendering, render_start.elapsed());
+    Ok(total)
+}
+
+//histogram of a directory's files bucketed by line count, smallest files on the left. each bucket
+//covers 4x the line range of the one before it and the bars are scaled to the fullest bucket.
+fn render_sparkline(line_counts: &[u128]) -> String {
+    let mut buckets = [0usize; SPARKLINE_BUCKETS];
+    for &lines in line_counts {
+        let mut bucket = 0;
+        let mut ceiling = 2;
+        while lines >= ceiling && bucket < SPARKLINE_BUCKETS - 1 {
+            bucket += 1;
+            ceiling *= 4;
+        }
+        buckets[bucket] += 1;
+    }
+
+    let fullest = buckets.iter().copied().max().unwrap_or_default().max(1);
+    buckets
+        .iter()
+        .map(|&count| match count {
+            0 => ' ',
+            _ => SPARKLINE_BARS[(count * (SPARKLINE_BARS.len() - 1)).div_ceil(fullest)],
+        })
+        .collect()
+}
+
+//the path that leads from the `base` directory to `path`, stepping out with ".." where they part
+//ways. both have to be canonical.
+fn relative_path(base: &Path, path: &Path) -> PathBuf {
+    let base_components = base.components().collect::<Vec<_>>();
+    let path_components = path.components().collect::<Vec<_>>();
+    let shared = base_components
+        .iter()
+        .zip(&path_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in shared..base_components.len() {
+        relative.push("..");
+    }
+    for component in &path_components[shared..] {
+        relative.push(component);
+    }
+    relative
+}
+
+//two roots overlap when one is the other or sits somewhere beneath it. returns the (inner, outer)
+//pair for the first overlap found, as the user spelled them.
+fn find_overlapping_roots(roots: &[PathBuf]) -> Result<Option<(PathBuf, PathBuf)>> {
+    let canonical = roots
+        .iter()
+        .map(fs::canonicalize)
+        .collect::<Result<Vec<_>>>()?;
+
+    for (i, inner) in canonical.iter().enumerate() {
+        for (j, outer) in canonical.iter().enumerate() {
+            if i != j && inner.starts_with(outer) {
+                return Ok(Some((roots[i].clone(), roots[j].clone())));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn format_byte_count(byte_count: u128) -> String {
+    if byte_count / 1_000_000_000 > 1 {
+        format!("{} GB", byte_count as f64 / 1_000_000_000.)
+    } else if byte_count / 1_000_000 > 1 {
+        format!("{} MB", byte_count as f64 / 1_000_000.)
+    } else if byte_count / 1_000 > 1 {
+        format!("{} KB", byte_count as f64 / 1_000.)
+    } else {
+        format!("{} B", byte_count)
+    }
+}
+
+fn print_dup_report(report: &dup::DupReport, scan: &Scan) {
+    println!("Duplicated lines ({DUP_BLOCK_LINES}+ line blocks):");
+    for (language, (duplicated, total)) in &report.languages {
+        let percent = match total {
+            0 => 0.,
+            _ => *duplicated as f64 * 100. / *total as f64,
+        };
+        println!("  {language:<12}{percent:>6.2}%  ({duplicated}/{total} lines)");
+    }
+
+    if report.blocks.is_empty() {
+        return;
+    }
+    println!("Top duplicated blocks:");
+    for block in &report.blocks {
+        println!("  {}x  {}", block.locations.len(), block.preview.yellow());
+        for (path, line) in &block.locations {
+            println!("      {}:{line}", scan.display_path(path));
+        }
+    }
+}
+
+fn print_language_report(languages: &HashMap<&'static str, (u128, Counts)>, scan: &Scan) {
+    let mut rows = languages.iter().collect::<Vec<_>>();
+    let metric = |counts: &Counts| scan.metric.of(counts);
+    rows.sort_by(|a, b| metric(&b.1.1).cmp(&metric(&a.1.1)).then(a.0.cmp(b.0)));
+
+    //only notebooks have text, the column is left out when there aren't any.
+    let text = rows.iter().any(|(_, (_, counts))| counts.text > 0);
+    let mut header = format!("{:<18}{:>8}{:>12}", "Language", "Files", "Lines");
+    if scan.breakdown {
+        header.push_str(&format!(
+            "{:>12}{:>12}{:>12}{:>12}",
+            "Code", "Comments", "Docs", "Blanks"
+        ));
+        if text {
+            header.push_str(&format!("{:>12}", "Text"));
+        }
+    }
+    header.push_str(&format!("{:>16}", "Bytes"));
+    if scan.percent {
+        header.push_str(&format!("{:>8}", "Share"));
+    }
+    println!("{}", header.bold());
+
+    let whole = rows
+        .iter()
+        .map(|(_, (_, counts))| metric(counts))
+        .sum::<u128>();
+    for (name, (files, counts)) in rows {
+        let mut row = format!("{name:<18}{files:>8}{:>12}", counts.lines);
+        if scan.breakdown {
+            row.push_str(&format!(
+                "{:>12}{:>12}{:>12}{:>12}",
+                counts.code, counts.comments, counts.docs, counts.blanks
+            ));
+            if text {
+                row.push_str(&format!("{:>12}", counts.text));
+            }
+        }
+        row.push_str(&format!("{:>16}", format_byte_count(counts.bytes)));
+        if scan.percent {
+            row.push_str(&format!("{:>8}", format_share(metric(counts), whole)));
+        }
+        println!("{row}");
+    }
+}
+
+//12.5%, of nothing is -.
+fn format_share(part: u128, whole: u128) -> String {
+    match whole {
+        0 => "-".to_string(),
+        whole => format!("{:.1}%", part as f64 / whole as f64 * 100.),
+    }
+}
+
+fn print_owner_report(owners: &HashMap<String, (u128, Counts)>, total: Counts, scan: &Scan) {
+    let mut rows = owners.iter().collect::<Vec<_>>();
+    let metric = |counts: &Counts| scan.metric.of(counts);
+    rows.sort_by(|a, b| metric(&b.1.1).cmp(&metric(&a.1.1)).then(a.0.cmp(b.0)));
+
+    let mut header = format!(
+        "{:<32}{:>8}{:>12}{:>16}",
+        "Owner", "Files", "Lines", "Bytes"
+    );
+    if scan.percent {
+        header.push_str(&format!("{:>8}", "Share"));
+    }
+    println!("{}", header.bold());
+    for (owner, (files, counts)) in rows {
+        let mut row = format!(
+            "{owner:<32}{files:>8}{:>12}{:>16}",
+            counts.lines,
+            format_byte_count(counts.bytes)
+        );
+        //a file with several owners is in each of their rows, the shares are of everything counted.
+        if scan.percent {
+            row.push_str(&format!(
+                "{:>8}",
+                format_share(metric(counts), metric(&total))
+            ));
+        }
+        println!("{row}");
+    }
+}
+
+fn print_line_length_report(longest: &[FileStat], scan: &Scan) {
+    if longest.is_empty() {
+        return;
+    }
+    println!("{}", "Longest lines:".bold());
+    for file_stat in longest {
+        let counts = &file_stat.counts;
+        let average = match counts.lines {
+            0 => 0.,
+            lines => counts.line_chars as f64 / lines as f64,
+        };
+        println!(
+            "  {:>8}  avg {average:>7.1}  {}",
+            counts.longest_line,
+            scan.display_path(&file_stat.path)
+        );
+    }
+}
+
+fn print_long_line_report(limit: u128, files: &mut [FileStat], scan: &Scan) {
+    let (lines, count) = (
+        files
+            .iter()
+            .map(|file_stat| file_stat.counts.long_lines)
+            .sum::<u128>(),
+        files.len(),
+    );
+    println!(
+        "{}",
+        format!("{lines} lines over {limit} characters in {count} files").bold()
+    );
+    files.sort_by(|a, b| {
+        (b.counts.long_lines, b.counts.longest_line)
+            .cmp(&(a.counts.long_lines, a.counts.longest_line))
+            .then(a.path.cmp(&b.path))
+    });
+    for file_stat in files.iter().take(LONG_LINE_FILES) {
+        println!(
+            "  {:>8} lines  longest {:>6}  {}",
+            file_stat.counts.long_lines,
+            file_stat.counts.longest_line,
+            scan.display_path(&file_stat.path)
+        );
+    }
+    if count > LONG_LINE_FILES {
+        println!("  ...and {} more files", count - LONG_LINE_FILES);
+    }
+}
+
+fn print_mixed_indent_report(files: &mut [FileStat], scan: &Scan) {
+    if files.is_empty() {
+        return;
+    }
+    println!("{}", "Indented with both tabs and spaces:".bold());
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    for file_stat in files.iter().take(MIXED_INDENT_FILES) {
+        println!(
+            "  {:>8} tab {:>8} space  {}",
+            file_stat.counts.tab_indented,
+            file_stat.counts.space_indented,
+            scan.display_path(&file_stat.path)
+        );
+    }
+    if files.len() > MIXED_INDENT_FILES {
+        println!("  ...and {} more files", files.len() - MIXED_INDENT_FILES);
+    }
+}
+
+fn print_mixed_ending_report(files: &mut [FileStat], scan: &Scan) {
+    if files.is_empty() {
+        return;
+    }
+    println!("{}", "Mixed line endings:".bold());
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    for file_stat in files.iter().take(MIXED_ENDING_FILES) {
+        let counts = &file_stat.counts;
+        println!(
+            "  {:>8} LF {:>8} CRLF {:>8} CR  {}",
+            counts.lf_endings,
+            counts.crlf_endings,
+            counts.cr_endings,
+            scan.display_path(&file_stat.path)
+        );
+    }
+    if files.len() > MIXED_ENDING_FILES {
+        println!("  ...and {} more files", files.len() - MIXED_ENDING_FILES);
+    }
+}
+
+//the cleanup a linter would ask for, counted on the way rather than in a separate pass.
+fn print_hygiene_report(files: &mut [FileStat], scan: &Scan) {
+    println!("{}", "Hygiene:".bold());
+    println!(
+        "  {} lines with trailing whitespace in {} files",
+        files
+            .iter()
+            .map(|file_stat| file_stat.counts.trailing_whitespace)
+            .sum::<u128>(),
+        files.len()
+    );
+    files.sort_by(|a, b| {
+        b.counts
+            .trailing_whitespace
+            .cmp(&a.counts.trailing_whitespace)
+            .then(a.path.cmp(&b.path))
+    });
+    for file_stat in files.iter().take(TRAILING_WHITESPACE_FILES) {
+        println!(
+            "  {:>8}  {}",
+            file_stat.counts.trailing_whitespace,
+            scan.display_path(&file_stat.path)
+        );
+    }
+    if files.len() > TRAILING_WHITESPACE_FILES {
+        println!(
+            "  ...and {} more files",
+            files.len() - TRAILING_WHITESPACE_FILES
+        );
+    }
+}
+
+fn print_todo_report(files: &mut [FileStat], scan: &Scan) {
+    println!("{}", "TODOs:".bold());
+    println!(
+        "  {} markers in {} files",
+        files
+            .iter()
+            .map(|file_stat| file_stat.counts.todos)
+            .sum::<u128>(),
+        files.len()
+    );
+    files.sort_by(|a, b| {
+        b.counts
+            .todos
+            .cmp(&a.counts.todos)
+            .then(a.path.cmp(&b.path))
+    });
+    for file_stat in files.iter().take(TODO_FILES) {
+        println!(
+            "  {:>8}  {}",
+            file_stat.counts.todos,
+            scan.display_path(&file_stat.path)
+        );
+    }
+    if files.len() > TODO_FILES {
+        println!("  ...and {} more files", files.len() - TODO_FILES);
+    }
+}
+
+fn print_empty_report(files: &mut [FileStat], scan: &Scan) {
+    let whitespace = files
+        .iter()
+        .filter(|file_stat| file_stat.counts.whitespace_files > 0)
+        .count();
+    println!("{}", "Empty files:".bold());
+    println!(
+        "  {} without a line, {} with nothing but whitespace",
+        files.len() - whitespace,
+        whitespace
+    );
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    for file_stat in files.iter().take(EMPTY_FILES) {
+        println!(
+            "  {:<10}  {}",
+            match file_stat.counts.whitespace_files {
+                0 => "empty",
+                _ => "whitespace",
+            },
+            scan.display_path(&file_stat.path)
+        );
+    }
+    if files.len() > EMPTY_FILES {
+        println!("  ...and {} more files", files.len() - EMPTY_FILES);
+    }
+}
+
+fn print_dupes_report(sets: &[identical::CopySet], scan: &Scan) {
+    let (lines, bytes) = sets.iter().fold((0, 0), |(lines, bytes), set| {
+        let (wasted_lines, wasted_bytes) = set.wasted();
+        (lines + wasted_lines, bytes + wasted_bytes)
+    });
+    println!("{}", "Identical files:".bold());
+    println!(
+        "  {} sets, {} redundant copies with {lines} lines, {}",
+        sets.len(),
+        sets.iter().map(|set| set.paths.len() - 1).sum::<usize>(),
+        format_byte_count(bytes)
+    );
+    for set in sets.iter().take(DUPE_SETS) {
+        println!(
+            "  {}x  {}L, {}",
+            set.paths.len(),
+            set.lines,
+            format_byte_count(set.bytes)
+        );
+        for path in &set.paths {
+            println!("      {}", scan.display_path(path));
+        }
+    }
+    if sets.len() > DUPE_SETS {
+        println!("  ...and {} more sets", sets.len() - DUPE_SETS);
+    }
+}
+
+fn print_encoding_report(files: &mut [FileStat], scan: &Scan) {
+    if files.is_empty() {
+        return;
+    }
+    println!("{}", "Not utf-8:".bold());
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    for file_stat in files.iter() {
+        println!(
+            "  {:<10}  {}",
+            file_stat.counts.encoding_name().unwrap_or_default(),
+            scan.display_path(&file_stat.path)
+        );
+    }
+}
+
+fn print_bom_report(files: &mut [FileStat], scan: &Scan) {
+    if files.is_empty() {
+        return;
+    }
+    println!("{}", "Byte order marks:".bold());
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    for file_stat in files.iter() {
+        println!(
+            "  {:<10}  {}",
+            file_stat
+                .counts
+                .encoding_name()
+                .unwrap_or(Encoding::Utf8.name()),
+            scan.display_path(&file_stat.path)
+        );
+    }
+}
+
+fn print_test_report(tests: &TestSplit, scan: &Scan) {
+    println!(
+        "{}",
+        format!(
+            "{:<40}{:>12}{:>12}{:>8}",
+            "Directory",
+            format!("Test {}", scan.metric.name()),
+            format!("Prod {}", scan.metric.name()),
+            "Ratio"
+        )
+        .bold()
+    );
+
+    let mut total = Split::default();
+    let row = |name: &str, split: &Split| {
+        //test lines per line of production code, the way coverage discussions usually put it.
+        let ratio = match split.prod_lines {
+            0 => "-".to_string(),
+            prod => format!("{:.2}", split.test_lines as f64 / prod as f64),
+        };
+        println!(
+            "{name:<40}{:>12}{:>12}{ratio:>8}",
+            split.test_lines, split.prod_lines
+        );
+    };
+    for (dir, split) in tests.dirs() {
+        //the root itself shows up as an empty path.
+        let name = scan.display_path(&dir);
+        row(if name.is_empty() { "." } else { &name }, &split);
+        total.test_files += split.test_files;
+        total.test_lines += split.test_lines;
+        total.prod_files += split.prod_files;
+        total.prod_lines += split.prod_lines;
+    }
+    row("Total", &total);
+    println!(
+        "{} test files, {} production files",
+        total.test_files, total.prod_files
+    );
+}
+
+fn print_noise_report(scan: &Scan) {
+    let mut noise = scan.noise.lock().unwrap();
+    noise.sort();
+
+    let bytes = noise
+        .iter()
+        .filter_map(|path| path.metadata().ok())
+        .map(|metadata| metadata.len() as u128)
+        .sum();
+    println!(
+        "Noise files skipped: {} ({})",
+        noise.len(),
+        format_byte_count(bytes)
+    );
+    for path in noise.iter() {
+        println!("  {}", scan.display_path(path).dimmed());
+    }
+}
+
+fn print_ignored_report(ignored: &[(PathBuf, Skip)], scan: &Scan) {
+    let mut ignored = ignored.to_vec();
+    ignored.sort_by(|a, b| a.0.cmp(&b.0));
+
+    println!("Ignored: {}", ignored.len());
+    for (path, skip) in &ignored {
+        let mut shown = scan.display_path(path);
+        if path.is_dir() {
+            shown.push('/');
+        }
+        println!("  {shown}  {}", skip.to_string().dimmed());
+    }
+}
+
+//`lc why <path>`: walks from the root down to the path the same way a scan would and reports the
+//first rule that stops it, whether that hits the path itself or one of the directories above it.
+fn explain_skip(scan: &Scan, target: &Path) -> Result<()> {
+    let canonical_target = fs::canonicalize(target)?;
+    let root = scan.filter.roots.iter().find_map(|root| {
+        let canonical_root = fs::canonicalize(root).ok()?;
+        canonical_target
+            .strip_prefix(&canonical_root)
+            .ok()
+            .map(|relative| (root.clone(), relative.to_path_buf()))
+    });
+
+    let Some((root, relative)) = root else {
+        println!(
+            "{} is outside the scanned paths",
+            target.display().to_string().bold()
+        );
+        return Ok(());
+    };
+
+    let mut current = root;
+    let components = relative.components().collect::<Vec<_>>();
+    for (idx, component) in components.iter().enumerate() {
+        current.push(component);
+        let skip = if idx == components.len() - 1 && current.is_file() {
+            scan.filter.file_skip(&current)
+        } else {
+            scan.filter.dir_skip(&current)
+        };
+
+        if let Some(skip) = skip {
+            println!(
+                "{} is {}",
+                target.display().to_string().bold(),
+                "not counted".red()
+            );
+            let mut shown = scan.display_path(&current);
+            if current.is_dir() {
+                shown.push('/');
+            }
+            println!("  {shown}: {skip}");
+            return Ok(());
+        }
+    }
+
+    println!(
+        "{} is {}",
+        target.display().to_string().bold(),
+        "counted".green()
+    );
+    Ok(())
+}
+
+//--submodules separate: each submodule counted on its own, with the same filters. a submodule found
+//inside one being counted gets a row of its own too.
+fn print_submodule_report(scan: &Scan) -> Result<()> {
+    println!("{}", "Submodules:".bold());
+    for (dir, files, counts) in submodule_totals(scan)? {
+        println!(
+            "  {:>10}L {:>12}  {:>6} files  {}",
+            counts.lines,
+            format_byte_count(counts.bytes),
+            files,
+            scan.display_path(&dir)
+        );
+    }
+    Ok(())
+}
+
+//each submodule's file count and totals. walking one can come across more submodules, so the list
+//is read an entry at a time and its lock let go of before the walk asks for it again.
+fn submodule_totals(scan: &Scan) -> Result<Vec<(PathBuf, usize, Counts)>> {
+    let mut totals = Vec::new();
+    for at in 0.. {
+        let next = scan.submodule_dirs.lock().unwrap().get(at).cloned();
+        let Some(dir) = next else { break };
+        let mut files = Vec::new();
+        collect_files(&dir, scan, &mut files)?;
+        let mut counts = Counts::default();
+        for file_stat in files
+            .par_iter()
+            .filter_map(|(path, _)| count_file(path, scan).ok())
+            .collect::<Vec<_>>()
+        {
+            counts += file_stat.counts;
+        }
+        totals.push((dir, files.len(), counts));
+    }
+    Ok(totals)
+}
+
+fn print_throttle_limit(scan: &Scan) {
+    if let Some(throttle) = &scan.throttle {
+        println!(
+            "{}",
+            format!(
+                "adaptive: ended up reading {} of {} files at once",
+                throttle.limit(),
+                rayon::current_num_threads()
+            )
+            .dimmed()
+        );
+    }
+}
+
+fn format_and_print_results(counts: Counts, time: Duration, scan: &Scan) {
+    let f_bytes = format_byte_count(counts.bytes);
+    println!("╭───────────────────────────────────────────────────╮");
+    println!(
+        "│{:<51}│\n│{:<51}│",
+        format!("Lines       :{}", counts.lines),
+        format!("Bytes       :{f_bytes}"),
+    );
+    if scan.words {
+        println!("│{:<51}│", format!("Words       :{}", counts.words));
+    }
+    if scan.chars {
+        println!(
+            "│{:<51}│\n│{:<51}│",
+            format!("Characters  :{}", counts.chars),
+            format!("Graphemes   :{}", counts.graphemes),
+        );
+    }
+    if scan.tokens {
+        println!("│{:<51}│", format!("Tokens      :~{}", counts.tokens));
+    }
+    if scan.todos.is_some() {
+        println!("│{:<51}│", format!("TODOs       :{}", counts.todos));
+    }
+    if scan.statements {
+        println!("│{:<51}│", format!("Statements  :{}", counts.statements));
+    }
+    if scan.logical {
+        println!("│{:<51}│", format!("Logical     :{}", counts.logical));
+    }
+    if scan.breakdown {
+        println!(
+            "│{:<51}│\n│{:<51}│",
+            format!("Code        :{}", counts.code),
+            format!("Comments    :{}", counts.comments),
+        );
+        //only rust has doc comments told apart so far, elsewhere this is always 0.
+        if counts.docs > 0 {
+            println!("│{:<51}│", format!("Docs        :{}", counts.docs));
+        }
+        println!("│{:<51}│", format!("Blanks      :{}", counts.blanks));
+        if counts.text > 0 {
+            println!("│{:<51}│", format!("Text        :{}", counts.text));
+        }
+    }
+    if scan.line_lengths.is_some() {
+        let average = match counts.lines {
+            0 => 0.,
+            lines => counts.line_chars as f64 / lines as f64,
+        };
+        println!(
+            "│{:<51}│\n│{:<51}│",
+            format!("Longest line:{}", counts.longest_line),
+            format!("Avg line    :{average:.1}"),
+        );
+    }
+    if scan.indentation.is_some() {
+        println!(
+            "│{:<51}│\n│{:<51}│",
+            format!(
+                "Indentation :{} tab, {} space lines",
+                counts.tab_indented, counts.space_indented
+            ),
+            format!("Mixed indent:{} files", counts.mixed_indent_files),
+        );
+    }
+    if scan.line_endings.is_some() {
+        println!(
+            "│{:<51}│\n│{:<51}│",
+            format!(
+                "Line endings:{} LF, {} CRLF, {} CR",
+                counts.lf_endings, counts.crlf_endings, counts.cr_endings
+            ),
+            format!("Mixed ends  :{} files", counts.mixed_ending_files),
+        );
+    }
+    //only --verbose reads every file through, otherwise just utf-16 is caught by its first chunk.
+    if scan.verbose.is_some()
+        && counts.utf16_files + counts.latin1_files + counts.shift_jis_files > 0
+    {
+        println!(
+            "│{:<51}│",
+            format!(
+                "Decoded     :{} utf-16, {} latin-1, {} shift-jis",
+                counts.utf16_files, counts.latin1_files, counts.shift_jis_files
+            )
+        );
+    }
+    if counts.binary_files > 0 {
+        println!(
+            "│{:<51}│",
+            format!(
+                "Binary      :{} files, {}",
+                counts.binary_files,
+                format_byte_count(counts.binary_bytes)
+            )
+        );
+    }
+    if counts.lfs_pointers > 0 {
+        println!(
+            "│{:<51}│",
+            format!(
+                "LFS         :{} pointers to {}",
+                counts.lfs_pointers,
+                format_byte_count(counts.lfs_bytes)
+            )
+        );
+    }
+    if scan.dedupe {
+        println!(
+            "│{:<51}│",
+            format!("Duplicates  :{} files left out", counts.duplicate_files)
+        );
+    }
+    if counts.lockfile_lines > 0 || counts.lockfile_bytes > 0 {
+        println!(
+            "│{:<51}│",
+            format!(
+                "Lockfiles   :{}L, {}",
+                counts.lockfile_lines,
+                format_byte_count(counts.lockfile_bytes)
+            )
+        );
+    }
+    println!(
+        "│{:<51}│",
+        format!("Time Taken  :{:.5} Seconds", time.as_secs_f64())
+    );
+    println!("╰───────────────────────────────────────────────────╯");
+    if let Some(cocomo) = &scan.cocomo {
+        print_cocomo_report(cocomo, counts.code);
+    }
+}
+
+//sloc is code lines only, comments and blanks aren't what the model was fit on.
+fn print_cocomo_report(cocomo: &Cocomo, sloc: u128) {
+    let estimate = cocomo.estimate(sloc);
+    println!("{}", "Estimated (COCOMO):".bold());
+    println!("  Effort      {:.2} person-months", estimate.effort);
+    println!("  Schedule    {:.2} months", estimate.months);
+    println!("  People      {:.2}", estimate.people);
+    println!("  Cost        ${:.0}", estimate.cost);
+}
+
+fn main() -> std::io::Result<()> {
+    let calls = Command::new("lc")
+        .version("1.2")
+        .author("Ethan Water")
+        .about("Line Counting Program")
+        .args([
+            Arg::new("paths")
+                .action(ArgAction::Append)
+                .value_name("PATH")
+                .help("Paths to count, defaults to the current directory"),
+            Arg::new("path")
+                .short('p')
+                .long("path")
+                .action(ArgAction::Append)
+                .value_name("PATH")
+                .help("Provides a path to lc (repeatable)"),
+            Arg::new("relative-to")
+                .long("relative-to")
+                .action(ArgAction::Set)
+                .value_name("PATH")
+                .help("Reports paths relative to PATH instead of the root they were found under"),
+            Arg::new("follow-symlinks")
+                .short('L')
+                .long("follow-symlinks")
+                .action(ArgAction::SetTrue)
+                .help("Follows symbolic links instead of skipping them, entering each directory once"),
+            Arg::new("one-file-system")
+                .short('x')
+                .long("one-file-system")
+                .action(ArgAction::SetTrue)
+                .help("Stays on the filesystem of each path, skipping anything mounted beneath it"),
+            Arg::new("submodules")
+                .long("submodules")
+                .value_name("MODE")
+                .value_parser(Submodules::from_name)
+                .help("What to do with git submodules: include them in the totals (the default), skip them, or count them separately"),
+            Arg::new("count-links")
+                .long("count-links")
+                .action(ArgAction::SetTrue)
+                .help("Counts every hard link to a file instead of just the first one found"),
+            Arg::new("allow-overlap")
+                .long("allow-overlap")
+                .action(ArgAction::SetTrue)
+                .help("Counts paths even when one contains another, counting the shared files twice"),
+            Arg::new("display")
+                .short('d')
+                .long("display")
+                .action(ArgAction::SetTrue)
+                .help("Displays the filetree search"),
+            Arg::new("stream")
+                .long("stream")
+                .action(ArgAction::SetTrue)
+                .requires("display")
+                .help("Prints the tree as it's counted instead of once it's done, in the same order"),
+            Arg::new("all")
+                .short('a')
+                .long("all")
+                .action(ArgAction::SetTrue)
+                .help("Includes hidden files and directories"),
+            Arg::new("include-vcs")
+                .long("include-vcs")
+                .action(ArgAction::SetTrue)
+                .help("Descends into .git, .hg and .svn directories"),
+            Arg::new("include-noise")
+                .long("include-noise")
+                .action(ArgAction::SetTrue)
+                .help("Counts editor backup and swap files (*~, *.swp, *.orig, *.rej, #*#)"),
+            Arg::new("include-generated")
+                .long("include-generated")
+                .action(ArgAction::SetTrue)
+                .help("Counts files .gitattributes marks linguist-generated or linguist-vendored, which GitHub leaves out of its stats"),
+            Arg::new("max-depth")
+                .long("max-depth")
+                .action(ArgAction::Set)
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Descends at most N directories below the root, 1 only counts the root's own files"),
+            Arg::new("min-depth")
+                .long("min-depth")
+                .action(ArgAction::Set)
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Only counts files at least N levels below the root"),
+            Arg::new("newer-than")
+                .long("newer-than")
+                .action(ArgAction::Set)
+                .value_name("AGE|DATE")
+                .value_parser(dates::parse_time_spec)
+                .help("Only counts files modified since AGE ago (30d, 12h, 2w, 6m, 1y) or DATE (2024-03-01)"),
+            Arg::new("older-than")
+                .long("older-than")
+                .action(ArgAction::Set)
+                .value_name("AGE|DATE")
+                .value_parser(dates::parse_time_spec)
+                .help("Only counts files last modified before AGE ago or DATE"),
+            Arg::new("preset")
+                .long("preset")
+                .action(ArgAction::Append)
+                .value_name("PRESET")
+                .value_parser(["rust", "node", "python", "auto"])
+                .help("Skips the build artifact directories of an ecosystem, auto detects it from the root (repeatable)"),
+            Arg::new("show-ignored")
+                .long("show-ignored")
+                .action(ArgAction::SetTrue)
+                .help("Lists every file and directory that was skipped, and why"),
+            Arg::new("noise")
+                .long("noise")
+                .action(ArgAction::SetTrue)
+                .help("Lists the editor backup and swap files that were skipped"),
+            Arg::new("sparkline")
+                .long("sparkline")
+                .action(ArgAction::SetTrue)
+                .help("Shows the spread of file sizes next to each directory (with --display)"),
+            Arg::new("statements")
+                .long("statements")
+                .action(ArgAction::SetTrue)
+                .help("EXPERIMENTAL: also counts semicolon-terminated statements in C-family sources"),
+            Arg::new("logical")
+                .long("logical")
+                .action(ArgAction::SetTrue)
+                .help("Also counts logical lines (statements and blocks) in C-family and Rust sources"),
+            Arg::new("breakdown")
+                .long("breakdown")
+                .action(ArgAction::SetTrue)
+                .help("Splits lines into code, comment and blank lines for known languages"),
+            Arg::new("metric")
+                .long("metric")
+                .value_name("METRIC")
+                .value_parser(Metric::from_name)
+                .help("What --languages, --tests and --sparkline go by: lines (the default), sloc (code lines, implies --breakdown) or bytes"),
+            Arg::new("percent")
+                .long("percent")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("stream")
+                .help("Adds each file's and directory's share of the total to the -d tree, and a share column to the --languages, --owners and --workspace tables"),
+            Arg::new("languages")
+                .long("languages")
+                .action(ArgAction::SetTrue)
+                .help("Prints files, lines and bytes per language, biggest first"),
+            Arg::new("line-lengths")
+                .long("line-lengths")
+                .action(ArgAction::SetTrue)
+                .help("Reports the longest and average line length, and the files with the longest lines"),
+            Arg::new("long-lines")
+                .long("long-lines")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u128))
+                .help("Lists the files with lines longer than N characters, most long lines first"),
+            Arg::new("indentation")
+                .long("indentation")
+                .action(ArgAction::SetTrue)
+                .help("Counts tab and space indented lines and lists the files that mix both"),
+            Arg::new("line-endings")
+                .long("line-endings")
+                .action(ArgAction::SetTrue)
+                .help("Counts LF, CRLF and lone CR line endings and lists the files that mix them"),
+            Arg::new("words")
+                .long("words")
+                .action(ArgAction::SetTrue)
+                .help("Also counts words (whitespace-separated, like wc -w) per file and in total"),
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(ArgAction::SetTrue)
+                .help("Shows each file's detected encoding with --display, and lists the files that weren't utf-8"),
+            Arg::new("show-bom")
+                .long("show-bom")
+                .action(ArgAction::SetTrue)
+                .help("Lists the files that start with a byte order mark"),
+            Arg::new("chars")
+                .long("chars")
+                .action(ArgAction::SetTrue)
+                .help("Also counts unicode characters and grapheme clusters per file and in total"),
+            Arg::new("tokens")
+                .long("tokens")
+                .action(ArgAction::SetTrue)
+                .help("Also estimates how many tokens an LLM tokenizer would make of each file and the total"),
+            Arg::new("todos")
+                .long("todos")
+                .action(ArgAction::SetTrue)
+                .help("Counts TODO, FIXME, HACK and XXX markers and lists the files with the most"),
+            Arg::new("todo-marker")
+                .long("todo-marker")
+                .action(ArgAction::Append)
+                .value_name("WORD")
+                .help("Counts WORD as a marker instead of the defaults (repeatable, implies --todos)"),
+            Arg::new("empty")
+                .long("empty")
+                .action(ArgAction::SetTrue)
+                .help("Lists the text files that are empty or nothing but whitespace"),
+            Arg::new("dupes")
+                .long("dupes")
+                .action(ArgAction::SetTrue)
+                .help("Lists sets of files with identical content and the lines and bytes the copies waste"),
+            Arg::new("dedupe")
+                .long("dedupe")
+                .action(ArgAction::SetTrue)
+                .help("Counts files with identical content only once in the totals (implies --dupes)"),
+            Arg::new("hygiene")
+                .long("hygiene")
+                .action(ArgAction::SetTrue)
+                .help("Counts lines with trailing whitespace and lists the files with the most"),
+            Arg::new("no-blank")
+                .long("no-blank")
+                .action(ArgAction::SetTrue)
+                .help("Leaves lines with nothing but whitespace on them out of the line counts"),
+            Arg::new("line-breaks")
+                .long("line-breaks")
+                .value_name("MODE")
+                .value_parser(["lf", "any"])
+                .default_value("lf")
+                .help("What ends a line: lf (a \\n, so CRLF is one line end and a lone CR is not one), or any (a lone CR too)"),
+            Arg::new("cocomo")
+                .long("cocomo")
+                .action(ArgAction::SetTrue)
+                .help("Estimates development effort, schedule and cost from the code lines with basic COCOMO (implies --breakdown)"),
+            Arg::new("cocomo-model")
+                .long("cocomo-model")
+                .value_name("MODEL")
+                .value_parser(Model::parse)
+                .help("The kind of project for --cocomo: organic (the default), semi-detached or embedded (implies --cocomo)"),
+            Arg::new("avg-wage")
+                .long("avg-wage")
+                .value_name("AMOUNT")
+                .value_parser(clap::value_parser!(f64))
+                .help("A developer's yearly salary for the --cocomo cost, 56286 by default (implies --cocomo)"),
+            Arg::new("overhead")
+                .long("overhead")
+                .value_name("FACTOR")
+                .value_parser(clap::value_parser!(f64))
+                .help("What a developer costs as a multiple of their salary for the --cocomo cost, 2.4 by default (implies --cocomo)"),
+            Arg::new("hotspots")
+                .long("hotspots")
+                .action(ArgAction::Set)
+                .value_name("N")
+                .num_args(0..=1)
+                .default_missing_value("10")
+                .value_parser(clap::value_parser!(usize))
+                .help("Lists the N (default 10) files with the most lines times commits in git history"),
+            Arg::new("hotspot-since")
+                .long("hotspot-since")
+                .action(ArgAction::Set)
+                .value_name("AGE|DATE")
+                .value_parser(dates::parse_time_spec)
+                .default_value("1y")
+                .requires("hotspots")
+                .help("How far back --hotspots counts commits, an AGE (30d, 6m, 1y) or a DATE"),
+            Arg::new("workspace")
+                .long("workspace")
+                .action(ArgAction::SetTrue)
+                .help("Breaks the totals down by Cargo, npm, yarn or pnpm workspace member, and each member into src, tests, benches and examples"),
+            Arg::new("owners")
+                .long("owners")
+                .action(ArgAction::SetTrue)
+                .help("Breaks the totals down by owner, going by the CODEOWNERS file"),
+            Arg::new("tests")
+                .long("tests")
+                .action(ArgAction::SetTrue)
+                .help("Prints test and production lines per top-level directory, and their ratio"),
+            Arg::new("test-pattern")
+                .long("test-pattern")
+                .action(ArgAction::Append)
+                .value_name("GLOB")
+                .value_parser(|s: &str| glob::Pattern::new(s))
+                .help("Counts paths (relative to the root) matching GLOB as tests instead of the defaults like **/tests/** and **/*_test.go (repeatable, implies --tests)"),
+            Arg::new("dup-lines")
+                .long("dup-lines")
+                .action(ArgAction::SetTrue)
+                .help("Reports duplicated lines per language and the most repeated blocks"),
+            Arg::new("dup-ignore-whitespace")
+                .long("dup-ignore-whitespace")
+                .action(ArgAction::SetTrue)
+                .help("Ignores all whitespace when comparing lines for --dup-lines"),
+            Arg::new("type")
+                .long("type")
+                .action(ArgAction::Append)
+                .value_name("TYPES")
+                .value_delimiter(',')
+                .value_parser(ContentType::from_name)
+                .help("Only counts files of these content types: code, media, executable, normal, text, license, makefile, lockfile"),
+            Arg::new("exclude-type")
+                .long("exclude-type")
+                .action(ArgAction::Append)
+                .value_name("TYPES")
+                .value_delimiter(',')
+                .value_parser(ContentType::from_name)
+                .help("Skips files of these content types"),
+            Arg::new("code-only")
+                .long("code-only")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("type")
+                .help("Only counts files classified as code, so plain text, media and executables stay out of the totals (the same as --type code)"),
+            Arg::new("no-lockfiles")
+                .long("no-lockfiles")
+                .action(ArgAction::SetTrue)
+                .help("Skips lockfiles (Cargo.lock, package-lock.json, yarn.lock, poetry.lock, ...)"),
+            Arg::new("ext")
+                .long("ext")
+                .action(ArgAction::Append)
+                .value_name("EXTENSIONS")
+                .value_delimiter(',')
+                .help("Only counts files with these extensions, e.g. rs,toml,md"),
+            Arg::new("include")
+                .long("include")
+                .action(ArgAction::Append)
+                .value_name("GLOB")
+                .value_parser(|s: &str| glob::Pattern::new(s))
+                .help("Only counts files whose name matches GLOB (repeatable)"),
+            Arg::new("include-re")
+                .long("include-re")
+                .action(ArgAction::Append)
+                .value_name("REGEX")
+                .value_parser(|s: &str| Regex::new(s))
+                .help("Only counts files whose path relative to the root matches REGEX (repeatable)"),
+            Arg::new("exclude-re")
+                .long("exclude-re")
+                .action(ArgAction::Append)
+                .value_name("REGEX")
+                .value_parser(|s: &str| Regex::new(s))
+                .help("Skips files and directories whose path relative to the root matches REGEX (repeatable)"),
+            Arg::new("prioritize")
+                .long("prioritize")
+                .action(ArgAction::Append)
+                .value_name("GLOB")
+                .value_parser(|s: &str| glob::Pattern::new(s))
+                .help("Scans and lists paths matching GLOB (relative to the root) before the rest (repeatable)"),
+            Arg::new("verify-parallel")
+                .long("verify-parallel")
+                .action(ArgAction::Set)
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Recounts a random sample of N files serially and checks them against the parallel scan"),
+            Arg::new("cache")
+                .long("cache")
+                .action(ArgAction::Set)
+                .value_name("FILE")
+                .num_args(0..=1)
+                .default_missing_value(".lc-cache")
+                .help("Remembers per-file counts in FILE (default .lc-cache) and only recounts files that changed"),
+            Arg::new("runtime")
+                .long("runtime")
+                .action(ArgAction::Set)
+                .value_name("RUNTIME")
+                .value_parser(["threads", "tokio"])
+                .default_value("threads")
+                .help("What drives the parallel count: threads, or tokio for bounded async tasks (needs the tokio feature)"),
+            Arg::new("io-backend")
+                .long("io-backend")
+                .action(ArgAction::Set)
+                .value_name("BACKEND")
+                .value_parser(["std", "uring"])
+                .default_value("std")
+                .help("How files are read: std, or uring for batched io_uring reads (linux, needs the uring feature)"),
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .action(ArgAction::Set)
+                .value_name("N")
+                .value_parser(clap::value_parser!(NonZeroUsize))
+                .help("Uses at most N worker threads, defaults to the number of CPUs"),
+            Arg::new("adaptive")
+                .long("adaptive")
+                .action(ArgAction::SetTrue)
+                .help("Tunes how many files are read at once while counting, up to -j (default 4 per CPU)"),
+            Arg::new("max-mem")
+                .long("max-mem")
+                .action(ArgAction::Set)
+                .value_name("SIZE")
+                .value_parser(budget::parse_size)
+                .help("Keeps the buffers of files being read under SIZE (e.g. 512M), workers wait for room instead"),
+            Arg::new("estimate")
+                .long("estimate")
+                .action(ArgAction::Set)
+                .value_name("FRACTION")
+                .value_parser(estimate::parse_fraction)
+                .conflicts_with_all(["display", "verify-parallel"])
+                .help("Counts a random FRACTION of the files (e.g. 0.05 or 5%) and extrapolates the totals"),
+            Arg::new("rev")
+                .long("rev")
+                .action(ArgAction::Set)
+                .value_name("REV")
+                .conflicts_with_all(["paths", "path", "display", "estimate", "verify-parallel"])
+                .help("Counts the repository as of REV (a tag, branch or commit) without checking it out"),
+            Arg::new("staged")
+                .long("staged")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["rev", "paths", "path", "display", "estimate", "verify-parallel"])
+                .help("Counts what is staged in the git index, the files as the next commit would have them"),
+            Arg::new("record")
+                .long("record")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["estimate", "rev", "staged"])
+                .help("Saves the totals of this run, per top-level directory, to the history `lc history` shows (a SQLite database at ~/.local/share/lc/history.db)"),
+            Arg::new("export")
+                .long("export")
+                .action(ArgAction::Set)
+                .value_name("FILE")
+                .conflicts_with("estimate")
+                .help("Writes the totals and every file's counts to FILE as a json report, for lc compare"),
+            Arg::new("timings")
+                .long("timings")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("estimate")
+                .help("Reports how long was spent walking, reading, counting and rendering"),
+        ])
+        .subcommand(
+            Command::new("why")
+                .about("Explains which rule keeps a path out of the count")
+                .arg(
+                    Arg::new("target")
+                        .required(true)
+                        .value_name("PATH")
+                        .help("File or directory to explain"),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Counts two trees and prints the lines and bytes added and removed per directory and language")
+                .args([
+                    Arg::new("trees")
+                        .required(true)
+                        .num_args(2)
+                        .value_names(["OLD", "NEW"])
+                        .help("The directories to compare, say a checkout of main and one of a branch"),
+                    Arg::new("git")
+                        .long("git")
+                        .action(ArgAction::SetTrue)
+                        .help("Takes OLD and NEW as git revisions (e.g. HEAD~10 HEAD) and reads them from the repository"),
+                ]),
+        )
+        .subcommand(
+            Command::new("age")
+                .about("Splits the lines there are now by how long ago git blame says they last changed, per directory")
+                .arg(
+                    Arg::new("target")
+                        .value_name("PATH")
+                        .help("Directory to date, defaults to the current directory"),
+                ),
+        )
+        .subcommand(
+            Command::new("authors")
+                .about("Attributes the lines there are now to their authors with git blame, overall and per directory")
+                .arg(
+                    Arg::new("target")
+                        .value_name("PATH")
+                        .help("Directory to attribute, defaults to the current directory"),
+                ),
+        )
+        .subcommand(
+            Command::new("churn")
+                .about("Prints the lines added and removed per file over a stretch of git history, next to their size now")
+                .args([
+                    Arg::new("target")
+                        .value_name("PATH")
+                        .help("Directory to look at, defaults to the current directory"),
+                    Arg::new("since")
+                        .long("since")
+                        .action(ArgAction::Set)
+                        .value_name("AGE|DATE")
+                        .value_parser(dates::parse_time_spec)
+                        .default_value("6m")
+                        .help("How far back to go, an AGE (30d, 2w, 6m, 1y) or a DATE (2024-03-01)"),
+                ]),
+        )
+        .subcommand(
+            Command::new("hook")
+                .about("Runs as a git hook")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("pre-commit")
+                        .about("Prints the net line change of the staged files against HEAD")
+                        .arg(
+                            Arg::new("max-lines")
+                                .long("max-lines")
+                                .action(ArgAction::Set)
+                                .value_name("N")
+                                .value_parser(clap::value_parser!(u128))
+                                .help("Fails the commit when it adds more than N lines net"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("install-hook")
+                .about("Writes a git hook that runs lc: pre-commit reports the staged line change, pre-push checks lc-budgets.toml")
+                .args([
+                    Arg::new("hook")
+                        .required(true)
+                        .value_name("HOOK")
+                        .value_parser(hook::HOOKS)
+                        .help("Which hook to install"),
+                    Arg::new("max-lines")
+                        .long("max-lines")
+                        .action(ArgAction::Set)
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u128))
+                        .help("For pre-commit, fails commits that add more than N lines net"),
+                    Arg::new("force")
+                        .long("force")
+                        .action(ArgAction::SetTrue)
+                        .help("Replaces a hook that lc didn't write"),
+                ]),
+        )
+        .subcommand(
+            Command::new("pr-summary")
+                .about("Prints a markdown summary of the line changes since HEAD branched off a base, for posting on a pull request")
+                .arg(
+                    Arg::new("base")
+                        .long("base")
+                        .action(ArgAction::Set)
+                        .value_name("REV")
+                        .default_value("origin/main")
+                        .help("The branch the pull request goes into"),
+                ),
+        )
+        .subcommand(
+            Command::new("snapshot")
+                .about("Saves the counts of every file under a name, and compares saved snapshots")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("save")
+                        .about("Counts PATH and saves the per-file counts as NAME")
+                        .args([
+                            Arg::new("name")
+                                .required(true)
+                                .value_name("NAME")
+                                .help("What to save the snapshot as"),
+                            Arg::new("target")
+                                .value_name("PATH")
+                                .help("Directory to count, defaults to the current directory"),
+                        ]),
+                )
+                .subcommand(
+                    Command::new("diff")
+                        .about("Prints the lines and bytes added and removed between two snapshots")
+                        .arg(
+                            Arg::new("names")
+                                .required(true)
+                                .num_args(2)
+                                .value_names(["OLD", "NEW"])
+                                .help("The snapshots to compare"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("branches")
+                .about("Counts each ref from git and prints their totals and languages side by side")
+                .arg(
+                    Arg::new("refs")
+                        .required(true)
+                        .num_args(1..)
+                        .value_name("REF")
+                        .help("Branches, tags or commits to compare, the first is listed first"),
+                ),
+        )
+        .subcommand(
+            Command::new("compare")
+                .about("Compares two json reports written with --export, listing the files that were added, removed or changed")
+                .arg(
+                    Arg::new("files")
+                        .required(true)
+                        .num_args(2)
+                        .value_names(["OLD", "NEW"])
+                        .help("Reports written with lc --export FILE, or snapshot files as lc snapshot save writes them to .lc-snapshots/"),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Lists the runs recorded with --record over the same paths")
+                .args([
+                    Arg::new("targets")
+                        .num_args(0..)
+                        .value_name("PATH")
+                        .help("The paths the runs counted, defaults to the current directory"),
+                    Arg::new("dir")
+                        .long("dir")
+                        .action(ArgAction::Set)
+                        .value_name("DIR")
+                        .help("Follows one top-level directory instead of the totals"),
+                    Arg::new("graph")
+                        .long("graph")
+                        .action(ArgAction::SetTrue)
+                        .help("Draws the lines over time as a chart instead of listing the runs"),
+                ]),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Checks the directories in lc-budgets.toml against their line and growth budgets")
+                .arg(
+                    Arg::new("base")
+                        .long("base")
+                        .action(ArgAction::Set)
+                        .value_name("REV")
+                        .default_value("HEAD")
+                        .help("The revision max_growth is measured from"),
+                ),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Times the serial, threaded and display modes over PATH and compares them")
+                .args([
+                    Arg::new("target")
+                        .required(true)
+                        .value_name("PATH")
+                        .help("Directory to benchmark"),
+                    Arg::new("iterations")
+                        .short('n')
+                        .long("iterations")
+                        .action(ArgAction::Set)
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10")
+                        .help("How many times each mode runs"),
+                ]),
+        )
+        .get_matches();
+
+    let mut roots = calls
+        .get_many::<String>("paths")
+        .unwrap_or_default()
+        .chain(calls.get_many::<String>("path").unwrap_or_default())
+        .chain(
+            calls
+                .subcommand_matches("bench")
+                .and_then(|bench| bench.get_one::<String>("target")),
+        )
+        .chain(
+            calls
+                .subcommand_matches("authors")
+                .and_then(|authors| authors.get_one::<String>("target")),
+        )
+        .chain(
+            calls
+                .subcommand_matches("age")
+                .and_then(|age| age.get_one::<String>("target")),
+        )
+        .chain(
+            calls
+                .subcommand_matches("churn")
+                .and_then(|churn| churn.get_one::<String>("target")),
+        )
+        .chain(
+            calls
+                .subcommand_matches("history")
+                .and_then(|history| history.get_many::<String>("targets"))
+                .unwrap_or_default(),
+        )
+        .chain(
+            calls
+                .subcommand_matches("snapshot")
+                .and_then(|snapshot| snapshot.subcommand_matches("save"))
+                .and_then(|save| save.get_one::<String>("target")),
+        )
+        .chain(
+            calls
+                .subcommand_matches("diff")
+                .filter(|diff| !*diff.get_one::<bool>("git").unwrap_or(&false))
+                .and_then(|diff| diff.get_many::<String>("trees"))
+                .unwrap_or_default(),
+        )
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+    if roots.is_empty() {
+        roots.push(env::current_dir()?);
+    }
+    if !*calls.get_one::<bool>("allow-overlap").unwrap_or(&false)
+        && let Some((inner, outer)) = find_overlapping_roots(&roots)?
+    {
+        eprintln!(
+            "{} '{}' overlaps with '{}', so its files would be counted twice (use --allow-overlap to count them anyway)",
+            "error:".red().bold(),
+            inner.display(),
+            outer.display()
+        );
+        std::process::exit(2);
+    }
+    let user_languages = user_languages::load().unwrap_or_else(|err| {
+        eprintln!("{} {err}", "error:".red().bold());
+        std::process::exit(2);
+    });
+
+    let verify_sample = calls.get_one::<usize>("verify-parallel").copied();
+    let mut preset_dirs = calls
+        .get_many::<String>("preset")
+        .unwrap_or_default()
+        .flat_map(|preset| {
+            roots
+                .iter()
+                .flat_map(|root| filter::preset_dirs(preset, root))
+        })
+        .collect::<Vec<_>>();
+    preset_dirs.sort();
+    preset_dirs.dedup();
+
+    let filter = Filter {
+        roots: roots.clone(),
+        all: *calls.get_one::<bool>("all").unwrap_or(&false),
+        include_vcs: *calls.get_one::<bool>("include-vcs").unwrap_or(&false),
+        include_noise: *calls.get_one::<bool>("include-noise").unwrap_or(&false),
+        include_generated: *calls.get_one::<bool>("include-generated").unwrap_or(&false),
+        attributes: roots.iter().map(|root| Attributes::load(root)).collect(),
+        preset_dirs,
+        min_depth: calls.get_one::<usize>("min-depth").copied(),
+        max_depth: calls.get_one::<usize>("max-depth").copied(),
+        newer_than: calls.get_one::<SystemTime>("newer-than").copied(),
+        older_than: calls.get_one::<SystemTime>("older-than").copied(),
+        types: calls
+            .get_many::<ContentType>("type")
+            .unwrap_or_default()
+            .copied()
+            .chain(
+                calls
+                    .get_one::<bool>("code-only")
+                    .unwrap_or(&false)
+                    .then_some(ContentType::CODE),
+            )
+            .collect(),
+        exclude_types: calls
+            .get_many::<ContentType>("exclude-type")
+            .unwrap_or_default()
+            .copied()
+            .chain(
+                calls
+                    .get_one::<bool>("no-lockfiles")
+                    .unwrap_or(&false)
+                    .then_some(ContentType::LOCKFILE),
+            )
+            .collect(),
+        extensions: calls
+            .get_many::<String>("ext")
+            .unwrap_or_default()
+            .map(|ext| ext.trim_start_matches('.').to_string())
+            .collect(),
+        include: calls
+            .get_many::<glob::Pattern>("include")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        include_re: calls
+            .get_many::<Regex>("include-re")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        exclude_re: calls
+            .get_many::<Regex>("exclude-re")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        prioritize: calls
+            .get_many::<glob::Pattern>("prioritize")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+    };
+    let adaptive = *calls.get_one::<bool>("adaptive").unwrap_or(&false);
+    let jobs = calls
+        .get_one::<NonZeroUsize>("jobs")
+        .map(|jobs| jobs.get())
+        .or(adaptive.then(adaptive::default_pool_size));
+    let cocomo = (*calls.get_one::<bool>("cocomo").unwrap_or(&false)
+        || ["cocomo-model", "avg-wage", "overhead"]
+            .iter()
+            .any(|name| calls.contains_id(name)))
+    .then(|| Cocomo {
+        model: calls
+            .get_one::<Model>("cocomo-model")
+            .copied()
+            .unwrap_or(Model::Organic),
+        wage: calls.get_one::<f64>("avg-wage").copied().unwrap_or(56286.),
+        overhead: calls.get_one::<f64>("overhead").copied().unwrap_or(2.4),
+    });
+    let owners = calls
+        .get_one::<bool>("owners")
+        .unwrap_or(&false)
+        .then(|| match Codeowners::load(&roots[0]) {
+            Some((codeowners, _)) => codeowners,
+            None => {
+                eprintln!(
+                    "{} no CODEOWNERS file in '{}' (looked in .github/, the root, docs/ and .gitlab/)",
+                    "error:".red().bold(),
+                    roots[0].display()
+                );
+                std::process::exit(2);
+            }
+        });
+    let workspace = calls
+        .get_one::<bool>("workspace")
+        .unwrap_or(&false)
+        .then(|| match Workspace::detect(&roots[0]) {
+            Some(workspace) => workspace,
+            None => {
+                eprintln!(
+                    "{} no workspace in '{}', --workspace needs a Cargo.toml, package.json or pnpm-workspace.yaml there",
+                    "error:".red().bold(),
+                    roots[0].display()
+                );
+                std::process::exit(2);
+            }
+        });
+    let dedupe = *calls.get_one::<bool>("dedupe").unwrap_or(&false);
+    let metric = calls
+        .get_one::<Metric>("metric")
+        .copied()
+        .unwrap_or_default();
+    let breakdown = *calls.get_one::<bool>("breakdown").unwrap_or(&false)
+        || cocomo.is_some()
+        || metric == Metric::Sloc;
+    let scan = Arc::new(Scan {
+        filter,
+        sparkline: *calls.get_one::<bool>("sparkline").unwrap_or(&false),
+        metric,
+        statements: *calls.get_one::<bool>("statements").unwrap_or(&false),
+        logical: *calls.get_one::<bool>("logical").unwrap_or(&false),
+        breakdown,
+        percent: *calls.get_one::<bool>("percent").unwrap_or(&false),
+        languages: calls
+            .get_one::<bool>("languages")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(HashMap::new())),
+        tests: (*calls.get_one::<bool>("tests").unwrap_or(&false)
+            || calls.contains_id("test-pattern"))
+        .then(|| {
+            TestSplit::new(
+                calls
+                    .get_many::<glob::Pattern>("test-pattern")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+            )
+        }),
+        owners,
+        workspace,
+        history: calls
+            .get_one::<bool>("record")
+            .unwrap_or(&false)
+            .then(History::default),
+        export: calls
+            .get_one::<String>("export")
+            .map(|path| Export::new(PathBuf::from(path))),
+        line_lengths: calls
+            .get_one::<bool>("line-lengths")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(Vec::new())),
+        long_line_limit: calls.get_one::<u128>("long-lines").copied(),
+        long_line_files: Mutex::new(Vec::new()),
+        indentation: calls
+            .get_one::<bool>("indentation")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(Vec::new())),
+        line_endings: calls
+            .get_one::<bool>("line-endings")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(Vec::new())),
+        hygiene: calls
+            .get_one::<bool>("hygiene")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(Vec::new())),
+        todos: (*calls.get_one::<bool>("todos").unwrap_or(&false)
+            || calls.contains_id("todo-marker"))
+        .then(|| {
+            Todos::new(
+                calls
+                    .get_many::<String>("todo-marker")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+            )
+        }),
+        todo_files: Mutex::new(Vec::new()),
+        cocomo,
+        empty: calls
+            .get_one::<bool>("empty")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(Vec::new())),
+        dupes: (*calls.get_one::<bool>("dupes").unwrap_or(&false) || dedupe)
+            .then(IdenticalFiles::default),
+        dedupe,
+        words: *calls.get_one::<bool>("words").unwrap_or(&false),
+        chars: *calls.get_one::<bool>("chars").unwrap_or(&false),
+        tokens: *calls.get_one::<bool>("tokens").unwrap_or(&false),
+        verbose: calls
+            .get_one::<bool>("verbose")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(Vec::new())),
+        cr_breaks: calls
+            .get_one::<String>("line-breaks")
+            .is_some_and(|mode| mode == "any"),
+        no_blank: *calls.get_one::<bool>("no-blank").unwrap_or(&false),
+        boms: calls
+            .get_one::<bool>("show-bom")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(Vec::new())),
+        stats: verify_sample.map(|_| Mutex::new(Vec::new())),
+        noise: Mutex::new(Vec::new()),
+        special: Mutex::new(Vec::new()),
+        ignored: calls
+            .get_one::<bool>("show-ignored")
+            .unwrap_or(&false)
+            .then(|| Mutex::new(Vec::new())),
+        relative_to: calls
+            .get_one::<String>("relative-to")
+            .map(fs::canonicalize)
+            .transpose()?,
+        throttle: adaptive.then(|| Throttle::new(jobs.unwrap())),
+        uring: calls
+            .get_one::<String>("io-backend")
+            .is_some_and(|backend| backend == "uring"),
+        cache: calls.get_one::<String>("cache").map(|path| {
+            let flag = |name| u8::from(*calls.get_one::<bool>(name).unwrap_or(&false));
+            let settings = format!(
+                "statements={} logical={} breakdown={} line_lengths={} long_lines={} indentation={} line_endings={} line_breaks={} no_blank={} hygiene={} words={} chars={} tokens={} todos={} empty={} dupes={} verbose={} languages={:x}",
+                flag("statements"),
+                flag("logical"),
+                u8::from(breakdown),
+                flag("line-lengths"),
+                calls.get_one::<u128>("long-lines").copied().unwrap_or(0),
+                flag("indentation"),
+                flag("line-endings"),
+                calls.get_one::<String>("line-breaks").unwrap(),
+                flag("no-blank"),
+                flag("hygiene"),
+                flag("words"),
+                flag("chars"),
+                flag("tokens"),
+                //the markers themselves, a different list counts differently.
+                match (flag("todos"), calls.get_many::<String>("todo-marker")) {
+                    (_, Some(markers)) => markers.cloned().collect::<Vec<_>>().join(","),
+                    (1, None) => "default".to_string(),
+                    _ => "0".to_string(),
+                },
+                flag("empty"),
+                //a copy left out with --dedupe is cached as one.
+                flag("dupes") + 2 * u8::from(dedupe),
+                flag("verbose"),
+                //the definitions from languages.toml, which decide how files are classified.
+                user_languages
+            );
+            Cache::load(Path::new(path), settings)
+        }),
+        follow_symlinks: *calls.get_one::<bool>("follow-symlinks").unwrap_or(&false),
+        one_file_system: *calls.get_one::<bool>("one-file-system").unwrap_or(&false),
+        submodules: calls
+            .get_one::<Submodules>("submodules")
+            .copied()
+            .unwrap_or_default(),
+        submodule_dirs: Mutex::new(Vec::new()),
+        count_links: *calls.get_one::<bool>("count-links").unwrap_or(&false),
+        linked_files: Mutex::new(HashSet::new()),
+        visited: Mutex::new(HashSet::new()),
+        dups: calls
+            .get_one::<bool>("dup-lines")
+            .unwrap_or(&false)
+            .then(|| {
+                DupIndex::new(
+                    *calls
+                        .get_one::<bool>("dup-ignore-whitespace")
+                        .unwrap_or(&false),
+                )
+            }),
+    });
+
+    let use_tokio = calls
+        .get_one::<String>("runtime")
+        .is_some_and(|runtime| runtime == "tokio");
+    if cfg!(not(feature = "tokio")) && use_tokio {
+        eprintln!(
+            "{} --runtime tokio needs lc to be built with the tokio feature",
+            "error:".red().bold()
+        );
+        std::process::exit(2);
+    }
+    if cfg!(not(feature = "uring")) && scan.uring {
+        eprintln!(
+            "{} --io-backend uring needs lc to be built with the uring feature",
+            "error:".red().bold()
+        );
+        std::process::exit(2);
+    }
+
+    //every parallel walk runs on rayon's global pool, so sizing it here bounds the whole run.
+    if let Some(jobs) = jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .map_err(std::io::Error::other)?;
+    }
+
+    if let Some(&max_mem) = calls.get_one::<usize>("max-mem") {
+        budget::limit_memory(max_mem);
+    }
+
+    scan.reset();
+    interrupt::install();
+    let show_timings = *calls.get_one::<bool>("timings").unwrap_or(&false);
+    if show_timings {
+        timings::enable();
+    }
+
+    if let Some(("why", why)) = calls.subcommand() {
+        let target = PathBuf::from(why.get_one::<String>("target").unwrap());
+        return explain_skip(&scan, &target);
+    }
+    if let Some(("diff", diff)) = calls.subcommand() {
+        let trees = diff
+            .get_many::<String>("trees")
+            .unwrap()
+            .collect::<Vec<_>>();
+        if *diff.get_one::<bool>("git").unwrap_or(&false) {
+            return diff::run_git(trees[0], trees[1], &scan).inspect_err(|err| {
+                eprintln!("{} {err}", "error:".red().bold());
+                std::process::exit(2);
+            });
+        }
+        return diff::run(Path::new(trees[0]), Path::new(trees[1]), &scan);
+    }
+    if let Some(("age", _)) = calls.subcommand() {
+        return age::run(&roots, &scan).inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        });
+    }
+    if let Some(("authors", _)) = calls.subcommand() {
+        return authors::run(&roots, &scan).inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        });
+    }
+    if let Some(("churn", churn)) = calls.subcommand() {
+        let since = *churn.get_one::<SystemTime>("since").unwrap();
+        return churn::run(&roots[0], since, &scan).inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        });
+    }
+    if let Some(("hook", hook)) = calls.subcommand()
+        && let Some(("pre-commit", pre_commit)) = hook.subcommand()
+    {
+        let max_lines = pre_commit.get_one::<u128>("max-lines").copied();
+        return hook::pre_commit(max_lines, &scan).inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        });
+    }
+    if let Some(("install-hook", install)) = calls.subcommand() {
+        let max_lines = install.get_one::<u128>("max-lines").copied();
+        let force = *install.get_one::<bool>("force").unwrap_or(&false);
+        return hook::install(install.get_one::<String>("hook").unwrap(), max_lines, force)
+            .inspect_err(|err| {
+                eprintln!("{} {err}", "error:".red().bold());
+                std::process::exit(2);
+            });
+    }
+    if let Some(("pr-summary", summary)) = calls.subcommand() {
+        let base = summary.get_one::<String>("base").unwrap();
+        return pr::run(base, &scan).inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        });
+    }
+    if let Some(("check", check)) = calls.subcommand() {
+        let base = check.get_one::<String>("base").unwrap();
+        match budget_file::check(base, &scan) {
+            Ok(true) => return Ok(()),
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                eprintln!("{} {err}", "error:".red().bold());
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(("history", history)) = calls.subcommand() {
+        let dir = history.get_one::<String>("dir").map(String::as_str);
+        let graph = *history.get_one::<bool>("graph").unwrap_or(&false);
+        return history::run(&roots, dir, graph);
+    }
+    if let Some(("snapshot", snapshot)) = calls.subcommand() {
+        let done = match snapshot.subcommand() {
+            Some(("save", save)) => {
+                snapshot::save(save.get_one::<String>("name").unwrap(), &roots[0], &scan)
+            }
+            Some(("diff", diff)) => {
+                let names = diff
+                    .get_many::<String>("names")
+                    .unwrap()
+                    .collect::<Vec<_>>();
+                snapshot::diff(names[0], names[1])
+            }
+            _ => unreachable!(),
+        };
+        return done.inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        });
+    }
+    if let Some(("branches", branches)) = calls.subcommand() {
+        let refs = branches
+            .get_many::<String>("refs")
+            .unwrap()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        return branches::run(&refs, &scan).inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        });
+    }
+    if let Some(("compare", compare)) = calls.subcommand() {
+        let files = compare
+            .get_many::<String>("files")
+            .unwrap()
+            .collect::<Vec<_>>();
+        return snapshot::compare(Path::new(files[0]), Path::new(files[1])).inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        });
+    }
+    if let Some(("bench", bench)) = calls.subcommand() {
+        let root = PathBuf::from(bench.get_one::<String>("target").unwrap());
+        return bench::run(&root, &scan, *bench.get_one::<usize>("iterations").unwrap());
+    }
+
+    let staged = *calls.get_one::<bool>("staged").unwrap_or(&false);
+    if staged || calls.contains_id("rev") {
+        let start_time = Instant::now();
+        let blobs = match calls.get_one::<String>("rev") {
+            Some(rev) => git::tree(rev),
+            None => git::index(),
+        };
+        let counts = blobs
+            .and_then(|blobs| linecount_blobs(blobs, &scan))
+            .unwrap_or_else(|err| {
+                eprintln!("{} {err}", "error:".red().bold());
+                std::process::exit(2);
+            });
+        format_and_print_results(counts, start_time.elapsed(), &scan);
+    } else if let Some(&fraction) = calls.get_one::<f64>("estimate") {
+        estimate_counts(&roots, &scan, fraction)?;
+    } else if *calls.get_one::<bool>("display").unwrap_or(&false) {
+        let stream = *calls.get_one::<bool>("stream").unwrap_or(&false);
+        let start_time = Instant::now();
+        let mut counts = Counts::default();
+        if scan.percent {
+            counts = linecount_display_shares(&roots, &scan, &mut io::stdout().lock())?;
+        } else {
+            for root in &roots {
+                let out = &mut io::stdout().lock();
+                counts += if stream {
+                    linecount_display_streamed(root, &scan, out)?
+                } else {
+                    linecount_display(root, &scan, out)?
+                };
+            }
+        }
+        let end_time = Instant::now();
+        format_and_print_results(counts, end_time - start_time, &scan);
+        if show_timings {
+            timings::print_report(end_time - start_time, rayon::current_num_threads());
+        }
+        print_throttle_limit(&scan);
+    } else {
+        let start_time = Instant::now();
+        let mut counts = Counts::default();
+        for root in &roots {
+            #[cfg(feature = "tokio")]
+            if use_tokio {
+                counts += tokio_count::linecount_tokio(root, Arc::clone(&scan), jobs)?;
+                continue;
+            }
+            counts += linecount_async(Some(root.clone()), Arc::clone(&scan))?;
+        }
+        let end_time = Instant::now();
+        format_and_print_results(counts, end_time - start_time, &scan);
+        if show_timings {
+            timings::print_report(end_time - start_time, rayon::current_num_threads());
+        }
+        print_throttle_limit(&scan);
+
+        if let Some(sample_size) = verify_sample {
+            let mismatches = verify_parallel(&scan, sample_size)?;
+            let sampled = scan.stats.as_ref().unwrap().lock().unwrap().len();
+            let checked = sample_size.min(sampled);
+
+            if mismatches.is_empty() {
+                println!(
+                    "{}",
+                    format!("verified {checked}/{checked} sampled files against the serial count")
+                        .green()
+                );
+            } else {
+                for (parallel, serial) in &mismatches {
+                    eprintln!(
+                        "{} {}: parallel ({}L, {}B) != serial ({}L, {}B)",
+                        "mismatch".red().bold(),
+                        scan.display_path(&parallel.path),
+                        parallel.counts.lines,
+                        parallel.counts.bytes,
+                        serial.counts.lines,
+                        serial.counts.bytes
+                    );
+                }
+                eprintln!(
+                    "{}/{checked} sampled files disagree with the serial count",
+                    mismatches.len()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(history) = &scan.history
+        && let Some(since) = history.since_last_run(&roots)
+    {
+        println!("{}", since.dimmed());
+    }
+    if let Some(languages) = &scan.languages {
+        print_language_report(&languages.lock().unwrap(), &scan);
+    }
+    if let Some(longest) = &scan.line_lengths {
+        print_line_length_report(&longest.lock().unwrap(), &scan);
+    }
+    if let Some(limit) = scan.long_line_limit {
+        print_long_line_report(limit, &mut scan.long_line_files.lock().unwrap(), &scan);
+    }
+    if let Some(mixed) = &scan.indentation {
+        print_mixed_indent_report(&mut mixed.lock().unwrap(), &scan);
+    }
+    if let Some(mixed) = &scan.line_endings {
+        print_mixed_ending_report(&mut mixed.lock().unwrap(), &scan);
+    }
+    if let Some(untidy) = &scan.hygiene {
+        print_hygiene_report(&mut untidy.lock().unwrap(), &scan);
+    }
+    if let Some(dupes) = &scan.dupes {
+        print_dupes_report(&dupes.sets(), &scan);
+    }
+    if let Some(empty) = &scan.empty {
+        print_empty_report(&mut empty.lock().unwrap(), &scan);
+    }
+    if scan.todos.is_some() {
+        print_todo_report(&mut scan.todo_files.lock().unwrap(), &scan);
+    }
+    if let Some(decoded) = &scan.verbose {
+        print_encoding_report(&mut decoded.lock().unwrap(), &scan);
+    }
+    if let Some(boms) = &scan.boms {
+        print_bom_report(&mut boms.lock().unwrap(), &scan);
+    }
+    if let Some(owners) = &scan.owners {
+        print_owner_report(&owners.totals(), owners.counted(), &scan);
+    }
+    if let Some(workspace) = &scan.workspace {
+        workspace.print_report(&scan);
+    }
+    if let Some(tests) = &scan.tests {
+        print_test_report(tests, &scan);
+    }
+    if let Some(&top) = calls.get_one::<usize>("hotspots") {
+        let since = *calls.get_one::<SystemTime>("hotspot-since").unwrap();
+        churn::print_hotspot_report(&roots, since, top, &scan).inspect_err(|err| {
+            eprintln!("{} {err}", "error:".red().bold());
+            std::process::exit(2);
+        })?;
+    }
+    if let Some(dups) = &scan.dups {
+        print_dup_report(&dups.report(), &scan);
+    }
+    if *calls.get_one::<bool>("noise").unwrap_or(&false) {
+        print_noise_report(&scan);
+    }
+    if let Some(ignored) = &scan.ignored {
+        print_ignored_report(&ignored.lock().unwrap(), &scan);
+    }
+
+    if scan.submodules == Submodules::Separate && !scan.submodule_dirs.lock().unwrap().is_empty() {
+        print_submodule_report(&scan)?;
+    }
+
+    if let Some(history) = &scan.history
+        && !interrupt::requested()
+    {
+        history.save(&roots)?;
+    }
+    if let Some(export) = &scan.export
+        && !interrupt::requested()
+    {
+        export.save(&scan)?;
+    }
+    if let Some(cache) = &scan.cache {
+        cache.save()?;
+    }
+
+    let submodules = scan.submodule_dirs.lock().unwrap().len();
+    if submodules > 0 && !calls.contains_id("submodules") {
+        eprintln!(
+            "{} {submodules} git submodules are counted in the totals (use --submodules skip or separate)",
+            "note:".yellow().bold()
+        );
+    }
+
+    let special = scan.special.lock().unwrap();
+    if !special.is_empty() {
+        eprintln!(
+            "{} skipped {} special files (fifos, sockets or devices), e.g. {}",
+            "note:".yellow().bold(),
+            special.len(),
+            scan.display_path(&special[0])
+        );
+    }
+
+    if interrupt::requested() {
+        let (files, dirs) = interrupt::progress();
+        eprintln!(
+            "{} interrupted, the totals only cover the {files} files in {dirs} directories counted so far",
+            "note:".yellow().bold()
+        );
+        std::process::exit(130);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filter::Filter;
+    use crate::{
+        BINARY_SNIFF_LEN, Content, ContentType, Counts, DirNode, FileStat, Scan, Skip, Submodules,
+        Visible, add_subtotals, collect_files, count_content, count_lines, count_lines_any,
+        is_binary, is_mount_point, linecount_async, submodule_totals,
+    };
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    const TEST_ITERATIONS: u128 = 1000;
+
+    #[test]
+    fn subtotals_add_up_subtrees() {
+        let node = |depth, files: &[(&str, u128)], dirs: &[&str]| DirNode {
+            depth,
+            files: files
+                .iter()
+                .map(|&(path, lines)| FileStat {
+                    path: PathBuf::from(path),
+                    counts: Counts {
+                        lines,
+                        ..Counts::default()
+                    },
+                })
+                .collect(),
+            dirs: dirs.iter().map(PathBuf::from).collect(),
+            subtotal: None,
+        };
+        let mut tree = HashMap::from([
+            (
+                PathBuf::from("r"),
+                node(0, &[("r/a", 1)], &["r/src", "r/docs"]),
+            ),
+            (
+                PathBuf::from("r/src"),
+                node(1, &[("r/src/b", 10)], &["r/src/x"]),
+            ),
+            (
+                PathBuf::from("r/src/x"),
+                node(2, &[("r/src/x/c", 100)], &[]),
+            ),
+            (PathBuf::from("r/docs"), node(1, &[], &[])),
+        ]);
+        add_subtotals(Path::new("r"), &mut tree);
+        let lines = |dir: &str| tree[Path::new(dir)].subtotal.unwrap().lines;
+        assert_eq!(lines("r"), 111);
+        assert_eq!(lines("r/src"), 110);
+        assert_eq!(lines("r/docs"), 0);
+    }
+
+    #[test]
+    fn hidden_names_by_their_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        assert!(Path::new("src/main.rs").is_visible());
+        assert!(!Path::new("src/.env").is_visible());
+        assert!(Path::new(".").is_visible());
+        assert!(Path::new(OsStr::from_bytes(b"bad\xff.rs")).is_visible());
+        assert!(!Path::new(OsStr::from_bytes(b".bad\xff")).is_visible());
+    }
+
+    #[test]
+    fn classify_content_types() {
+        assert_eq!(Path::new("README.md").content_type(), ContentType::CODE);
+        assert_eq!(Path::new("lib.jar").content_type(), ContentType::CODE);
+        assert_eq!(Path::new("notes.txt").content_type(), ContentType::TEXT);
+        assert_eq!(Path::new("Makefile").content_type(), ContentType::MAKEFILE);
+    }
+
+    #[test]
+    fn classifies_lockfiles_by_name() {
+        for name in [
+            "Cargo.lock",
+            "package-lock.json",
+            "yarn.lock",
+            "poetry.lock",
+        ] {
+            assert_eq!(Path::new(name).content_type(), ContentType::LOCKFILE);
+        }
+        assert_eq!(Path::new("package.json").content_type(), ContentType::CODE);
+    }
+
+    #[test]
+    fn tallies_lockfiles_on_the_side() {
+        let lockfile = Counts::of_file(Path::new("web/yarn.lock"), 20_000, 600_000, 0);
+        assert_eq!(lockfile.lines, 20_000);
+        assert_eq!(
+            (lockfile.lockfile_lines, lockfile.lockfile_bytes),
+            (20_000, 600_000)
+        );
+        let code = Counts::of_file(Path::new("src/lib.rs"), 10, 300, 0);
+        assert_eq!((code.lockfile_lines, code.lockfile_bytes), (0, 0));
+    }
+
+    #[test]
+    fn no_lockfiles_leaves_them_out() {
+        //--no-lockfiles adds lockfiles to the excluded types.
+        let filter = Filter {
+            exclude_types: vec![ContentType::LOCKFILE],
+            ..Filter::default()
+        };
+        assert_eq!(
+            filter.file_skip(Path::new("Cargo.lock")),
+            Some(Skip::Type(ContentType::LOCKFILE))
+        );
+        assert_eq!(filter.file_skip(Path::new("Cargo.toml")), None);
+    }
+
+    #[test]
+    fn reports_nested_submodules() {
+        let root = std::env::temp_dir().join(format!("lc-submodules-test-{}", std::process::id()));
+        for dir in ["a/.git", "a/b/.git"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["main.rs", "a/lib.rs", "a/b/mod.rs"] {
+            fs::write(root.join(file), "one\ntwo\n").unwrap();
+        }
+        let scan = Scan {
+            filter: Filter {
+                roots: vec![root.clone()],
+                ..Filter::default()
+            },
+            submodules: Submodules::Separate,
+            ..Scan::default()
+        };
+
+        let mut files = Vec::new();
+        collect_files(&root, &scan, &mut files).unwrap();
+        assert_eq!(files.len(), 1);
+        let totals = submodule_totals(&scan).unwrap();
+        let found = totals
+            .iter()
+            .map(|(dir, files, counts)| (dir.strip_prefix(&root).unwrap(), *files, counts.lines))
+            .collect::<Vec<_>>();
+        assert_eq!(found, [(Path::new("a"), 1, 2), (Path::new("a/b"), 1, 2)]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn code_only_leaves_out_everything_else() {
+        //--code-only is --type code.
+        let filter = Filter {
+            types: vec![ContentType::CODE],
+            ..Filter::default()
+        };
+        assert_eq!(filter.file_skip(Path::new("src/main.rs")), None);
+        assert_eq!(filter.file_skip(Path::new("README.md")), None);
+        for (path, content_type) in [
+            ("notes.txt", ContentType::TEXT),
+            ("logo.png", ContentType::MEDIA),
+            ("LICENSE", ContentType::LICENSE),
+            ("Cargo.lock", ContentType::LOCKFILE),
+        ] {
+            assert_eq!(
+                filter.file_skip(Path::new(path)),
+                Some(Skip::Type(content_type))
+            );
+        }
+    }
+
+    #[test]
+    fn sniffs_nul_bytes_as_binary() {
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\0\0\0"));
+        assert!(!is_binary(b"fn main() {}\n"));
+    }
+
+    #[test]
+    fn sniffs_only_the_head() {
+        let mut content = b"line\n".repeat(BINARY_SNIFF_LEN);
+        content.push(0);
+        assert!(!is_binary(&content));
+    }
+
+    #[test]
+    fn utf16_text_isnt_binary() {
+        assert!(!is_binary(b"\xff\xfef\0n\0 \0m\0a\0i\0n\0\n\0"));
+    }
+
+    #[test]
+    fn binary_files_count_bytes_only() {
+        let content = b"\0\x01\x02line\nline\n";
+        let counts = count_content(Path::new("lib.o"), content, &Scan::default()).counts;
+        assert_eq!(counts.lines, 0);
+        assert_eq!(counts.bytes, content.len() as u128);
+        assert_eq!(
+            (counts.binary_files, counts.binary_bytes),
+            (1, content.len() as u128)
+        );
+    }
+
+    //a directory inside the temporary directory, on the same filesystem even when /tmp is a
+    //mount of its own.
+    fn same_filesystem_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lc-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn mount_points_by_device() {
+        let dir = same_filesystem_dir("mount-point");
+        assert!(is_mount_point(Path::new("/proc")));
+        assert!(!is_mount_point(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn one_file_system_skips_mount_points() {
+        let scan = Scan {
+            one_file_system: true,
+            ignored: Some(Mutex::new(Vec::new())),
+            ..Scan::default()
+        };
+        let dir = same_filesystem_dir("one-file-system");
+        assert!(scan.skip_dir(Path::new("/proc")));
+        assert!(!scan.skip_dir(&dir));
+        assert_eq!(
+            scan.ignored.unwrap().into_inner().unwrap(),
+            [(PathBuf::from("/proc"), Skip::OtherFilesystem)]
+        );
+        assert!(!Scan::default().skip_dir(Path::new("/proc")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn long_lines_lists_offenders() {
+        let scan = Scan {
+            long_line_limit: Some(10),
+            ..Scan::default()
+        };
+        let long = count_content(Path::new("long.rs"), b"short\nfar longer than ten\n", &scan);
+        let short = count_content(Path::new("short.rs"), b"short\n", &scan);
+        assert_eq!(long.counts.long_lines, 1);
+        assert_eq!(long.counts.longest_line, 19);
+        scan.record(&long);
+        scan.record(&short);
+        assert_eq!(
+            scan.long_line_files.lock().unwrap().as_slice(),
+            std::slice::from_ref(&long)
+        );
+    }
+
+    #[test]
+    fn indentation_lists_mixed_files() {
+        let scan = Scan {
+            indentation: Some(Mutex::new(Vec::new())),
+            ..Scan::default()
+        };
+        let mixed = count_content(Path::new("mixed.py"), b"if a:\n\tb()\n    c()\n", &scan);
+        let tabs = count_content(Path::new("tabs.go"), b"{\n\tb()\n}\n", &scan);
+        assert_eq!(mixed.counts.mixed_indent_files, 1);
+        assert_eq!(tabs.counts.mixed_indent_files, 0);
+        scan.record(&mixed);
+        scan.record(&tabs);
+        assert_eq!(scan.indentation.unwrap().into_inner().unwrap(), [mixed]);
+    }
+
+    #[test]
+    fn line_endings_lists_mixed_files() {
+        let scan = Scan {
+            line_endings: Some(Mutex::new(Vec::new())),
+            ..Scan::default()
+        };
+        let mixed = count_content(Path::new("mixed.c"), b"a\r\nb\n", &scan);
+        let crlf = count_content(Path::new("crlf.c"), b"a\r\nb\r\n", &scan);
+        assert_eq!(mixed.counts.mixed_ending_files, 1);
+        assert_eq!(crlf.counts.mixed_ending_files, 0);
+        scan.record(&mixed);
+        scan.record(&crlf);
+        assert_eq!(scan.line_endings.unwrap().into_inner().unwrap(), [mixed]);
+    }
+
+    #[test]
+    fn line_breaks_any_ends_lines_at_a_lone_cr() {
+        let content = b"one\rtwo\r\nthree\r";
+        let any = Scan {
+            cr_breaks: true,
+            ..Scan::default()
+        };
+        assert_eq!(
+            count_content(Path::new("mac.txt"), content, &any)
+                .counts
+                .lines,
+            3
+        );
+        assert_eq!(
+            count_content(Path::new("mac.txt"), content, &Scan::default())
+                .counts
+                .lines,
+            2
+        );
+    }
+
+    #[test]
+    fn hygiene_lists_untidy_files() {
+        let scan = Scan {
+            hygiene: Some(Mutex::new(Vec::new())),
+            ..Scan::default()
+        };
+        let untidy = count_content(Path::new("untidy.rs"), b"let a = 1; \n", &scan);
+        let tidy = count_content(Path::new("tidy.rs"), b"let a = 1;\n", &scan);
+        assert_eq!(untidy.counts.trailing_whitespace, 1);
+        assert_eq!(tidy.counts.trailing_whitespace, 0);
+        scan.record(&untidy);
+        scan.record(&tidy);
+        assert_eq!(scan.hygiene.unwrap().into_inner().unwrap(), [untidy]);
+    }
+
+    #[test]
+    fn words_only_with_the_flag() {
+        let content = b"one two\nthree\n";
+        let words = Scan {
+            words: true,
+            ..Scan::default()
+        };
+        let counted = count_content(Path::new("notes.md"), content, &words).counts;
+        assert_eq!(counted.words, 3);
+        let mut totals = counted;
+        totals += counted;
+        assert_eq!(totals.words, 6);
+        assert_eq!(
+            count_content(Path::new("notes.md"), content, &Scan::default())
+                .counts
+                .words,
+            0
+        );
+    }
+
+    //what --empty lists once each of these is counted and recorded.
+    fn listed_as_empty(files: &[(&str, &[u8])]) -> Vec<PathBuf> {
+        let scan = Scan {
+            empty: Some(Mutex::new(Vec::new())),
+            ..Scan::default()
+        };
+        for (path, content) in files {
+            scan.record(&count_content(Path::new(path), content, &scan));
+        }
+        let listed = scan.empty.unwrap().into_inner().unwrap();
+        listed.into_iter().map(|file_stat| file_stat.path).collect()
+    }
+
+    #[test]
+    fn empty_lists_files_without_lines() {
+        assert_eq!(
+            listed_as_empty(&[("empty.rs", b""), ("main.rs", b"fn main() {}\n")]),
+            [PathBuf::from("empty.rs")]
+        );
+    }
+
+    #[test]
+    fn empty_lists_whitespace_only_files() {
+        assert_eq!(
+            listed_as_empty(&[("blank.py", b"  \n\t\n"), ("main.py", b"\n  pass\n")]),
+            [PathBuf::from("blank.py")]
+        );
+    }
+
+    #[test]
+    fn empty_leaves_out_binary_files() {
+        assert!(listed_as_empty(&[("zeroes.bin", b"\0\0\0\0")]).is_empty());
+    }
+
+    #[test]
+    fn no_blank_leaves_out_whitespace_lines() {
+        let content = b"a\n\n  \n\tb\n";
+        let no_blank = Scan {
+            no_blank: true,
+            ..Scan::default()
+        };
+        let counted = count_content(Path::new("a.rs"), content, &no_blank).counts;
+        assert_eq!(counted.lines, 2);
+        assert_eq!(counted.bytes, content.len() as u128);
+        assert_eq!(
+            count_content(Path::new("a.rs"), content, &Scan::default())
+                .counts
+                .lines,
+            4
+        );
+    }
+
+    #[test]
+    fn no_blank_splits_at_a_lone_cr_with_line_breaks_any() {
+        let scan = Scan {
+            no_blank: true,
+            cr_breaks: true,
+            ..Scan::default()
+        };
+        let counted = count_content(Path::new("mac.txt"), b"a\r\rb\r\n\r\n", &scan);
+        assert_eq!(counted.counts.lines, 2);
+    }
+
+    #[test]
+    fn counts_lines_from_bytes() {
+        assert_eq!(count_lines(b""), 0);
+        assert_eq!(count_lines(b"one"), 1);
+        assert_eq!(count_lines(b"one\r\ntwo\n\n"), 3);
+        assert_eq!(count_lines(b"caf\xe9\nna\xefve"), 2);
+        assert_eq!(count_lines(b"one\rtwo\r"), 1);
+        assert_eq!(count_lines_any(b"one\rtwo\r\nthree\r"), 3);
+    }
 
     #[test]
     fn get_average_execution_time() {
@@ -528,10 +4368,10 @@ mod tests {
 
         while iteration < TEST_ITERATIONS {
             let start_time = Instant::now();
-            let (_lines, bytes) = linecount_async(None).unwrap();
+            let counts = linecount_async(None, Arc::new(Scan::default())).unwrap();
             let end_time = Instant::now();
 
-            t_bytes += bytes;
+            t_bytes += counts.bytes;
             total_execution_time += (end_time - start_time).as_secs_f64();
             iteration += 1;
         }
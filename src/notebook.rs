@@ -0,0 +1,237 @@
+use std::path::Path;
+
+//the cells of a jupyter notebook, with the json around them gone. a notebook's outputs (images as
+//base64, whole dataframes) are most of the file, and none of it was written by anyone.
+#[derive(Debug, Default, PartialEq)]
+pub struct Notebook {
+    //the kernel's language, from metadata.kernelspec or metadata.language_info.
+    pub language: Option<String>,
+    //every code cell's source, one after another.
+    pub code: String,
+    //markdown and raw cells.
+    pub text: String,
+}
+
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "ipynb")
+}
+
+//None when it isn't a notebook after all, then the file is counted like any other.
+pub fn parse(content: &[u8]) -> Option<Notebook> {
+    let text = std::str::from_utf8(content).ok()?;
+    let mut parser = Parser { rest: text };
+    let root = parser.value()?;
+    if !parser.rest.trim_start().is_empty() {
+        return None;
+    }
+
+    let metadata = root.get("metadata");
+    let language = metadata
+        .and_then(|metadata| metadata.get("kernelspec")?.get("language"))
+        .or_else(|| metadata?.get("language_info")?.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let mut notebook = Notebook {
+        language,
+        ..Notebook::default()
+    };
+    let Value::Array(cells) = root.get("cells")? else {
+        return None;
+    };
+    for cell in cells {
+        //the source is either one string or a list of lines that keep their \n.
+        let source = match cell.get("source")? {
+            Value::String(source) => source.clone(),
+            Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+            _ => return None,
+        };
+        let into = match cell.get("cell_type")?.as_str()? {
+            "code" => &mut notebook.code,
+            _ => &mut notebook.text,
+        };
+        into.push_str(&source);
+        if !source.is_empty() && !source.ends_with('\n') {
+            into.push('\n');
+        }
+    }
+    Some(notebook)
+}
+
+enum Value {
+    Null,
+    Bool,
+    Number,
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+//just enough json for a notebook, numbers and booleans are checked but not kept.
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn eat(&mut self, token: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.rest = self.rest.trim_start();
+        match self.rest.chars().next()? {
+            '{' => {
+                self.eat("{");
+                let mut fields = Vec::new();
+                if self.eat("}") {
+                    return Some(Value::Object(fields));
+                }
+                loop {
+                    self.rest = self.rest.trim_start();
+                    let name = self.string()?;
+                    if !self.eat(":") {
+                        return None;
+                    }
+                    fields.push((name, self.value()?));
+                    if self.eat("}") {
+                        return Some(Value::Object(fields));
+                    }
+                    if !self.eat(",") {
+                        return None;
+                    }
+                }
+            }
+            '[' => {
+                self.eat("[");
+                let mut items = Vec::new();
+                if self.eat("]") {
+                    return Some(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    if self.eat("]") {
+                        return Some(Value::Array(items));
+                    }
+                    if !self.eat(",") {
+                        return None;
+                    }
+                }
+            }
+            '"' => self.string().map(Value::String),
+            _ if self.eat("null") => Some(Value::Null),
+            _ if self.eat("true") || self.eat("false") => Some(Value::Bool),
+            _ => {
+                let end = self
+                    .rest
+                    .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                    .unwrap_or(self.rest.len());
+                self.rest[..end].parse::<f64>().ok()?;
+                self.rest = &self.rest[end..];
+                Some(Value::Number)
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let mut chars = self.rest.strip_prefix('"')?.char_indices();
+        let mut string = String::new();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[idx + 2..];
+                    return Some(string);
+                }
+                '\\' => string.push(match chars.next()?.1 {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'u' => {
+                        let high = unit(&mut chars)?;
+                        //anything past the basic plane comes as a surrogate pair, 😀.
+                        let units = match high {
+                            0xD800..=0xDBFF => {
+                                if chars.next()?.1 != '\\' || chars.next()?.1 != 'u' {
+                                    return None;
+                                }
+                                vec![high, unit(&mut chars)?]
+                            }
+                            _ => vec![high],
+                        };
+                        char::decode_utf16(units)
+                            .next()?
+                            .unwrap_or(char::REPLACEMENT_CHARACTER)
+                    }
+                    c => c,
+                }),
+                c => string.push(c),
+            }
+        }
+        None
+    }
+}
+
+//the four hex digits after a \u.
+fn unit(chars: &mut std::str::CharIndices) -> Option<u16> {
+    let hex = (0..4)
+        .map(|_| chars.next().map(|(_, c)| c))
+        .collect::<Option<String>>()?;
+    u16::from_str_radix(&hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn keeps_just_the_cells() {
+        let notebook = parse(
+            r##"{
+ "cells": [
+  {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n", "Some \"prose\" é"]},
+  {"cell_type": "code", "execution_count": 1, "metadata": {}, "outputs": [
+    {"output_type": "stream", "name": "stdout", "text": ["1\n", "2\n"]}
+   ], "source": ["x = 1\n", "\n", "print(x)"]},
+  {"cell_type": "code", "execution_count": null, "metadata": {}, "outputs": [], "source": "y = 2"}
+ ],
+ "metadata": {"kernelspec": {"display_name": "Python 3", "language": "python", "name": "python3"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##
+            .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(notebook.language.as_deref(), Some("python"));
+        assert_eq!(notebook.code, "x = 1\n\nprint(x)\ny = 2\n");
+        assert_eq!(notebook.text, "# Title\nSome \"prose\" é\n");
+        assert!(parse(b"{\"cells\": [").is_none());
+        assert!(parse(b"not json").is_none());
+    }
+}
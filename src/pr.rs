@@ -0,0 +1,100 @@
+use crate::diff::{self, Delta, Snapshot};
+use crate::{Scan, git};
+use std::fmt::Write;
+use std::io::Result;
+use std::path::Path;
+
+//how many of the files that grew the most the summary lists.
+const GROWING_FILES: usize = 5;
+//how many new files are named before the rest are only counted.
+const NEW_FILES: usize = 10;
+
+//`lc pr-summary --base origin/main`: a markdown block for ci to post on a pull request, the change
+//in lines from where HEAD branched off the base.
+pub fn run(base: &str, scan: &Scan) -> Result<()> {
+    let base = git::merge_base(base, "HEAD")?;
+    let (old, new) = diff::count_changed(git::tree(&base)?, git::tree("HEAD")?, scan)?;
+    print!("{}", render(&old, &new));
+    Ok(())
+}
+
+fn render(old: &Snapshot, new: &Snapshot) -> String {
+    let files = diff::diff(old, new, |path| path.to_string_lossy().into_owned());
+    let total = files.values().fold(Delta::default(), |mut total, delta| {
+        total.add(*delta);
+        total
+    });
+
+    let mut out = String::from("### Line count\n\n");
+    let _ = writeln!(
+        out,
+        "**+{} / -{} lines** (net {:+}) across {} files",
+        total.added_lines,
+        total.removed_lines,
+        total.net_lines(),
+        files.len()
+    );
+
+    let mut growing = files
+        .iter()
+        .filter(|(_, delta)| delta.net_lines() > 0 && delta.added_files == 0)
+        .collect::<Vec<_>>();
+    growing.sort_by(|a, b| b.1.net_lines().cmp(&a.1.net_lines()).then(a.0.cmp(b.0)));
+    if !growing.is_empty() {
+        out.push_str("\n| Grew the most | Lines | Change |\n|---|---:|---:|\n");
+        for (path, delta) in growing.iter().take(GROWING_FILES) {
+            let lines = new.get(Path::new(path)).map_or(0, |counts| counts.lines);
+            let _ = writeln!(out, "| `{path}` | {lines} | {:+} |", delta.net_lines());
+        }
+    }
+
+    let added = files
+        .iter()
+        .filter(|(_, delta)| delta.added_files > 0)
+        .collect::<Vec<_>>();
+    if !added.is_empty() {
+        let mut named = added
+            .iter()
+            .take(NEW_FILES)
+            .map(|(path, delta)| format!("`{path}` ({} lines)", delta.added_lines))
+            .collect::<Vec<_>>();
+        if added.len() > NEW_FILES {
+            named.push(format!("and {} more", added.len() - NEW_FILES));
+        }
+        let _ = writeln!(out, "\n**New files:** {}", named.join(", "));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+    use crate::Counts;
+    use crate::diff::Snapshot;
+    use std::path::PathBuf;
+
+    #[test]
+    fn renders_markdown() {
+        let counts = |lines| Counts {
+            lines,
+            bytes: lines * 10,
+            ..Counts::default()
+        };
+        let old = Snapshot::from([
+            (PathBuf::from("src/main.rs"), counts(100)),
+            (PathBuf::from("src/old.rs"), counts(20)),
+        ]);
+        let new = Snapshot::from([
+            (PathBuf::from("src/main.rs"), counts(130)),
+            (PathBuf::from("src/new.rs"), counts(40)),
+        ]);
+        assert_eq!(
+            render(&old, &new),
+            "### Line count\n\n\
+             **+70 / -20 lines** (net +50) across 3 files\n\n\
+             | Grew the most | Lines | Change |\n|---|---:|---:|\n\
+             | `src/main.rs` | 130 | +30 |\n\n\
+             **New files:** `src/new.rs` (40 lines)\n"
+        );
+    }
+}
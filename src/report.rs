@@ -0,0 +1,149 @@
+use crate::cache::{self, NAMES};
+use crate::diff::Snapshot;
+use crate::{Counts, Scan};
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+//what a report says it is, so lc compare can tell one from a snapshot file.
+const FORMAT: &str = "lc-report";
+
+//--export FILE: the counts of every file the run counted, by path below its root, written out as
+//a json report once the run is done:
+//`{"format": "lc-report", "roots": [...], "totals": {...}, "files": {"src/main.rs": {...}}}`.
+//each set of counts is an object with every count by name, see cache::NAMES.
+pub struct Export {
+    path: PathBuf,
+    files: Mutex<Snapshot>,
+}
+
+impl Export {
+    pub fn new(path: PathBuf) -> Export {
+        Export {
+            path,
+            files: Mutex::new(Snapshot::new()),
+        }
+    }
+
+    pub fn record(&self, relative: &Path, counts: &Counts) {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(relative.to_path_buf(), *counts);
+    }
+
+    //the totals are the files' counts added up, so they match the files whichever way lc counted.
+    pub fn save(&self, scan: &Scan) -> Result<()> {
+        let roots = scan
+            .filter
+            .roots
+            .iter()
+            .map(|root| root.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        let files = self.files.lock().unwrap();
+        let mut totals = Counts::default();
+        for counts in files.values() {
+            totals += *counts;
+        }
+        let report = encode(&roots, totals, &files);
+        fs::write(&self.path, serde_json::to_string_pretty(&report)? + "\n")
+    }
+}
+
+fn encode(roots: &[String], totals: Counts, files: &Snapshot) -> Value {
+    let mut paths = files.keys().collect::<Vec<_>>();
+    paths.sort();
+    let files = paths
+        .into_iter()
+        .map(|path| {
+            let name = path.to_string_lossy().into_owned();
+            (name, encode_counts(&files[path]))
+        })
+        .collect::<Map<_, _>>();
+    json!({
+        "format": FORMAT,
+        "roots": roots,
+        "totals": encode_counts(&totals),
+        "files": files,
+    })
+}
+
+fn encode_counts(counts: &Counts) -> Value {
+    NAMES
+        .iter()
+        .zip(cache::encode(counts))
+        .map(|(name, count)| (name.to_string(), json!(count)))
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+//a report's files, or none if `content` isn't one. a count the report doesn't have is 0, so a
+//report from an older lc still reads.
+pub fn decode(content: &str) -> Option<Snapshot> {
+    let report = serde_json::from_str::<Value>(content).ok()?;
+    if report.get("format")? != FORMAT {
+        return None;
+    }
+    report
+        .get("files")?
+        .as_object()?
+        .iter()
+        .map(|(path, counts)| Some((PathBuf::from(path), decode_counts(counts)?)))
+        .collect()
+}
+
+fn decode_counts(counts: &Value) -> Option<Counts> {
+    let counts = counts.as_object()?;
+    let mut numbers = NAMES.iter().map(|name| {
+        counts
+            .get(*name)
+            .map_or(Some(0), |count| count.as_u64().map(u128::from))
+    });
+    cache::decode(&mut || numbers.next().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use crate::Counts;
+    use crate::diff::Snapshot;
+    use std::path::PathBuf;
+
+    #[test]
+    fn round_trips() {
+        let snapshot = Snapshot::from([
+            (
+                PathBuf::from("src/main.rs"),
+                Counts {
+                    lines: 120,
+                    bytes: 4096,
+                    code: 100,
+                    ..Counts::default()
+                },
+            ),
+            (PathBuf::from("docs/a b\tc.md"), Counts::default()),
+        ]);
+        let report = encode(&["/repo".to_string()], Counts::default(), &snapshot);
+        assert_eq!(decode(&report.to_string()), Some(snapshot));
+    }
+
+    #[test]
+    fn missing_counts_are_zero() {
+        let report = r#"{"format": "lc-report", "files": {"a.rs": {"lines": 3}}}"#;
+        let snapshot = decode(report).unwrap();
+        assert_eq!(snapshot[&PathBuf::from("a.rs")].lines, 3);
+        assert_eq!(snapshot[&PathBuf::from("a.rs")].bytes, 0);
+    }
+
+    #[test]
+    fn turns_down_other_json() {
+        assert_eq!(decode(r#"{"files": {}}"#), None);
+        assert_eq!(
+            decode(r#"{"format": "lc-report", "files": {"a.rs": {"lines": -1}}}"#),
+            None
+        );
+        assert_eq!(decode("lc-snapshot 37\n"), None);
+    }
+}
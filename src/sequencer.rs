@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+
+//puts results that finish in any order back into the order they're asked for in. workers push
+//each directory's result as soon as it's done, the reader takes them one path at a time and waits
+//for whichever one is next, so only the results that arrived ahead of their turn are ever held.
+pub struct Sequencer<T> {
+    state: Mutex<State<T>>,
+    arrived: Condvar,
+}
+
+struct State<T> {
+    pending: HashMap<PathBuf, T>,
+    finished: bool,
+}
+
+impl<T> Sequencer<T> {
+    pub fn new() -> Sequencer<T> {
+        Sequencer {
+            state: Mutex::new(State {
+                pending: HashMap::new(),
+                finished: false,
+            }),
+            arrived: Condvar::new(),
+        }
+    }
+
+    pub fn push(&self, path: PathBuf, result: T) {
+        self.state.lock().unwrap().pending.insert(path, result);
+        self.arrived.notify_all();
+    }
+
+    //nothing more is coming, anything still asked for after this never arrived (a directory that
+    //couldn't be listed).
+    pub fn finish(&self) {
+        self.state.lock().unwrap().finished = true;
+        self.arrived.notify_all();
+    }
+
+    pub fn take(&self, path: &Path) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(result) = state.pending.remove(path) {
+                return Some(result);
+            }
+            if state.finished {
+                return None;
+            }
+            state = self.arrived.wait(state).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sequencer;
+    use std::path::{Path, PathBuf};
+    use std::thread;
+
+    #[test]
+    fn hands_results_back_in_order() {
+        let sequencer = Sequencer::new();
+        let taken = thread::scope(|s| {
+            s.spawn(|| {
+                for name in ["c", "a", "b"] {
+                    sequencer.push(PathBuf::from(name), name.to_uppercase());
+                }
+                sequencer.finish();
+            });
+            ["a", "missing", "b", "c"].map(|name| sequencer.take(Path::new(name)))
+        });
+
+        assert_eq!(
+            taken,
+            [Some("A".into()), None, Some("B".into()), Some("C".into())]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::cache::{self, COUNTS};
+use crate::diff::{self, Snapshot};
+use crate::{Scan, report};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+//where snapshots are kept, next to the default --cache file.
+const DIR: &str = ".lc-snapshots";
+
+//the header says how many counts each line holds, so a snapshot taken before a count was added is
+//turned down instead of read wrong.
+fn header() -> String {
+    format!("lc-snapshot {COUNTS}")
+}
+
+fn location(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("'{name}' can't be a snapshot name, it's used as a file name"),
+        ));
+    }
+    Ok(Path::new(DIR).join(name))
+}
+
+//`lc snapshot save NAME`: every file's counts under `root`, by path below it, so two snapshots can
+//be compared exactly even after the tree they came from is gone.
+pub fn save(name: &str, root: &Path, scan: &Scan) -> Result<()> {
+    let snapshot = diff::count_tree(root, scan)?;
+    let path = location(name)?;
+    fs::create_dir_all(DIR)?;
+    fs::write(&path, encode(&snapshot))?;
+    println!("saved {} files to {}", snapshot.len(), path.display());
+    Ok(())
+}
+
+//`lc snapshot diff OLD NEW`: the same report as lc diff, between two saved snapshots.
+pub fn diff(old: &str, new: &str) -> Result<()> {
+    diff::print_reports(&load(old)?, &load(new)?);
+    Ok(())
+}
+
+//`lc compare OLD NEW`: two json reports lc --export wrote, or two snapshot files, wherever they
+//are, say one copied over from another machine or kept from a ci run. file by file and then the
+//same report as lc diff.
+pub fn compare(old: &Path, new: &Path) -> Result<()> {
+    let (old, new) = (read_any(old)?, read_any(new)?);
+    diff::print_files(&old, &new);
+    println!();
+    diff::print_reports(&old, &new);
+    Ok(())
+}
+
+fn load(name: &str) -> Result<Snapshot> {
+    read(&location(name)?)
+}
+
+//a json report or a snapshot file, whichever `path` holds.
+fn read_any(path: &Path) -> Result<Snapshot> {
+    let content = read_to_string(path)?;
+    report::decode(&content)
+        .or_else(|| decode(&content))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} is neither a report lc --export wrote nor a snapshot this version of lc saved",
+                    path.display()
+                ),
+            )
+        })
+}
+
+fn read_to_string(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .map_err(|err| Error::new(err.kind(), format!("can't read {}: {err}", path.display())))
+}
+
+fn read(path: &Path) -> Result<Snapshot> {
+    decode(&read_to_string(path)?).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} isn't a snapshot this version of lc saved, or is damaged",
+                path.display()
+            ),
+        )
+    })
+}
+
+//one line per file, the counts tab separated and the path last, like the cache. paths with a
+//newline in them are left out.
+fn encode(snapshot: &Snapshot) -> String {
+    let mut paths = snapshot.keys().collect::<Vec<_>>();
+    paths.sort();
+    let mut out = header();
+    for path in paths {
+        let Some(name) = path.to_str().filter(|name| !name.contains('\n')) else {
+            continue;
+        };
+        out.push('\n');
+        for field in cache::encode(&snapshot[path]) {
+            out.push_str(&format!("{field}\t"));
+        }
+        out.push_str(name);
+    }
+    out
+}
+
+fn decode(content: &str) -> Option<Snapshot> {
+    let mut lines = content.lines();
+    if lines.next()? != header() {
+        return None;
+    }
+    lines
+        .map(|line| {
+            let mut fields = line.splitn(COUNTS + 1, '\t');
+            let mut number = || fields.next()?.parse::<u128>().ok();
+            let counts = cache::decode(&mut number)?;
+            Some((PathBuf::from(fields.next()?), counts))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use crate::Counts;
+    use crate::diff::Snapshot;
+    use std::path::PathBuf;
+
+    #[test]
+    fn round_trips() {
+        let snapshot = Snapshot::from([
+            (
+                PathBuf::from("src/main.rs"),
+                Counts {
+                    lines: 120,
+                    bytes: 4096,
+                    code: 100,
+                    ..Counts::default()
+                },
+            ),
+            (PathBuf::from("docs/a b\tc.md"), Counts::default()),
+        ]);
+        assert_eq!(decode(&encode(&snapshot)), Some(snapshot));
+        assert_eq!(decode("lc-snapshot 1\n1\tx"), None);
+    }
+}
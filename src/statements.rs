@@ -0,0 +1,220 @@
+use std::path::Path;
+
+const C_FAMILY_EXTENSIONS: [&str; 16] = [
+    "c", "h", "cpp", "hpp", "cc", "cxx", "hh", "hxx", "cs", "java", "js", "jsx", "mjs", "cjs",
+    "ts", "tsx",
+];
+
+pub fn is_c_family(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| C_FAMILY_EXTENSIONS.contains(&ext))
+}
+
+//how a language is lexed for --logical. rust needs its own: a ' is usually a lifetime rather than
+//a character, strings can be raw and span lines, and block comments nest.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Syntax {
+    C,
+    Rust,
+}
+
+pub fn syntax(path: &Path) -> Option<Syntax> {
+    if is_c_family(path) {
+        return Some(Syntax::C);
+    }
+    path.extension()
+        .is_some_and(|ext| ext == "rs")
+        .then_some(Syntax::Rust)
+}
+
+enum State {
+    Code,
+    LineComment,
+    //how many block comments deep, only rust nests them.
+    BlockComment(usize),
+    Quoted(u8),
+    //a rust raw string, and how many #s close it.
+    RawString(usize),
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Tally {
+    statements: u128,
+    blocks: u128,
+}
+
+//counts semicolon-terminated statements. semicolons inside strings, character literals and
+//comments don't count, and neither do the ones inside parentheses, so `for (i = 0; i < n; i++)`
+//is one statement rather than three.
+pub fn count_statements(source: &[u8]) -> u128 {
+    tally(source, Syntax::C).statements
+}
+
+//--logical: statements plus the blocks opened with a {, the usual way logical lines are counted
+//for c-like languages. `if (x) {` is one logical line and so is `y();`, however they're spread
+//over physical ones.
+pub fn count_logical(source: &[u8], syntax: Syntax) -> u128 {
+    let tally = tally(source, syntax);
+    tally.statements + tally.blocks
+}
+
+fn tally(source: &[u8], syntax: Syntax) -> Tally {
+    let rust = syntax == Syntax::Rust;
+    let mut state = State::Code;
+    //parentheses (and brackets, for rust's [0; 4]) open right now, and what that was outside of
+    //each enclosing block. a closure passed as an argument still has statements in it.
+    let mut depth = 0usize;
+    let mut outer = Vec::new();
+    let mut tally = Tally::default();
+    let mut i = 0;
+
+    while i < source.len() {
+        let byte = source[i];
+        let next = source.get(i + 1).copied();
+
+        match state {
+            State::Code => match byte {
+                b'/' if next == Some(b'/') => {
+                    state = State::LineComment;
+                    i += 1;
+                }
+                b'/' if next == Some(b'*') => {
+                    state = State::BlockComment(1);
+                    i += 1;
+                }
+                b'\'' if rust => {
+                    //'a' and '\n' are characters, 'a on its own is a lifetime or a label.
+                    let len = source.get(i + 1).map_or(1, |&lead| utf8_len(lead));
+                    if next == Some(b'\\') {
+                        state = State::Quoted(byte);
+                    } else if source.get(i + 1 + len) == Some(&b'\'') {
+                        i += len + 1;
+                    }
+                }
+                b'r' if rust && !ident_before(source, i) => {
+                    let hashes = source[i + 1..]
+                        .iter()
+                        .take_while(|&&byte| byte == b'#')
+                        .count();
+                    if source.get(i + 1 + hashes) == Some(&b'"') {
+                        state = State::RawString(hashes);
+                        i += hashes + 1;
+                    }
+                }
+                b'"' | b'\'' => state = State::Quoted(byte),
+                b'`' if !rust => state = State::Quoted(byte),
+                b'(' => depth += 1,
+                b'[' if rust => depth += 1,
+                b')' => depth = depth.saturating_sub(1),
+                b']' if rust => depth = depth.saturating_sub(1),
+                b'{' => {
+                    tally.blocks += 1;
+                    outer.push(depth);
+                    depth = 0;
+                }
+                b'}' => depth = outer.pop().unwrap_or_default(),
+                b';' if depth == 0 => tally.statements += 1,
+                _ => {}
+            },
+            State::LineComment => {
+                if byte == b'\n' {
+                    state = State::Code;
+                }
+            }
+            State::BlockComment(nesting) => {
+                if byte == b'*' && next == Some(b'/') {
+                    state = match nesting {
+                        1 => State::Code,
+                        _ => State::BlockComment(nesting - 1),
+                    };
+                    i += 1;
+                } else if rust && byte == b'/' && next == Some(b'*') {
+                    state = State::BlockComment(nesting + 1);
+                    i += 1;
+                }
+            }
+            State::Quoted(quote) => {
+                if byte == b'\\' {
+                    i += 1;
+                } else if byte == quote || (byte == b'\n' && quote != b'`' && !rust) {
+                    //an unterminated string or char literal ends with its line
+                    state = State::Code;
+                }
+            }
+            State::RawString(hashes) => {
+                let closing = source[i + 1..]
+                    .iter()
+                    .take(hashes)
+                    .take_while(|&&byte| byte == b'#')
+                    .count();
+                if byte == b'"' && closing == hashes {
+                    state = State::Code;
+                    i += hashes;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    tally
+}
+
+//r"..", br".." and r#".."# start raw strings, but not the r at the end of `for` or `bar`.
+fn ident_before(source: &[u8], i: usize) -> bool {
+    let before = match i.checked_sub(1).map(|at| source[at]) {
+        Some(b'b') => i.checked_sub(2).map(|at| source[at]),
+        before => before,
+    };
+    before.is_some_and(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+}
+
+//how many bytes the utf-8 character starting with `lead` takes.
+fn utf8_len(lead: u8) -> usize {
+    match lead {
+        0xF0.. => 4,
+        0xE0.. => 3,
+        0xC0.. => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Syntax, count_logical, count_statements};
+
+    #[test]
+    fn ignores_strings_comments_and_for_headers() {
+        let source = br#"
+            int main() {
+                char *s = "a; b; \"c;\"";
+                char c = ';';
+                // not; counted;
+                /* neither;
+                   is; this; */
+                for (int i = 0; i < 3; i++) { s++; }
+                return 0;
+            }
+        "#;
+        assert_eq!(count_statements(source), 4);
+        assert_eq!(count_logical(source, Syntax::C), 6);
+    }
+
+    #[test]
+    fn lexes_rust() {
+        let source = r##"
+            fn longest<'a>(a: &'a str, b: &'a str) -> &'a str {
+                let quote = '"';
+                let raw = r#"not; "a"; statement"#;
+                /* nested /* comment; */ still; */
+                let zeros = [0; 4];
+                items.iter().for_each(|item| {
+                    println!("{item};");
+                });
+                'outer: loop { break 'outer; }
+                if a.len() > b.len() { a } else { b }
+            }
+        "##;
+        assert_eq!(count_logical(source.as_bytes(), Syntax::Rust), 11);
+    }
+}
@@ -0,0 +1,142 @@
+use glob::{MatchOptions, Pattern};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+//what counts as test code when no --test-pattern is given, matched against the path below the
+//root so a checkout that happens to live under /tests isn't all tests.
+const DEFAULT_PATTERNS: &[&str] = &[
+    "**/tests/**",
+    "**/test/**",
+    "**/benches/**",
+    "**/__tests__/**",
+    "**/spec/**",
+    "**/*_test.go",
+    "**/*_test.py",
+    "**/test_*.py",
+    "**/*_test.rs",
+    "**/*.spec.ts",
+    "**/*.test.ts",
+    "**/*.spec.tsx",
+    "**/*.test.tsx",
+    "**/*.spec.js",
+    "**/*.test.js",
+    "**/*Test.java",
+    "**/*Tests.cs",
+    "**/*_spec.rb",
+];
+
+const OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Split {
+    pub test_files: u128,
+    pub test_lines: u128,
+    pub prod_files: u128,
+    pub prod_lines: u128,
+}
+
+//--tests: lines of test and production code, per top level directory of each root (a crate in a
+//workspace, a package in a monorepo). files right in the root go under the root itself.
+pub struct TestSplit {
+    patterns: Vec<Pattern>,
+    dirs: Mutex<HashMap<PathBuf, Split>>,
+}
+
+impl TestSplit {
+    //patterns given on the command line replace the defaults rather than adding to them, so a
+    //default that's wrong for a project can be left out.
+    pub fn new(patterns: Vec<Pattern>) -> TestSplit {
+        let patterns = match patterns.is_empty() {
+            true => DEFAULT_PATTERNS
+                .iter()
+                .map(|pattern| Pattern::new(pattern).unwrap())
+                .collect(),
+            false => patterns,
+        };
+        TestSplit {
+            patterns,
+            dirs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn is_test(&self, relative: &Path) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches_path_with(relative, OPTIONS))
+    }
+
+    pub fn record(&self, roots: &[PathBuf], path: &Path, lines: u128) {
+        let Some((root, relative)) = roots
+            .iter()
+            .find_map(|root| Some((root, path.strip_prefix(root).ok()?)))
+        else {
+            return;
+        };
+        let dir = match relative.parent().and_then(|parent| parent.iter().next()) {
+            Some(top) => root.join(top),
+            None => root.clone(),
+        };
+
+        let test = self.is_test(relative);
+        let mut dirs = self.dirs.lock().unwrap();
+        let split = dirs.entry(dir).or_default();
+        if test {
+            split.test_files += 1;
+            split.test_lines += lines;
+        } else {
+            split.prod_files += 1;
+            split.prod_lines += lines;
+        }
+    }
+
+    pub fn dirs(&self) -> Vec<(PathBuf, Split)> {
+        let mut dirs = self.dirs.lock().unwrap().drain().collect::<Vec<_>>();
+        dirs.sort_by(|a, b| a.0.cmp(&b.0));
+        dirs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Split, TestSplit};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn splits_tests_per_directory() {
+        let split = TestSplit::new(Vec::new());
+        let roots = [PathBuf::from("repo")];
+        for (path, lines) in [
+            ("repo/core/src/lib.rs", 100),
+            ("repo/core/tests/it.rs", 40),
+            ("repo/web/app.spec.ts", 10),
+            ("repo/web/app.ts", 30),
+            ("repo/build.rs", 5),
+        ] {
+            split.record(&roots, Path::new(path), lines);
+        }
+
+        let dirs = split.dirs();
+        assert_eq!(
+            dirs.iter()
+                .map(|(dir, _)| dir.to_str().unwrap())
+                .collect::<Vec<_>>(),
+            ["repo", "repo/core", "repo/web"]
+        );
+        assert_eq!(
+            dirs[1].1,
+            Split {
+                test_files: 1,
+                test_lines: 40,
+                prod_files: 1,
+                prod_lines: 100,
+            }
+        );
+        assert_eq!((dirs[2].1.test_lines, dirs[2].1.prod_lines), (10, 30));
+        assert_eq!(dirs[0].1.prod_lines, 5);
+    }
+}
@@ -0,0 +1,76 @@
+use colored::Colorize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy)]
+pub enum Phase {
+    Walking,
+    Reading,
+    Counting,
+    Rendering,
+}
+
+const PHASES: [(Phase, &str); 4] = [
+    (Phase::Walking, "walking"),
+    (Phase::Reading, "reading"),
+    (Phase::Counting, "counting"),
+    (Phase::Rendering, "rendering"),
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+//nanoseconds spent in each phase, summed over every thread that spent them.
+static SPENT: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+
+//--timings. until this is called, time() just runs what it's given and nothing is measured.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn time<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+    let start_time = Instant::now();
+    let result = f();
+    add(phase, start_time.elapsed());
+    result
+}
+
+//for the spots that can't hand over a closure, like an .await.
+pub fn add(phase: Phase, spent: Duration) {
+    if ENABLED.load(Ordering::Relaxed) {
+        SPENT[phase as usize].fetch_add(spent.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+//the phases are summed over all threads, so next to the wall time they also tell how much of
+//the time the workers had they actually spent on something. mostly reading means the disk is
+//the bottleneck, workers idle most of the run means the work isn't being spread out well.
+pub fn print_report(wall: Duration, threads: usize) {
+    let spent = PHASES.map(|(phase, name)| {
+        let nanos = SPENT[phase as usize].load(Ordering::Relaxed);
+        (name, Duration::from_nanos(nanos))
+    });
+    let busy = spent.iter().map(|(_, spent)| *spent).sum::<Duration>();
+
+    println!("Timings (summed over {threads} threads, {wall:.2?} wall):");
+    for (name, spent) in spent {
+        println!(
+            "  {name:<10}{:>12}{:>8.1}%",
+            format!("{spent:.2?}"),
+            percent(spent, busy)
+        );
+    }
+    println!(
+        "{}",
+        format!(
+            "  workers busy {:.1}% of the time",
+            percent(busy, wall * threads as u32)
+        )
+        .dimmed()
+    );
+}
+
+fn percent(part: Duration, whole: Duration) -> f64 {
+    100.0 * part.as_secs_f64() / whole.as_secs_f64().max(f64::EPSILON)
+}
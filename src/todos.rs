@@ -0,0 +1,63 @@
+use regex::bytes::Regex;
+
+//what --todos looks for when no --todo-marker is given.
+const DEFAULT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+//--todos: markers left in for later. they're matched as whole words and case sensitively, so a
+//`todos` variable or a TODOS file isn't one.
+pub struct Todos {
+    pattern: Regex,
+}
+
+impl Todos {
+    //markers given on the command line replace the defaults, like --test-pattern.
+    pub fn new(markers: Vec<String>) -> Todos {
+        let markers = match markers.is_empty() {
+            true => DEFAULT_MARKERS
+                .iter()
+                .map(|marker| marker.to_string())
+                .collect(),
+            false => markers,
+        };
+        //a marker like @todo can't have a word boundary in front of its @.
+        let boundary = |c: Option<char>| match c {
+            Some(c) if c.is_alphanumeric() || c == '_' => r"\b",
+            _ => "",
+        };
+        let alternatives = markers
+            .iter()
+            .map(|marker| {
+                let (start, end) = (marker.chars().next(), marker.chars().last());
+                format!(
+                    "{}{}{}",
+                    boundary(start),
+                    regex::escape(marker),
+                    boundary(end)
+                )
+            })
+            .collect::<Vec<_>>();
+        Todos {
+            pattern: Regex::new(&alternatives.join("|")).unwrap(),
+        }
+    }
+
+    pub fn count(&self, content: &[u8]) -> u128 {
+        self.pattern.find_iter(content).count() as u128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Todos;
+
+    #[test]
+    fn counts_whole_markers() {
+        let todos = Todos::new(Vec::new());
+        assert_eq!(
+            todos.count(b"// TODO: one\n// FIXME(me) XXX\nlet todos = TODOS; // HACKY\n"),
+            3
+        );
+        let custom = Todos::new(vec!["@todo".into(), "NOTE".into()]);
+        assert_eq!(custom.count(b"/* @todo later */ // TODO NOTE\n"), 2);
+    }
+}
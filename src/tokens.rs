@@ -0,0 +1,56 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+//the pieces a gpt style tokenizer cuts text into before byte pair encoding ever runs, close to
+//cl100k's pattern (the regex crate has no lookahead, so trailing whitespace isn't split off the
+//run it's in): a word with the space or symbol in front of it, up to three digits, a run of
+//punctuation, or whitespace.
+static PIECE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?x)
+        '(?i:[sdmt]|ll|ve|re)
+      | [^\r\n\p{L}\p{N}]?\p{L}+
+      | \p{N}{1,3}
+      | \x20?[^\s\p{L}\p{N}]+[\r\n]*
+      | \s*[\r\n]+
+      | \s+
+        ",
+    )
+    .unwrap()
+});
+
+//--tokens: roughly what an llm tokenizer would make of the content, without shipping a 100k entry
+//vocabulary. every piece is at least one token; a long word gets split up by the merges about
+//every six letters, punctuation about every three, and outside ascii it's about a token a
+//character.
+pub fn estimate(content: &[u8]) -> u128 {
+    let text = String::from_utf8_lossy(content);
+    PIECE
+        .find_iter(&text)
+        .map(|piece| {
+            let piece = piece.as_str();
+            let ascii = piece.bytes().filter(u8::is_ascii).count();
+            let wide = piece.chars().count() - ascii;
+            let per_token = match piece.trim_start().bytes().next() {
+                Some(byte) if byte.is_ascii_alphanumeric() => 6,
+                Some(_) => 3,
+                //whitespace, a run of indentation is a single token.
+                None => ascii.max(1),
+            };
+            (ascii.div_ceil(per_token) + wide).max(1) as u128
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::estimate;
+
+    #[test]
+    fn estimates_tokens() {
+        assert_eq!(estimate(b""), 0);
+        assert_eq!(estimate(b"fn main() {\n    println!(\"hi\");\n}"), 12);
+        assert_eq!(estimate(" 日本語".as_bytes()), 4);
+        assert_eq!(estimate(b"12345"), 2);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::budget;
+use crate::interrupt;
+use crate::timings::{self, Phase};
+use crate::walk;
+use crate::{
+    Counts, FILE_CHANNEL_BOUND, FileStat, LineCounter, READ_CHUNK_LEN, Scan, is_opaque, notebook,
+};
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinSet;
+
+//how many files are being counted at once. tasks past this wait for a permit instead of piling up.
+const MAX_IN_FLIGHT: usize = 64;
+
+//--runtime tokio: directories are listed by the regular walker on a blocking thread, and every file
+//it turns up becomes a task on the runtime, never more than MAX_IN_FLIGHT of them at a time. the
+//number of threads is the runtime's (or -j), not one per directory or per file.
+pub fn linecount_tokio(root: &Path, scan: Arc<Scan>, jobs: Option<usize>) -> Result<Counts> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    if let Some(jobs) = jobs {
+        builder.worker_threads(jobs);
+    }
+    builder
+        .build()?
+        .block_on(count_tree(root.to_path_buf(), scan))
+}
+
+async fn count_tree(root: PathBuf, scan: Arc<Scan>) -> Result<Counts> {
+    let (sender, mut receiver) = mpsc::channel::<PathBuf>(FILE_CHANNEL_BOUND);
+    let walker = {
+        let scan = Arc::clone(&scan);
+        tokio::task::spawn_blocking(move || {
+            walk::walk(&root, &scan, |listing| {
+                for file in &listing.files {
+                    sender.blocking_send(file.clone()).map_err(Error::other)?;
+                }
+                Ok(())
+            })
+        })
+    };
+
+    let permits = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+    let mut tasks = JoinSet::new();
+    let mut total = Counts::default();
+    while let Some(path) = receiver.recv().await {
+        let permit = Arc::clone(&permits).acquire_owned().await.unwrap();
+        let task_scan = Arc::clone(&scan);
+        tasks.spawn(async move {
+            let file_stat = count_file(path, task_scan).await;
+            drop(permit);
+            file_stat
+        });
+
+        while let Some(done) = tasks.try_join_next() {
+            add_file_stat(&mut total, done, &scan);
+        }
+    }
+    while let Some(done) = tasks.join_next().await {
+        add_file_stat(&mut total, done, &scan);
+    }
+
+    walker.await.map_err(Error::other)??;
+    Ok(total)
+}
+
+//a file that can't be read is left out, same as with the thread pool.
+fn add_file_stat(
+    total: &mut Counts,
+    done: std::result::Result<Result<FileStat>, tokio::task::JoinError>,
+    scan: &Scan,
+) {
+    if let Ok(Ok(file_stat)) = done {
+        *total += file_stat.counts;
+        if let Some(stats) = &scan.stats {
+            stats.lock().unwrap().push(file_stat);
+        }
+    }
+}
+
+//plain line counting reads asynchronously. anything that needs more than that (dup detection,
+//statements, --breakdown, line lengths, the cache, notebooks) or isn't read at all goes through the regular counter on the blocking pool.
+async fn count_file(path: PathBuf, scan: Arc<Scan>) -> Result<FileStat> {
+    if scan.dups.is_some()
+        || scan.cache.is_some()
+        || scan.statements
+        || scan.logical
+        || scan.breakdown
+        || scan.reads_whole_files()
+        || is_opaque(&path)
+        || notebook::is_notebook(&path)
+    {
+        return tokio::task::spawn_blocking(move || scan.count(&path))
+            .await
+            .map_err(Error::other)?;
+    }
+
+    interrupt::check()?;
+    let read_start = Instant::now();
+    let mut file = File::open(&path).await?;
+    timings::add(Phase::Reading, read_start.elapsed());
+    //the wait for memory blocks, so the rest of this worker's tasks move to another thread meanwhile.
+    let _memory = tokio::task::block_in_place(|| budget::reserve_memory(READ_CHUNK_LEN));
+    let mut chunk = vec![0; READ_CHUNK_LEN];
+    let mut counter = LineCounter::default();
+    loop {
+        let read_start = Instant::now();
+        let read = file.read(&mut chunk).await?;
+        timings::add(Phase::Reading, read_start.elapsed());
+        if read == 0 || !timings::time(Phase::Counting, || counter.feed(&chunk[..read])) {
+            break;
+        }
+    }
+
+    let file_stat = FileStat {
+        counts: counter.finish(&path)?,
+        path,
+    };
+    scan.record(&file_stat);
+    Ok(file_stat)
+}
@@ -0,0 +1,160 @@
+use crate::budget;
+use crate::timings::{self, Phase};
+use crate::{FileStat, LineCounter, READ_CHUNK_LEN, fd};
+use io_uring::{IoUring, opcode, types};
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::io::{Error, Result};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+//how many files are in flight at once, and so how many buffers of READ_CHUNK_LEN each worker holds.
+const RING_ENTRIES: u32 = 64;
+
+thread_local! {
+    static RING: RefCell<Option<IoUring>> = const { RefCell::new(None) };
+}
+
+//a directory's files counted with a handful of syscalls instead of a few per file: they're all
+//opened in one submission, then read a chunk at a time, each round of reads going in as one
+//batch. the counting matches count_stream. an error here means the ring itself couldn't be set
+//up or used, a file that can't be opened or read only fails its own entry.
+pub fn count_files(paths: &[PathBuf]) -> Result<Vec<Result<FileStat>>> {
+    RING.with_borrow_mut(|ring| {
+        if ring.is_none() {
+            *ring = Some(IoUring::new(RING_ENTRIES)?);
+        }
+        let ring = ring.as_mut().unwrap();
+
+        let mut results = Vec::with_capacity(paths.len());
+        for batch in paths.chunks(RING_ENTRIES as usize) {
+            results.extend(count_batch(ring, batch)?);
+        }
+        Ok(results)
+    })
+}
+
+#[derive(Default)]
+struct Reading {
+    fd: Option<i32>,
+    error: Option<Error>,
+    done: bool,
+    counter: LineCounter,
+    chunk: Vec<u8>,
+}
+
+fn count_batch(ring: &mut IoUring, batch: &[PathBuf]) -> Result<Vec<Result<FileStat>>> {
+    let names = batch
+        .iter()
+        .map(|path| CString::new(path.as_os_str().as_bytes()).map_err(Error::other))
+        .collect::<Result<Vec<_>>>()?;
+    let mut files = batch.iter().map(|_| Reading::default()).collect::<Vec<_>>();
+    let _permit = fd::reserve(batch.len());
+    let _memory = budget::reserve_memory(batch.len() * READ_CHUNK_LEN);
+
+    let opens = names.iter().enumerate().map(|(idx, name)| {
+        opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), name.as_ptr())
+            .flags(libc::O_RDONLY | libc::O_CLOEXEC)
+            .build()
+            .user_data(idx as u64)
+    });
+    for (idx, result) in submit(ring, opens.collect())? {
+        if result < 0 {
+            files[idx].error = Some(Error::from_raw_os_error(-result));
+            files[idx].done = true;
+        } else {
+            files[idx].fd = Some(result);
+            files[idx].chunk = vec![0; READ_CHUNK_LEN];
+        }
+    }
+
+    loop {
+        let reads = files
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, file)| !file.done)
+            .map(|(idx, file)| {
+                opcode::Read::new(
+                    types::Fd(file.fd.unwrap()),
+                    file.chunk.as_mut_ptr(),
+                    READ_CHUNK_LEN as u32,
+                )
+                .offset(file.counter.bytes)
+                .build()
+                .user_data(idx as u64)
+            })
+            .collect::<Vec<_>>();
+        if reads.is_empty() {
+            break;
+        }
+
+        for (idx, result) in submit(ring, reads)? {
+            let file = &mut files[idx];
+            match result {
+                0 => file.done = true,
+                read if read < 0 => {
+                    file.error = Some(Error::from_raw_os_error(-read));
+                    file.done = true;
+                }
+                read => {
+                    let chunk = &file.chunk[..read as usize];
+                    file.done = !timings::time(Phase::Counting, || file.counter.feed(chunk));
+                }
+            }
+        }
+    }
+
+    let closes = files
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, file)| {
+            let fd = file.fd?;
+            Some(
+                opcode::Close::new(types::Fd(fd))
+                    .build()
+                    .user_data(idx as u64),
+            )
+        })
+        .collect();
+    submit(ring, closes)?;
+
+    Ok(batch
+        .iter()
+        .zip(files)
+        .map(|(path, file)| {
+            if let Some(error) = file.error {
+                return Err(error);
+            }
+            Ok(FileStat {
+                path: path.clone(),
+                counts: file.counter.finish(path)?,
+            })
+        })
+        .collect())
+}
+
+//pushes one round of entries, waits for all of them and hands back (index, result) pairs.
+fn submit(ring: &mut IoUring, entries: Vec<io_uring::squeue::Entry>) -> Result<Vec<(usize, i32)>> {
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    //the buffers and paths the entries point at live in count_batch until every completion for
+    //them has been collected below.
+    unsafe { ring.submission().push_multiple(&entries) }.map_err(Error::other)?;
+    timings::time(Phase::Reading, || ring.submit_and_wait(entries.len()))?;
+
+    let mut completed = Vec::with_capacity(entries.len());
+    while completed.len() < entries.len() {
+        completed.extend(
+            ring.completion()
+                .map(|cqe| (cqe.user_data() as usize, cqe.result())),
+        );
+        if completed.len() < entries.len() {
+            timings::time(Phase::Reading, || {
+                ring.submit_and_wait(entries.len() - completed.len())
+            })?;
+        }
+    }
+    Ok(completed)
+}
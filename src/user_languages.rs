@@ -0,0 +1,319 @@
+use crate::languages::{self, Language};
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+//in the directory lc is run from, next to .lc-cache.
+const PROJECT_FILE: &str = ".lc-languages.toml";
+
+//languages the classifier doesn't ship with, for in-house dsls and odd extensions:
+//
+//  [Gizmo]
+//  extensions = ["gz", "gzm"]
+//  file_names = ["Gizmofile"]
+//  line_comments = ["--"]
+//  block_comments = [["{-", "-}"]]
+//  doc_comments = ["--|"]
+//  doc_block_comments = [["{-|", "-}"]]
+//
+//a table named after a built in language takes whatever it leaves out from that one, so
+//`[Python]` with just `extensions = ["pyw"]` adds an extension. the project file wins over the
+//user one (~/.config/lc/languages.toml) and both win over the built in languages.
+//comes back with a hash of what was loaded, the cache keys on it since other definitions count
+//files differently.
+pub fn load() -> Result<u64, String> {
+    let mut user = Vec::new();
+    let mut hasher = DefaultHasher::new();
+    for path in [Some(PathBuf::from(PROJECT_FILE)), user_file()]
+        .into_iter()
+        .flatten()
+    {
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                text.hash(&mut hasher);
+                user.extend(parse(&text).map_err(|err| format!("{}: {err}", path.display()))?)
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => 0.hash(&mut hasher),
+            Err(err) => return Err(format!("{}: {err}", path.display())),
+        }
+    }
+    languages::add_user(user);
+    Ok(hasher.finish())
+}
+
+fn user_file() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(Path::new(&env::var_os("HOME")?).join(".config")))?;
+    Some(config.join("lc").join("languages.toml"))
+}
+
+//just the bit of toml the file needs: [tables], and keys set to strings or (nested) arrays of
+//them. arrays can run over several lines.
+fn parse(text: &str) -> Result<Vec<Language>, String> {
+    let mut languages: Vec<Table> = Vec::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((idx, line)) = lines.next() {
+        let line_no = idx + 1;
+        let mut line = strip_comment(line).trim().to_string();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            let name = name.trim().trim_matches('"');
+            if name.is_empty() {
+                return Err(format!("line {line_no}: a language needs a name"));
+            }
+            languages.push(Table {
+                name: name.to_string(),
+                ..Table::default()
+            });
+            continue;
+        }
+
+        let Some(table) = languages.last_mut() else {
+            return Err(format!("line {line_no}: expected a [language] first"));
+        };
+        let Some((key, _)) = line.split_once('=') else {
+            return Err(format!("line {line_no}: expected key = value"));
+        };
+        let key = key.trim().to_string();
+        while depth(&line) > 0 {
+            match lines.next() {
+                Some((_, more)) => {
+                    line.push(' ');
+                    line.push_str(strip_comment(more));
+                }
+                None => return Err(format!("line {line_no}: unclosed [")),
+            }
+        }
+        let (_, value) = line.split_once('=').unwrap();
+        let value = Value::parse_all(value).map_err(|err| format!("line {line_no}: {err}"))?;
+        table
+            .set(&key, value)
+            .map_err(|err| format!("line {line_no}: {err}"))?;
+    }
+    Ok(languages.into_iter().map(Table::into_language).collect())
+}
+
+#[derive(Default)]
+struct Table {
+    name: String,
+    extensions: Option<Vec<String>>,
+    file_names: Option<Vec<String>>,
+    line_comments: Option<Vec<String>>,
+    block_comments: Option<Vec<(String, String)>>,
+    doc_comments: Option<Vec<String>>,
+    doc_blocks: Option<Vec<(String, String)>>,
+}
+
+impl Table {
+    fn set(&mut self, key: &str, value: Value) -> Result<(), String> {
+        match key {
+            "extensions" => self.extensions = Some(value.strings(key)?),
+            "file_names" => self.file_names = Some(value.strings(key)?),
+            "line_comments" => self.line_comments = Some(value.strings(key)?),
+            "block_comments" => self.block_comments = Some(value.pairs(key)?),
+            "doc_comments" => self.doc_comments = Some(value.strings(key)?),
+            "doc_block_comments" => self.doc_blocks = Some(value.pairs(key)?),
+            _ => return Err(format!("unknown key '{key}'")),
+        }
+        Ok(())
+    }
+
+    //the strings live for the rest of the run like the built in ones, the file is only read once.
+    fn into_language(self) -> Language {
+        let builtin = languages::LANGUAGES
+            .iter()
+            .find(|lang| lang.name == self.name);
+        let strs = |list: Option<Vec<String>>,
+                    fallback: fn(&Language) -> &'static [&'static str]| {
+            match list {
+                Some(list) => &*Vec::leak(list.into_iter().map(|s| &*s.leak()).collect()),
+                None => builtin.map_or(&[][..], fallback),
+            }
+        };
+        let pairs =
+            |list: Option<Vec<(String, String)>>,
+             fallback: fn(&Language) -> &'static [(&'static str, &'static str)]| {
+                match list {
+                    Some(list) => &*Vec::leak(
+                        list.into_iter()
+                            .map(|(start, end)| (&*start.leak(), &*end.leak()))
+                            .collect(),
+                    ),
+                    None => builtin.map_or(&[][..], fallback),
+                }
+            };
+        Language {
+            extensions: strs(self.extensions, |lang| lang.extensions),
+            file_names: strs(self.file_names, |lang| lang.file_names),
+            line_comments: strs(self.line_comments, |lang| lang.line_comments),
+            block_comments: pairs(self.block_comments, |lang| lang.block_comments),
+            doc_comments: strs(self.doc_comments, |lang| lang.doc_comments),
+            doc_blocks: pairs(self.doc_blocks, |lang| lang.doc_blocks),
+            name: self.name.leak(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Value {
+    Str(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn parse_all(text: &str) -> Result<Value, String> {
+        let (value, rest) = Value::parse(text.trim_start())?;
+        if !rest.trim().is_empty() {
+            return Err(format!("unexpected '{}'", rest.trim()));
+        }
+        Ok(value)
+    }
+
+    fn parse(text: &str) -> Result<(Value, &str), String> {
+        let mut chars = text.char_indices();
+        match chars.next() {
+            Some((_, '[')) => {
+                let mut items = Vec::new();
+                let mut rest = text[1..].trim_start();
+                loop {
+                    if let Some(after) = rest.strip_prefix(']') {
+                        return Ok((Value::List(items), after));
+                    }
+                    let (item, after) = Value::parse(rest)?;
+                    items.push(item);
+                    rest = after.trim_start();
+                    if let Some(after) = rest.strip_prefix(',') {
+                        rest = after.trim_start();
+                    } else if !rest.starts_with(']') {
+                        return Err("expected , or ] in an array".into());
+                    }
+                }
+            }
+            //literal strings, nothing is escaped in them.
+            Some((_, '\'')) => match text[1..].find('\'') {
+                Some(end) => Ok((Value::Str(text[1..end + 1].into()), &text[end + 2..])),
+                None => Err("unclosed '".into()),
+            },
+            Some((_, '"')) => {
+                let mut string = String::new();
+                while let Some((idx, c)) = chars.next() {
+                    match c {
+                        '"' => return Ok((Value::Str(string), &text[idx + 1..])),
+                        '\\' => string.push(match chars.next() {
+                            Some((_, 'n')) => '\n',
+                            Some((_, 't')) => '\t',
+                            Some((_, c @ ('"' | '\\'))) => c,
+                            _ => return Err("unknown escape in a string".into()),
+                        }),
+                        c => string.push(c),
+                    }
+                }
+                Err("unclosed \"".into())
+            }
+            _ => Err("expected a string or an array".into()),
+        }
+    }
+
+    fn list(self, key: &str) -> Result<Vec<Value>, String> {
+        match self {
+            Value::List(items) => Ok(items),
+            Value::Str(_) => Err(format!("{key} should be an array")),
+        }
+    }
+
+    fn strings(self, key: &str) -> Result<Vec<String>, String> {
+        self.list(key)?
+            .into_iter()
+            .map(|item| match item {
+                Value::Str(string) => Ok(string),
+                Value::List(_) => Err(format!("{key} should be an array of strings")),
+            })
+            .collect()
+    }
+
+    fn pairs(self, key: &str) -> Result<Vec<(String, String)>, String> {
+        self.list(key)?
+            .into_iter()
+            .map(|pair| match <[String; 2]>::try_from(pair.strings(key)?) {
+                Ok([start, end]) => Ok((start, end)),
+                Err(_) => Err(format!("{key} should be pairs like [\"/*\", \"*/\"]")),
+            })
+            .collect()
+    }
+}
+
+//everything from a # that isn't inside a string.
+pub fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), c) if c == open && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..idx],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+//how many [ are still open, outside of strings.
+pub fn depth(line: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), c) if c == open && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn parses_languages() {
+        let languages = parse(
+            "# in-house\n[Gizmo]\nextensions = [\"gz\", 'gzm'] # both\nline_comments = [\"--\"]\nblock_comments = [\n  [\"{-\", \"-}\"],\n]\n\n[Python]\nextensions = [\"pyw\"]\n",
+        )
+        .unwrap();
+
+        assert_eq!(languages[0].name, "Gizmo");
+        assert_eq!(languages[0].extensions, ["gz", "gzm"]);
+        assert_eq!(languages[0].block_comments, [("{-", "-}")]);
+        assert!(languages[0].file_names.is_empty());
+        assert_eq!(languages[1].extensions, ["pyw"]);
+        assert_eq!(languages[1].line_comments, ["#"]);
+
+        assert!(parse("extensions = [\"x\"]").is_err());
+        assert!(parse("[X]\nextension = [\"x\"]").is_err());
+        assert!(parse("[X]\nextensions = [\"x\"").is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use crate::interrupt;
+use crate::timings::{self, Phase};
+use crate::{Scan, Skip, special_kind};
+use rayon::Scope;
+use rayon::prelude::*;
+use std::fs::{self, DirEntry};
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+//one directory's worth of a walk: the files to count and the subdirectories to go into, both
+//already filtered and sorted (prioritized entries first, then by path). depth is 0 for a root.
+pub struct Listing {
+    pub dir: PathBuf,
+    pub depth: usize,
+    pub files: Vec<PathBuf>,
+    pub dirs: Vec<PathBuf>,
+}
+
+//how many entries of a directory are read before they get classified. only the batch and the
+//entries that are kept are held, and with a parallel walk each batch is spread over the pool.
+const ENTRY_BATCH: usize = 1024;
+
+enum Entry {
+    File(PathBuf),
+    Dir(PathBuf),
+}
+
+pub fn list_dir(dir: &Path, depth: usize, scan: &Scan) -> Result<Listing> {
+    read_listing(dir, depth, scan, false)
+}
+
+//the same listing with the entries classified in parallel, so one huge flat directory isn't left
+//to a single worker.
+fn list_dir_parallel(dir: &Path, depth: usize, scan: &Scan) -> Result<Listing> {
+    read_listing(dir, depth, scan, true)
+}
+
+//each step is timed on its own rather than the whole listing, with --timings a worker that steals
+//counting work while it waits for the rest of a batch shouldn't put that down as walking.
+fn read_listing(dir: &Path, depth: usize, scan: &Scan, parallel: bool) -> Result<Listing> {
+    //let ignore_vec = fetch_gitignore(&dir_path)?;
+    interrupt::check()?;
+    let mut entries = timings::time(Phase::Walking, || fs::read_dir(dir))?;
+    let (mut files, mut dirs) = (Vec::new(), Vec::new());
+    let classify = |entry| timings::time(Phase::Walking, || classify(entry, scan));
+
+    //an entry that can't be read is left out and the rest of the directory is still listed.
+    loop {
+        let batch = timings::time(Phase::Walking, || {
+            entries
+                .by_ref()
+                .flatten()
+                .take(ENTRY_BATCH)
+                .collect::<Vec<_>>()
+        });
+        if batch.is_empty() {
+            break;
+        }
+
+        let kept = if parallel {
+            batch
+                .into_par_iter()
+                .filter_map(classify)
+                .collect::<Vec<_>>()
+        } else {
+            batch.into_iter().filter_map(classify).collect()
+        };
+        for entry in kept {
+            match entry {
+                Entry::File(path) => files.push(path),
+                Entry::Dir(path) => dirs.push(path),
+            }
+        }
+    }
+
+    //sorted before hard links and revisits are weeded out, so the same name or link wins every run.
+    //--prioritize then moves its entries to the front, the stable sort keeps them in path order.
+    //its key is worked out once per entry, it may stat.
+    timings::time(Phase::Walking, || {
+        files.sort();
+        dirs.sort();
+        if !scan.filter.prioritize.is_empty() {
+            files.sort_by_cached_key(|file| !scan.filter.is_prioritized(file));
+            dirs.sort_by_cached_key(|dir| !scan.filter.is_prioritized(dir));
+        }
+        files.retain(|file| !scan.skip_hard_link(file));
+        dirs.retain(|dir| !scan.skip_revisit(dir));
+    });
+
+    interrupt::record_dir();
+    Ok(Listing {
+        dir: dir.to_path_buf(),
+        depth,
+        files,
+        dirs,
+    })
+}
+
+//whether an entry gets counted, walked into or neither.
+fn classify(entry: DirEntry, scan: &Scan) -> Option<Entry> {
+    let filetype = scan.file_type(&entry).ok()?;
+    let path = entry.path();
+
+    if filetype.is_symlink() {
+        scan.record_skip(&path, Skip::Symlink);
+    } else if let Some(kind) = special_kind(&filetype) {
+        scan.skip_special(&path, kind);
+    } else if filetype.is_file() {
+        return (!scan.skip_file(&path)).then_some(Entry::File(path));
+    } else if filetype.is_dir() {
+        return (!scan.skip_dir(&path)).then_some(Entry::Dir(path));
+    }
+    None
+}
+
+//depth-first over an explicit stack instead of the call stack, so how deep a tree goes doesn't
+//matter. directories come out in the order the tree display prints them: a directory, then each
+//of its subdirectories in turn. a subdirectory that can't be read is left out, a root that can't
+//be read is an error.
+pub fn walk(root: &Path, scan: &Scan, mut visit: impl FnMut(&Listing) -> Result<()>) -> Result<()> {
+    let mut stack = vec![list_dir(root, 0, scan)?];
+
+    while let Some(listing) = stack.pop() {
+        visit(&listing)?;
+        for dir in listing.dirs.iter().rev() {
+            if let Ok(child) = list_dir(dir, listing.depth + 1, scan) {
+                stack.push(child);
+            }
+        }
+    }
+    Ok(())
+}
+
+//the same walk with every directory as its own task on rayon's work-stealing pool. all of them are
+//spawned into one scope rather than each waiting on its children, so the queue is the only thing
+//that grows with the tree and the order directories get visited in is up to the pool.
+pub fn walk_parallel(root: &Path, scan: &Scan, visit: &(impl Fn(&Listing) + Sync)) -> Result<()> {
+    let listing = list_dir_parallel(root, 0, scan)?;
+    rayon::scope(|s| spawn_children(s, listing, scan, visit));
+    Ok(())
+}
+
+fn spawn_children<'s>(
+    s: &Scope<'s>,
+    listing: Listing,
+    scan: &'s Scan,
+    visit: &'s (impl Fn(&Listing) + Sync),
+) {
+    for dir in &listing.dirs {
+        let (dir, depth) = (dir.clone(), listing.depth + 1);
+        s.spawn(move |s| {
+            if let Ok(child) = list_dir_parallel(&dir, depth, scan) {
+                spawn_children(s, child, scan, visit);
+            }
+        });
+    }
+    visit(&listing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::walk;
+    use crate::filter::Filter;
+    use crate::{Scan, Skip};
+    use glob::Pattern;
+    use std::ffi::{CString, OsStr};
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::ffi::OsStringExt;
+    use std::os::unix::fs::symlink;
+    use std::os::unix::net::UnixListener;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    fn tree(name: &str) -> PathBuf {
+        let root = empty_dir(name);
+        for dir in ["b/y", "b/x", "a", "docs", ".git"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "z.rs",
+            "b/y/2.rs",
+            "b/x/1.rs",
+            "a/0.rs",
+            "docs/guide.md",
+            "README.md",
+        ] {
+            fs::write(root.join(file), "line\n").unwrap();
+        }
+        fs::write(root.join(OsStr::from_bytes(b"m\xff.rs")), "line\n").unwrap();
+        root
+    }
+
+    fn empty_dir(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("lc-walk-{name}-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    //a scan of `root` that keeps track of what it leaves out.
+    fn scan(root: &Path) -> Scan {
+        Scan {
+            filter: Filter {
+                roots: vec![root.to_path_buf()],
+                ..Filter::default()
+            },
+            ignored: Some(Mutex::new(Vec::new())),
+            ..Scan::default()
+        }
+    }
+
+    fn dirs(listings: &[(PathBuf, Vec<PathBuf>)]) -> Vec<&Path> {
+        listings.iter().map(|(dir, _)| dir.as_path()).collect()
+    }
+
+    //what the walk left out and why, relative to the root and sorted by path.
+    fn skipped(root: &Path, scan: &Scan) -> Vec<(PathBuf, Skip)> {
+        let mut skipped = scan
+            .ignored
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, skip)| (path.strip_prefix(root).unwrap().to_path_buf(), skip.clone()))
+            .collect::<Vec<_>>();
+        skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        skipped
+    }
+
+    //every listing the walk visits, as the directory and its files relative to the root.
+    fn visited(root: &Path, scan: &Scan) -> Vec<(PathBuf, Vec<PathBuf>)> {
+        let relative = |path: &Path| path.strip_prefix(root).unwrap().to_path_buf();
+        let mut listings = Vec::new();
+        walk(root, scan, |listing| {
+            let files = listing.files.iter().map(|file| relative(file)).collect();
+            listings.push((relative(&listing.dir), files));
+            Ok(())
+        })
+        .unwrap();
+        listings
+    }
+
+    #[test]
+    fn walks_in_path_order() {
+        let root = tree("order");
+        let scan = Scan {
+            filter: Filter {
+                roots: vec![root.clone()],
+                ..Filter::default()
+            },
+            ..Scan::default()
+        };
+        let listings = visited(&root, &scan);
+        let dirs = listings.iter().map(|(dir, _)| dir).collect::<Vec<_>>();
+        assert_eq!(dirs, ["", "a", "b", "b/x", "b/y", "docs"].map(Path::new));
+        assert_eq!(
+            listings[0].1,
+            [
+                Path::new("README.md"),
+                Path::new(OsStr::from_bytes(b"m\xff.rs")),
+                Path::new("z.rs"),
+            ]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn prioritized_entries_first() {
+        let root = tree("prioritize");
+        let scan = Scan {
+            filter: Filter {
+                roots: vec![root.clone()],
+                prioritize: vec![
+                    Pattern::new("b/y/**").unwrap(),
+                    Pattern::new("z.rs").unwrap(),
+                ],
+                ..Filter::default()
+            },
+            ..Scan::default()
+        };
+        let listings = visited(&root, &scan);
+        let dirs = listings.iter().map(|(dir, _)| dir).collect::<Vec<_>>();
+        assert_eq!(dirs, ["", "b", "b/y", "b/x", "a", "docs"].map(Path::new));
+        assert_eq!(listings[0].1[0], Path::new("z.rs"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn linked_tree(name: &str) -> PathBuf {
+        let root = empty_dir(name);
+        fs::create_dir_all(root.join("dir")).unwrap();
+        fs::write(root.join("dir/a.rs"), "line\n").unwrap();
+        symlink("dir/a.rs", root.join("link.rs")).unwrap();
+        symlink("dir", root.join("link")).unwrap();
+        root
+    }
+
+    #[test]
+    fn skips_symlinks_by_default() {
+        let root = linked_tree("symlinks");
+        let scan = scan(&root);
+        let listings = visited(&root, &scan);
+        assert_eq!(dirs(&listings), ["", "dir"].map(Path::new));
+        assert!(listings[0].1.is_empty());
+        assert_eq!(
+            skipped(&root, &scan),
+            [
+                (PathBuf::from("link"), Skip::Symlink),
+                (PathBuf::from("link.rs"), Skip::Symlink)
+            ]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn follows_symlinks_to_what_they_point_at() {
+        let root = linked_tree("follow");
+        let scan = Scan {
+            follow_symlinks: true,
+            ..scan(&root)
+        };
+        scan.reset();
+        let listings = visited(&root, &scan);
+        assert_eq!(listings[0].1, [Path::new("link.rs")]);
+        //"link" is the same directory as "dir", which came first.
+        assert_eq!(dirs(&listings), ["", "dir"].map(Path::new));
+        assert_eq!(
+            skipped(&root, &scan),
+            [(PathBuf::from("link"), Skip::Revisited)]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn followed_symlinks_stop_at_loops() {
+        let root = empty_dir("loop");
+        fs::create_dir_all(root.join("dir")).unwrap();
+        symlink("..", root.join("dir/up")).unwrap();
+        let scan = Scan {
+            follow_symlinks: true,
+            ..scan(&root)
+        };
+        scan.reset();
+        let listings = visited(&root, &scan);
+        assert_eq!(dirs(&listings), ["", "dir"].map(Path::new));
+        assert_eq!(
+            skipped(&root, &scan),
+            [(PathBuf::from("dir/up"), Skip::Revisited)]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn hard_linked_tree(name: &str) -> PathBuf {
+        let root = empty_dir(name);
+        fs::write(root.join("a.rs"), "line\n").unwrap();
+        fs::hard_link(root.join("a.rs"), root.join("b.rs")).unwrap();
+        root
+    }
+
+    #[test]
+    fn counts_hard_links_once() {
+        let root = hard_linked_tree("hard-links");
+        let scan = scan(&root);
+        assert_eq!(visited(&root, &scan)[0].1, [Path::new("a.rs")]);
+        assert_eq!(
+            skipped(&root, &scan),
+            [(PathBuf::from("b.rs"), Skip::HardLink)]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn count_links_keeps_every_name() {
+        let root = hard_linked_tree("count-links");
+        let scan = Scan {
+            count_links: true,
+            ..scan(&root)
+        };
+        assert_eq!(
+            visited(&root, &scan)[0].1,
+            [Path::new("a.rs"), Path::new("b.rs")]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn skips_fifos_and_sockets() {
+        let root = empty_dir("special");
+        fs::write(root.join("a.rs"), "line\n").unwrap();
+        let fifo = CString::new(root.join("pipe").into_os_string().into_vec()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o644) }, 0);
+        let _socket = UnixListener::bind(root.join("sock")).unwrap();
+
+        let scan = scan(&root);
+        assert_eq!(visited(&root, &scan)[0].1, [Path::new("a.rs")]);
+        assert_eq!(
+            skipped(&root, &scan),
+            [
+                (PathBuf::from("pipe"), Skip::Special("fifo")),
+                (PathBuf::from("sock"), Skip::Special("socket"))
+            ]
+        );
+        assert_eq!(scan.special.lock().unwrap().len(), 2);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
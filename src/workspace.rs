@@ -0,0 +1,333 @@
+use crate::user_languages::{depth, strip_comment};
+use crate::{Counts, Scan, format_share};
+use colored::Colorize;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+//what a member's files are split into, by the directory right under the member.
+const SECTIONS: [&str; 5] = ["Src", "Tests", "Benches", "Examples", "Other"];
+
+//what files outside every member are listed under.
+const OUTSIDE: &str = "(workspace)";
+
+static QUOTED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""([^"]*)"|'([^']*)'"#).unwrap());
+static JSON_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""name"\s*:\s*"([^"]*)""#).unwrap());
+
+//a crate of the workspace, with its directory relative to the root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+//--workspace: counts per member and section.
+pub struct Workspace {
+    //the deepest directories first, so a member nested in another claims its own files.
+    members: Vec<Member>,
+    totals: Mutex<BTreeMap<(String, usize), Counts>>,
+}
+
+impl Workspace {
+    //the members of the workspace rooted at `root`, None when it isn't one.
+    pub fn detect(root: &Path) -> Option<Workspace> {
+        let mut members = cargo_members(root);
+        members.extend(js_members(root));
+        if members.is_empty() {
+            return None;
+        }
+        members.sort_by_key(|member| std::cmp::Reverse(member.dir.components().count()));
+        Some(Workspace {
+            members,
+            totals: Mutex::default(),
+        })
+    }
+
+    pub fn record(&self, relative: &Path, counts: &Counts) {
+        let (name, section) = match self
+            .members
+            .iter()
+            .find(|member| relative.starts_with(&member.dir))
+        {
+            Some(member) => (
+                member.name.clone(),
+                section(relative.strip_prefix(&member.dir).unwrap()),
+            ),
+            None => (OUTSIDE.to_string(), SECTIONS.len() - 1),
+        };
+        *self
+            .totals
+            .lock()
+            .unwrap()
+            .entry((name, section))
+            .or_default() += *counts;
+    }
+
+    pub fn print_report(&self, scan: &Scan) {
+        let mut rows: BTreeMap<&str, [u128; SECTIONS.len()]> = BTreeMap::new();
+        let totals = self.totals.lock().unwrap();
+        for ((name, section), counts) in totals.iter() {
+            rows.entry(name).or_default()[*section] += scan.metric.of(counts);
+        }
+
+        let mut header = format!("{:<28}", "Package");
+        for section in SECTIONS {
+            header.push_str(&format!("{section:>10}"));
+        }
+        header.push_str(&format!("{:>12}", format!("Total {}", scan.metric.name())));
+        if scan.percent {
+            header.push_str(&format!("{:>8}", "Share"));
+        }
+        println!("{}", header.bold());
+        let whole = rows.values().flatten().sum::<u128>();
+        let mut rows = rows.into_iter().collect::<Vec<_>>();
+        rows.sort_by(|a, b| {
+            let total = |row: &[u128]| row.iter().sum::<u128>();
+            total(&b.1).cmp(&total(&a.1)).then(a.0.cmp(b.0))
+        });
+        for (name, sections) in rows {
+            let mut row = format!("{name:<28}");
+            for measure in sections {
+                row.push_str(&format!("{measure:>10}"));
+            }
+            row.push_str(&format!("{:>12}", sections.iter().sum::<u128>()));
+            if scan.percent {
+                row.push_str(&format!(
+                    "{:>8}",
+                    format_share(sections.iter().sum(), whole)
+                ));
+            }
+            println!("{row}");
+        }
+    }
+}
+
+//src, tests, benches and examples are where cargo looks for them, everything else (build.rs, the
+//manifest, docs) is other. js tests sit next to what they test as *.test.ts or *.spec.js.
+fn section(within: &Path) -> usize {
+    let file_name = within.file_name().unwrap_or_default().to_string_lossy();
+    if file_name.contains(".test.") || file_name.contains(".spec.") {
+        return SECTIONS.iter().position(|&known| known == "Tests").unwrap();
+    }
+    let first = within.components().next();
+    let name = match within.components().nth(1) {
+        Some(_) => first.map(|first| first.as_os_str().to_string_lossy()),
+        None => None,
+    };
+    let section = match name.as_deref() {
+        Some("src" | "lib") => "Src",
+        Some("tests" | "test" | "__tests__") => "Tests",
+        Some("benches" | "bench" | "benchmarks") => "Benches",
+        Some("examples" | "example") => "Examples",
+        _ => "Other",
+    };
+    SECTIONS.iter().position(|&known| known == section).unwrap()
+}
+
+//the root package and everything `[workspace] members` names, globs and all.
+fn cargo_members(root: &Path) -> Vec<Member> {
+    let Ok(manifest) = fs::read_to_string(root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let name = |manifest: &str| toml_string(manifest, "package", "name");
+    let patterns = toml_strings(&manifest, "workspace", "members");
+    members(root, name(&manifest), &patterns, "Cargo.toml", name)
+}
+
+//npm and yarn list their workspaces in package.json, either as an array or under `packages`,
+//pnpm keeps them in pnpm-workspace.yaml.
+fn js_members(root: &Path) -> Vec<Member> {
+    let manifest = fs::read_to_string(root.join("package.json")).unwrap_or_default();
+    let mut patterns = json_strings(&manifest, "workspaces");
+    if let Ok(pnpm) = fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        patterns.extend(yaml_list(&pnpm, "packages"));
+    }
+    members(
+        root,
+        json_name(&manifest),
+        &patterns,
+        "package.json",
+        json_name,
+    )
+}
+
+//the root package, when it has a name, and the directories `patterns` match. a ! in front takes
+//matches back out, and a member is only taken when its own manifest is there to name it.
+fn members(
+    root: &Path,
+    root_name: Option<String>,
+    patterns: &[String],
+    manifest: &str,
+    name: impl Fn(&str) -> Option<String>,
+) -> Vec<Member> {
+    let mut members = Vec::new();
+    if let Some(name) = root_name {
+        members.push(Member {
+            name,
+            dir: PathBuf::new(),
+        });
+    }
+    //glob hands matches back without the leading ./ a root like . has.
+    let base = root
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect::<PathBuf>();
+    let excluded = patterns
+        .iter()
+        .filter_map(|pattern| glob::Pattern::new(pattern.strip_prefix('!')?).ok())
+        .collect::<Vec<_>>();
+    for pattern in patterns.iter().filter(|pattern| !pattern.starts_with('!')) {
+        let Ok(dirs) = glob::glob(&root.join(pattern).to_string_lossy()) else {
+            continue;
+        };
+        for dir in dirs.flatten() {
+            if let Ok(content) = fs::read_to_string(dir.join(manifest))
+                && let Some(name) = name(&content)
+                && let Ok(relative) = dir.strip_prefix(&base)
+                && !excluded
+                    .iter()
+                    .any(|pattern| pattern.matches_path(relative))
+                && !members.iter().any(|member: &Member| member.dir == relative)
+            {
+                members.push(Member {
+                    name,
+                    dir: relative.to_path_buf(),
+                });
+            }
+        }
+    }
+    members
+}
+
+//the raw value of `key` in `[table]`, arrays gathered up over as many lines as they take. just
+//enough toml for a manifest's names and member lists.
+fn toml_value(text: &str, table: &str, key: &str) -> Option<String> {
+    let mut current = String::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let line = strip_comment(line).trim();
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            current = name.trim().to_string();
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if current != table || name.trim() != key {
+            continue;
+        }
+        let mut value = value.trim().to_string();
+        while depth(&value) > 0 {
+            value.push(' ');
+            value.push_str(strip_comment(lines.next()?));
+        }
+        return Some(value);
+    }
+    None
+}
+
+fn toml_strings(text: &str, table: &str, key: &str) -> Vec<String> {
+    let value = toml_value(text, table, key).unwrap_or_default();
+    QUOTED
+        .captures_iter(&value)
+        .filter_map(|quoted| Some(quoted.get(1).or(quoted.get(2))?.as_str().to_string()))
+        .collect()
+}
+
+fn toml_string(text: &str, table: &str, key: &str) -> Option<String> {
+    toml_strings(text, table, key).into_iter().next()
+}
+
+//the strings in the array under `"key"`, or in its `packages` array when it's an object. the
+//first `"key"` in the file is taken to be the top-level one, which it is in any package.json.
+fn json_strings(text: &str, key: &str) -> Vec<String> {
+    let Some(at) = text.find(&format!("\"{key}\"")) else {
+        return Vec::new();
+    };
+    let value = text[at + key.len() + 2..].trim_start();
+    let Some(value) = value.strip_prefix(':').map(str::trim_start) else {
+        return Vec::new();
+    };
+    let value = match value.starts_with('{') {
+        true => {
+            let Some(at) = value.find("\"packages\"") else {
+                return Vec::new();
+            };
+            &value[at..]
+        }
+        false => value,
+    };
+    let Some(open) = value.find('[') else {
+        return Vec::new();
+    };
+    //up to the ] that closes it, one inside a string doesn't.
+    let array = value[open..]
+        .char_indices()
+        .filter(|&(_, c)| c == ']')
+        .map(|(at, _)| &value[open..=open + at])
+        .find(|array| depth(array) == 0)
+        .unwrap_or_default();
+    QUOTED
+        .captures_iter(array)
+        .filter_map(|quoted| Some(quoted.get(1)?.as_str().to_string()))
+        .collect()
+}
+
+//the package's `"name"`, the first in the file.
+fn json_name(text: &str) -> Option<String> {
+    JSON_NAME.captures(text).map(|name| name[1].to_string())
+}
+
+//the `- item` lines under `key:`, quotes and comments taken off.
+fn yaml_list(text: &str, key: &str) -> Vec<String> {
+    text.lines()
+        .skip_while(|line| line.trim_end() != format!("{key}:"))
+        .skip(1)
+        .map(|line| line.split_once(" #").map_or(line, |(item, _)| item).trim())
+        .take_while(|line| line.is_empty() || line.starts_with('-'))
+        .filter_map(|line| line.strip_prefix('-'))
+        .map(|item| item.trim().trim_matches(['"', '\'']).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_name, json_strings, section, toml_string, toml_strings, yaml_list};
+    use std::path::Path;
+
+    #[test]
+    fn reads_manifests_and_sections() {
+        let manifest = "[package]\nname = \"lc\" # the cli\n\n[workspace]\nmembers = [\n  \"crates/*\", # all of them\n  'tools/gen',\n]\n\n[dependencies]\nname = \"not this\"\n";
+        assert_eq!(
+            toml_string(manifest, "package", "name").as_deref(),
+            Some("lc")
+        );
+        assert_eq!(
+            toml_strings(manifest, "workspace", "members"),
+            ["crates/*", "tools/gen"]
+        );
+        assert_eq!(section(Path::new("src/main.rs")), 0);
+        assert_eq!(section(Path::new("tests/cli.rs")), 1);
+        assert_eq!(section(Path::new("build.rs")), 4);
+
+        let package = r#"{"name": "web", "workspaces": {"packages": ["packages/*", "apps/[ab]*"]}, "dependencies": {"name": "x"}}"#;
+        assert_eq!(json_name(package).as_deref(), Some("web"));
+        assert_eq!(
+            json_strings(package, "workspaces"),
+            ["packages/*", "apps/[ab]*"]
+        );
+        assert_eq!(
+            json_strings("{\n  \"workspaces\": [\"a\",\n \"b\"]\n}", "workspaces"),
+            ["a", "b"]
+        );
+        let pnpm = "packages:\n  - 'packages/*' # libs\n  - \"!**/test/**\"\n\ncatalog:\n  - x\n";
+        assert_eq!(yaml_list(pnpm, "packages"), ["packages/*", "!**/test/**"]);
+        assert_eq!(section(Path::new("src/app.test.tsx")), 1);
+    }
+}